@@ -1,17 +1,78 @@
 #![allow(unused_macros)]
 
 use halo2_proofs::arithmetic::CurveExt;
-use pasta_curves::{arithmetic::CurveAffine, group::ff::PrimeField, group::Curve, pallas};
+use pasta_curves::{
+    arithmetic::CurveAffine,
+    group::ff::{Field, PrimeField},
+    group::Curve,
+    pallas,
+};
 
 use halo2_gadgets::{
     ecc::chip::{find_zs_and_us, H},
     primitives::sinsemilla::{CommitDomain, HashDomain},
 };
 
+use lazy_static::lazy_static;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
 use super::types::*;
 
+// the max number of distinct strings interned by string_to_static_str
+pub const MAX_INTERNED_STRS: usize = 4096;
+
+lazy_static! {
+    static ref INTERNED_STRS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+    static ref COMPACT_NAMES: Mutex<HashMap<String, &'static str>> = Mutex::new(HashMap::new());
+}
+
+// the strings are interned, an identical string is leaked only once, so the leak is bounded by
+// the distinct names (of gates, regions...) configured. Beyond MAX_INTERNED_STRS distinct
+// strings, the new ones are leaked without being interned.
 pub fn string_to_static_str(s: &String) -> &'static str {
-    Box::leak(s.clone().into_boxed_str())
+    let mut interned = INTERNED_STRS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(v) = interned.get(s.as_str()) {
+        return v;
+    }
+
+    let v: &'static str = Box::leak(s.clone().into_boxed_str());
+    if interned.len() < MAX_INTERNED_STRS {
+        interned.insert(v);
+    } else {
+        println!(
+            "!string_to_static_str: more than {} distinct strings, [{}] is not interned",
+            MAX_INTERNED_STRS, s
+        );
+    }
+    v
+}
+
+// a borrowed name is static already, only an owned one is interned
+pub fn cow_to_static_str(s: &Cow<'static, str>) -> &'static str {
+    match s {
+        Cow::Borrowed(s) => s,
+        Cow::Owned(s) => string_to_static_str(s),
+    }
+}
+
+// the name of a region (or an assigned cell) in synthesize. With the compact-names feature (and
+// without debug-info) a description is named by its number in the order first seen, the names of
+// the gates and constraints are kept since the keys depend on them
+pub fn region_name(desc: &String) -> &'static str {
+    if cfg!(all(feature = "compact-names", not(feature = "debug-info"))) {
+        let mut names = COMPACT_NAMES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(v) = names.get(desc) {
+            return v;
+        }
+
+        let v = string_to_static_str(&format!("r{}", names.len()));
+        names.insert(desc.clone(), v);
+        v
+    } else {
+        string_to_static_str(desc)
+    }
 }
 
 macro_rules! assert_error {
@@ -164,6 +225,22 @@ pub fn generate_zs_us(base: pallas::Affine, num_windows: usize) -> TZsUs {
     convert_zs_us(find_zs_and_us(base, num_windows).unwrap())
 }
 
+// spot check that the zs and us are computed from the base point, for the first window:
+// u_k^2 = y_k + z, where y_k is the y-coordinate of [k + 2] base
+pub fn check_zs_and_us(base: &pallas::Affine, zs: &TZs, us: &TUs) -> bool {
+    if zs.len() == 0 || zs.len() != us.len() {
+        return false;
+    }
+
+    let z = pallas::Base::from(zs[0]);
+    (0..H).all(|k| {
+        let point = (*base * pallas::Scalar::from(k as u64 + 2)).to_affine();
+        let y = *point.coordinates().unwrap().y();
+        let u = pallas::Base::from_repr(us[0][k]);
+        bool::from(u.is_some()) && u.unwrap().square() == y + z
+    })
+}
+
 pub fn generate_q_hash_domain(domain: &str) -> TGenerator {
     affine_to_bytes(&generator_q_hash_domain(domain))
 }
@@ -176,10 +253,16 @@ pub fn generate_r(domain: &str) -> TGenerator {
     affine_to_bytes(&generator_r(domain))
 }
 
+// a generator by hash_to_curve(domain)(tag), e.g. K of a nullifier (tag "K") or V and R of a
+// value commitment ("v" and "r"). The generators of the sinsemilla domains are not of this form:
+// Q = hash_to_curve("z.cash:SinsemillaQ")(domain) (of domain + "-M" for a commit domain) and R of
+// a commit domain = hash_to_curve(domain + "-r")(&[]), see Q_CommitDomain and R
+pub fn domain_generator(domain: &str, tag: &[u8]) -> pallas::Point {
+    pallas::Point::hash_to_curve(domain)(tag)
+}
+
 pub fn generate_hash_point(domain: &str, t: &[u8]) -> TGenerator {
-    let hasher = pallas::Point::hash_to_curve(domain);
-    let point = hasher(t);
-    affine_to_bytes(&point.to_affine())
+    affine_to_bytes(&domain_generator(domain, t).to_affine())
 }
 
 pub fn point_to_affine(p: &TGenerator) -> pallas::Affine {
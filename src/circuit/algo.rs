@@ -1,4 +1,5 @@
-use pasta_curves::{pallas, EpAffine};
+use group::{ff::Field, Curve};
+use pasta_curves::{arithmetic::CurveAffine, pallas, EpAffine};
 
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter},
@@ -18,15 +19,27 @@ use std::collections::BTreeMap;
 
 use super::base::*;
 use super::synthesize::*;
+use crate::base;
+use crate::consts::{ATTRIBUTE_COMPUTED, SIGN_OF_MEMO};
 use crate::domains::*;
+use crate::primitives::utils::mod_r_p;
 use crate::types::*;
 
 pub(crate) type ICContext<'a> = (
     &'a mut Option<BTreeMap<String, Vec<String>>>,
-    &'a PoseidonConfig<pallas::Base, 3, 2>,
+    &'a BTreeMap<String, PoseidonConfig<pallas::Base, 3, 2>>,
     &'a BTreeMap<String, Option<pallas::Point>>,
 );
 
+// the spec name of a poseidon operator, "" for "poseidon" and <name> for "poseidon:<name>"
+pub(crate) fn poseidon_spec_name(operator: &str) -> Option<&str> {
+    if operator == "poseidon" {
+        Some("")
+    } else {
+        operator.strip_prefix("poseidon:")
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum ScalarResult {
     None,
@@ -35,7 +48,9 @@ pub(crate) enum ScalarResult {
     ScalarFixedPointShort(ScalarFixedShort<EpAffine, ecc::chip::EccChip<DomainFixedBases>>),
 }
 
-#[derive(Clone, Debug)]
+// with debug-info, Debug shows the values of the fields, the cells and the points
+#[derive(Clone)]
+#[cfg_attr(not(feature = "debug-info"), derive(Debug))]
 pub(crate) enum Operand {
     Field(Option<pallas::Base>),
     Point(Option<ecc::Point<EpAffine, ecc::chip::EccChip<DomainFixedBases>>>),
@@ -54,6 +69,46 @@ pub(crate) enum Operand {
     ShortField(DomainShort),
 }
 
+#[cfg(feature = "debug-info")]
+impl std::fmt::Debug for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |v: Option<&pallas::Base>| match v {
+            Some(v) => format!("{:?}", v),
+            None => "None".to_string(),
+        };
+        let point = |x: Option<&pallas::Base>, y: Option<&pallas::Base>| match (x, y) {
+            (Some(x), Some(y)) => format!("({:?}, {:?})", x, y),
+            _ => "None".to_string(),
+        };
+
+        match self {
+            Self::Field(v) => write!(f, "Field({})", value(v.as_ref())),
+            Self::Point(Some(p)) => write!(
+                f,
+                "Point{}",
+                point(p.inner().x().value(), p.inner().y().value())
+            ),
+            Self::NIPoint(Some(p)) => write!(
+                f,
+                "NIPoint{}",
+                point(p.inner().x().value(), p.inner().y().value())
+            ),
+            Self::Scalar(Some(v)) => write!(f, "Scalar({:?})", v),
+            Self::MagnitudeSign(Some((magnitude, sign))) => write!(
+                f,
+                "MagnitudeSign({}, {})",
+                value(magnitude.value()),
+                value(sign.value())
+            ),
+            Self::Cell(Some(cell)) => write!(f, "Cell({:?}, {})", cell.cell(), value(cell.value())),
+            Self::FullField(v) => write!(f, "FullField({:?})", v),
+            Self::BaseField(v) => write!(f, "BaseField({:?})", v),
+            Self::ShortField(v) => write!(f, "ShortField({:?})", v),
+            _ => write!(f, "{}(None)", self.to_type_string()),
+        }
+    }
+}
+
 impl Operand {
     pub fn to_type_string(&self) -> String {
         match self {
@@ -70,6 +125,20 @@ impl Operand {
     }
 }
 
+// binds the operand to name in values, the memos of the points computed of the operand previously
+// bound to name are dropped, otherwise a later compute of the name would reuse the point of the
+// other operand
+pub(crate) fn bind_operand(
+    values: &mut BTreeMap<String, Operand>,
+    name: &String,
+    operand: Operand,
+) {
+    let key = format!("[{}]", name);
+    values
+        .retain(|memo_name, _| !(memo_name.starts_with(SIGN_OF_MEMO) && memo_name.contains(&key)));
+    values.insert(name.clone(), operand);
+}
+
 impl AlgoItem {
     fn do_point_compute(
         layouter: &mut impl Layouter<pallas::Base>,
@@ -106,7 +175,8 @@ impl AlgoItem {
                                 }
                             };
 
-                        let ret = operand1.add(layouter.namespace(|| &desc), &operand2)?;
+                        let ret = operand1
+                            .add(layouter.namespace(|| base::region_name(&desc)), &operand2)?;
                         Ok((Operand::Point(Some(ret)), ScalarResult::None))
                     }
                     "NIPoint" => {
@@ -128,7 +198,8 @@ impl AlgoItem {
                                 }
                             };
 
-                        let ret = operand1.add(layouter.namespace(|| &desc), &operand2)?;
+                        let ret = operand1
+                            .add(layouter.namespace(|| base::region_name(&desc)), &operand2)?;
                         Ok((Operand::Point(Some(ret)), ScalarResult::None))
                     }
                     "Cell" | "CommitCell" => {
@@ -203,7 +274,39 @@ impl AlgoItem {
                             }
                         };
 
-                        let (point, var) = operand1.mul(layouter.namespace(|| &desc), &operand2)?;
+                        // the cell is the scalar as an integer (ScalarVar::from_base), since
+                        // p < q it is [mod_r_p(x)] P as computed off-circuit, e.g. [ivk] g_d
+                        let expected = operand1
+                            .inner()
+                            .x()
+                            .value()
+                            .zip(operand1.inner().y().value())
+                            .zip(operand2.value())
+                            .map(|((x, y), v)| {
+                                (pallas::Affine::from_xy(*x, *y).unwrap() * mod_r_p(*v))
+                                    .to_affine()
+                                    .coordinates()
+                                    .map(|p| (*p.x(), *p.y()))
+                                    .unwrap_or((pallas::Base::zero(), pallas::Base::zero()))
+                            });
+
+                        let (point, var) = operand1
+                            .mul(layouter.namespace(|| base::region_name(&desc)), &operand2)?;
+
+                        let result = point
+                            .inner()
+                            .x()
+                            .value()
+                            .zip(point.inner().y().value())
+                            .map(|(x, y)| (*x, *y));
+                        assert_synthesize_error!(
+                            expected.is_none() || result == expected,
+                            &format!(
+                                "[mul-nipoint]: [{}] differs from [mod_r_p({})] {}",
+                                desc, operand2_info.0, operand1_info.0
+                            )
+                        );
+
                         Ok((
                             Operand::Point(Some(point)),
                             ScalarResult::ScalarVarNIPoint(var),
@@ -225,7 +328,8 @@ impl AlgoItem {
                             }
                         };
 
-                        let (ret, scalar) = operand1.mul(layouter.namespace(|| &desc), operand2)?;
+                        let (ret, scalar) = operand1
+                            .mul(layouter.namespace(|| base::region_name(&desc)), operand2)?;
                         Ok((
                             Operand::Point(Some(ret)),
                             ScalarResult::ScalarFixedPoint(scalar),
@@ -248,7 +352,8 @@ impl AlgoItem {
                                 }
                             };
 
-                        let point = operand1.mul(layouter.namespace(|| &desc), operand2)?;
+                        let point = operand1
+                            .mul(layouter.namespace(|| base::region_name(&desc)), operand2)?;
                         Ok((Operand::Point(Some(point)), ScalarResult::None))
                     }
                     "ShortField" => {
@@ -270,7 +375,8 @@ impl AlgoItem {
                             }
                         };
 
-                        let (point, scalar) = operand1.mul(layouter.namespace(|| &desc), (m, s))?;
+                        let (point, scalar) = operand1
+                            .mul(layouter.namespace(|| base::region_name(&desc)), (m, s))?;
                         Ok((
                             Operand::Point(Some(point)),
                             ScalarResult::ScalarFixedPointShort(scalar),
@@ -291,6 +397,57 @@ impl AlgoItem {
         }
     }
 
+    // assigns the computed field to the cell [name] of the gate, the gate constrains it
+    fn assign_field(
+        layouter: &mut impl Layouter<pallas::Base>,
+        config: &ConfigData,
+        gate_states: &mut BTreeMap<String, bool>,
+        gate_index: usize,
+        name: &String,
+        field: Option<pallas::Base>,
+        cell_values: &mut CellValues,
+        debug_info: &mut Option<BTreeMap<String, Vec<String>>>,
+    ) -> Result<Option<AssignedCell<pallas::Base, pallas::Base>>, plonk::Error> {
+        let mut field_values = BTreeMap::new();
+        field_values.insert(name.clone(), field);
+        let assginedcell_values = assign_region(
+            layouter,
+            config,
+            gate_states,
+            gate_index,
+            cell_values,
+            &field_values,
+            debug_info,
+        )?;
+
+        let v = assginedcell_values.get(name);
+        assert_synthesize_error!(
+            v.is_some(),
+            &format!("[AlgoItem]: [{}] not in assginedcell_values", name)
+        );
+
+        cell_values.insert(name.clone(), (v.unwrap().clone(), None));
+        Ok(v.unwrap().clone())
+    }
+
+    // the cell results of add and mul are Cells (see compute_two), so is an operand of poseidon
+    fn poseidon_operand(
+        operand: &(String, Operand, String),
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        match &operand.1 {
+            Operand::Cell(Some(p)) => Ok(p.clone()),
+            _ => {
+                assert_synthesize_error_and_panic!(
+                    false,
+                    &format!(
+                        "[poseidon]: invalid operand: [{:?}], should be Operand::Cell",
+                        operand
+                    )
+                );
+            }
+        }
+    }
+
     fn compute_two(
         layouter: &mut impl Layouter<pallas::Base>,
         ecc_chip: &ecc::chip::EccChip<DomainFixedBases>,
@@ -320,31 +477,29 @@ impl AlgoItem {
         }
 
         let mut result = match operator.as_str() {
-            "poseidon" => match operand1.2.as_str() {
-                "Cell" | "CommitCell" => {
-                    let operand1: AssignedCell<pallas::Base, pallas::Base> = match &operand1.1 {
-                        Operand::Cell(Some(p)) => p.clone(),
-                        _ => {
-                            assert_synthesize_error_and_panic!(false, &format!("[poseidon-operand1]: invalid operand1: [{:?}], should be Operand::Cell", operand1));
-                        }
-                    };
-                    let operand2: AssignedCell<pallas::Base, pallas::Base> = match &operand2.1 {
-                        Operand::Cell(Some(p)) => p.clone(),
-                        _ => {
-                            assert_synthesize_error_and_panic!(false, &format!("[poseidon-operand2]: invalid operand2: [{:?}], should be Operand::Cell", operand2));
-                        }
-                    };
+            op if poseidon_spec_name(op).is_some() => match operand1.2.as_str() {
+                "Cell" | "CommitCell" | "Field" => {
+                    let operand1 = Self::poseidon_operand(operand1)?;
+                    let operand2 = Self::poseidon_operand(operand2)?;
+
+                    let spec = poseidon_spec_name(operator).unwrap();
+                    let poseidon_config = context.1.get(spec);
+                    assert_synthesize_error!(
+                        poseidon_config.is_some(),
+                        &format!("[poseidon]: the spec [{}] is not configured", spec)
+                    );
 
+                    // the round constants are the ones of the config, the spec type is unused
                     let mut desc = format!("poseidon init: [{}][{}]", name, desc);
                     let poseidon_hasher =
                         PoseidonHash::<_, _, poseidon::P128Pow5T3, ConstantLength<2>, 3, 2>::init(
-                            PoseidonChip::construct(context.1.clone()),
-                            layouter.namespace(|| &desc),
+                            PoseidonChip::construct(poseidon_config.unwrap().clone()),
+                            layouter.namespace(|| base::region_name(&desc)),
                         )?;
 
                     desc = format!("poseidon hash: [{}][{}]", name, desc);
                     let cell = poseidon_hasher.hash(
-                        layouter.namespace(|| &desc),
+                        layouter.namespace(|| base::region_name(&desc)),
                         [operand1.clone(), operand2.clone()],
                     )?;
 
@@ -362,33 +517,67 @@ impl AlgoItem {
             },
 
             _ => {
-                let mut exchange = false;
-                if operator == "add" {
-                    exchange = operand1.2 == "Point" && operand2.2 == "NIPoint";
-                }
-                if !exchange && operator == "mul" {
-                    if !exchange {
-                        exchange = operand2.2 == "NIPoint"
-                            || operand2.2 == "FullField"
-                            || operand2.2 == "BaseField"
-                            || operand2.2 == "ShortField";
+                // a point computed of two named operands is kept in values, so it is computed
+                // once for the synthesis, e.g. [ivk]g_d used by two constraints. The scalar of a
+                // reused multiplication is not returned
+                let memo_name = if operand1.0 != "" && operand2.0 != "" {
+                    Some(format!(
+                        "{}[{}] {} [{}]",
+                        SIGN_OF_MEMO, operand1.0, operator, operand2.0
+                    ))
+                } else {
+                    None
+                };
+                if let Some(Operand::Point(Some(point))) = memo_name
+                    .as_ref()
+                    .and_then(|memo_name| values.get(memo_name))
+                {
+                    Ok((Operand::Point(Some(point.clone())), ScalarResult::None))
+                } else {
+                    let mut exchange = false;
+                    if operator == "add" {
+                        exchange = operand1.2 == "Point" && operand2.2 == "NIPoint";
+                    }
+                    if !exchange && operator == "mul" {
+                        if !exchange {
+                            exchange = operand2.2 == "NIPoint"
+                                || operand2.2 == "FullField"
+                                || operand2.2 == "BaseField"
+                                || operand2.2 == "ShortField";
+                        }
                     }
-                }
 
-                if exchange {
-                    Self::do_point_compute(
-                        layouter, ecc_chip, config, desc, operator, operand2, operand1,
-                    )
-                } else {
-                    Self::do_point_compute(
-                        layouter, ecc_chip, config, desc, operator, operand1, operand2,
-                    )
+                    let result = if exchange {
+                        Self::do_point_compute(
+                            layouter, ecc_chip, config, desc, operator, operand2, operand1,
+                        )
+                    } else {
+                        Self::do_point_compute(
+                            layouter, ecc_chip, config, desc, operator, operand1, operand2,
+                        )
+                    };
+                    if let (Some(memo_name), Ok((point @ Operand::Point(Some(_)), _))) =
+                        (memo_name, &result)
+                    {
+                        values.insert(memo_name, point.clone());
+                    }
+                    result
                 }
             }
         }?;
 
+        // an unnamed cell add or mul is constrained by the arith gate, a named one by the gate of
+        // its cell below
+        if name == "" {
+            if let (Operand::Field(_), Operand::Cell(Some(a)), Operand::Cell(Some(b))) =
+                (&result.0, &operand1.1, &operand2.1)
+            {
+                let cell = assign_arith(layouter, config, operator, a, b, desc, context.0)?;
+                result.0 = Operand::Cell(Some(cell));
+            }
+        }
+
         if name != "" {
-            let mut field_values = BTreeMap::new();
             match result.0 {
                 Operand::Field(field) => {
                     let cell_data = if cell_info.contains_key(name) {
@@ -402,27 +591,17 @@ impl AlgoItem {
                         v.unwrap()
                     };
 
-                    field_values.insert(name.clone(), field);
-                    let assginedcell_values = assign_region(
+                    let cell = Self::assign_field(
                         layouter,
                         config,
                         gate_states,
                         cell_data.2,
+                        name,
+                        field,
                         cell_values,
-                        &field_values,
                         context.0,
                     )?;
-
-                    {
-                        let v = assginedcell_values.get(name);
-                        assert_synthesize_error!(
-                            v.is_some(),
-                            &format!("[AlgoItem]: [{}] not in assginedcell_values", name)
-                        );
-
-                        cell_values.insert(name.clone(), (v.unwrap().clone(), None));
-                        result.0 = Operand::Cell(v.unwrap().clone());
-                    }
+                    result.0 = Operand::Cell(cell);
                 }
                 Operand::Cell(ref cell) => {
                     cell_values
@@ -432,12 +611,57 @@ impl AlgoItem {
                 _ => {}
             }
 
-            values.insert(name.clone(), result.0.clone());
+            bind_operand(values, name, result.0.clone());
         }
 
         Ok(result)
     }
 
+    // the boolean cell is_zero(operand1)
+    fn compute_is_zero(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        config: &ConfigData,
+        operand1: Operand,
+        values: &mut BTreeMap<String, Operand>,
+        cell_values: &mut CellValues,
+        context: &mut ICContext,
+    ) -> Result<(Operand, ScalarResult), plonk::Error> {
+        if context.0.is_some() {
+            context
+                .0
+                .as_mut()
+                .unwrap()
+                .entry("compute".to_string())
+                .or_insert(Vec::new())
+                .push(format!(
+                    "[{}] = '{}' [{}, {}]",
+                    self.name, self.operator, self.operand1.0, self.operand1.1
+                ));
+        }
+
+        let cell = match operand1 {
+            Operand::Cell(Some(cell)) => cell,
+            _ => {
+                assert_synthesize_error_and_panic!(
+                    false,
+                    &format!(
+                        "[is_zero]: invalid operand1: [{}][{}], should be Operand::Cell",
+                        self.operand1.0,
+                        operand1.to_type_string()
+                    )
+                );
+            }
+        };
+
+        let is_zero = assign_is_zero(layouter, config, &self.name, &cell, context.0)?;
+        if self.name != "" {
+            cell_values.insert(self.name.clone(), (Some(is_zero.clone()), None));
+            bind_operand(values, &self.name, Operand::Cell(Some(is_zero.clone())));
+        }
+        Ok((Operand::Cell(Some(is_zero)), ScalarResult::None))
+    }
+
     fn compute(
         &self,
         layouter: &mut impl Layouter<pallas::Base>,
@@ -455,7 +679,30 @@ impl AlgoItem {
                 v.is_some(),
                 &format!("[AlgoItem]: operand1[{}] not in values", self.operand1.0)
             );
-            return Ok((v.unwrap().clone(), ScalarResult::None));
+            if self.operator == "is_zero" {
+                return self.compute_is_zero(
+                    layouter,
+                    config,
+                    v.unwrap().clone(),
+                    values,
+                    cell_values,
+                    context,
+                );
+            }
+
+            let v = v.unwrap().clone();
+            // binds the operand to a new name, no constraint
+            if self.operator == "alias" {
+                assert_synthesize_error!(
+                    self.name != "",
+                    &format!("[AlgoItem]: alias of [{}] has no name", self.operand1.0)
+                );
+                if let Some(cell) = cell_values.get(&self.operand1.0).cloned() {
+                    cell_values.insert(self.name.clone(), cell);
+                }
+                bind_operand(values, &self.name, v.clone());
+            }
+            return Ok((v, ScalarResult::None));
         }
 
         let operand2 = self.operand2.as_ref().unwrap();
@@ -470,6 +717,16 @@ impl AlgoItem {
             )
         );
 
+        // a computed operand takes the type of its result
+        let type_of = |operand: &(String, String), v: &Operand| {
+            if operand.1 == ATTRIBUTE_COMPUTED {
+                v.to_type_string()
+            } else {
+                operand.1.clone()
+            }
+        };
+        let (v1, v2) = (v1.unwrap().clone(), v2.unwrap().clone());
+
         Self::compute_two(
             layouter,
             ecc_chip,
@@ -480,10 +737,10 @@ impl AlgoItem {
             &self.operator,
             &(
                 self.operand1.0.clone(),
-                v1.unwrap().clone(),
-                self.operand1.1.clone(),
+                v1.clone(),
+                type_of(&self.operand1, &v1),
             ),
-            &(operand2.0.clone(), v2.unwrap().clone(), operand2.1.clone()),
+            &(operand2.0.clone(), v2.clone(), type_of(operand2, &v2)),
             values,
             cell_values,
             cell_info,
@@ -492,7 +749,120 @@ impl AlgoItem {
     }
 }
 
+impl AlgoItem {
+    // whether compute_two adds or multiplies two cells, the Field operands are witnessed cells
+    fn is_cell_arith(operator: &String, type1: &String, type2: &String) -> bool {
+        let is_cell = |t: &String| t == "Cell" || t == "CommitCell" || t == "Field";
+        (operator == "add" || operator == "mul") && is_cell(type1) && is_cell(type2)
+    }
+
+    // the Operand type string of the result of compute_two
+    fn result_type(operator: &String, type1: &String, type2: &String) -> String {
+        match operator.as_str() {
+            op if poseidon_spec_name(op).is_some() => "Cell".to_string(),
+            _ if Self::is_cell_arith(operator, type1, type2) => "Cell".to_string(),
+            _ => "Point".to_string(),
+        }
+    }
+
+    fn trace(&self, lines: &mut Vec<String>) -> String {
+        if self.operand2.is_none() {
+            if self.operator == "is_zero" {
+                lines.push(format!(
+                    "[{}] = '{}' [{}, {}]",
+                    self.name, self.operator, self.operand1.0, self.operand1.1
+                ));
+                return "Cell".to_string();
+            }
+            if self.operator == "alias" {
+                lines.push(format!(
+                    "[{}] = '{}' [{}, {}]",
+                    self.name, self.operator, self.operand1.0, self.operand1.1
+                ));
+            }
+            return self.operand1.1.clone();
+        }
+
+        let operand2 = self.operand2.as_ref().unwrap();
+        lines.push(format!(
+            "[{}] = [{}, {}] '{}' [{}, {}]",
+            self.name, self.operand1.0, self.operand1.1, self.operator, operand2.0, operand2.1
+        ));
+        Self::result_type(&self.operator, &self.operand1.1, &operand2.1)
+    }
+}
+
 impl Algo {
+    // the lines compute pushes into context.0 with the debug-info feature, without synthesizing
+    pub fn trace(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.items.len() == 0 {
+            return lines;
+        }
+
+        let mut prev_name = self.items[0].1.name.clone();
+        let mut prev_type = self.items[0].1.trace(&mut lines);
+        for i in 1..self.items.len() {
+            let (operator, item) = &self.items[i];
+            let _type = item.trace(&mut lines);
+
+            let operand2 = if item.operand2.is_some() {
+                (item.name.clone(), _type)
+            } else {
+                item.operand1.clone()
+            };
+            lines.push(format!(
+                "[{}] = [{}, {}] '{}' [{}, {}]",
+                item.name, prev_name, prev_type, operator, operand2.0, operand2.1
+            ));
+
+            prev_type = AlgoItem::result_type(operator, &prev_type, &operand2.1);
+            prev_name = item.name.clone();
+        }
+        lines
+    }
+
+    // whether an unnamed item adds or multiplies two cells, see configure_arith. The type of a
+    // computed operand is known in synthesis only, it is taken as a cell
+    pub(crate) fn has_unnamed_cell_arith(&self) -> bool {
+        let cell_of = |t: &String| {
+            if t == ATTRIBUTE_COMPUTED {
+                "Cell".to_string()
+            } else {
+                t.clone()
+            }
+        };
+        let is_unnamed_arith =
+            |item: &AlgoItem, operator: &String, type1: &String, type2: &String| {
+                item.name == ""
+                    && AlgoItem::is_cell_arith(operator, &cell_of(type1), &cell_of(type2))
+            };
+        let mut lines = Vec::new();
+        let mut prev_type = String::new();
+        for (i, (operator, item)) in self.items.iter().enumerate() {
+            if let Some(operand2) = &item.operand2 {
+                if is_unnamed_arith(item, &item.operator, &item.operand1.1, &operand2.1) {
+                    return true;
+                }
+            }
+            let _type = item.trace(&mut lines);
+            if i > 0 {
+                let type2 = if item.operand2.is_some() {
+                    _type
+                } else {
+                    item.operand1.1.clone()
+                };
+                if is_unnamed_arith(item, operator, &prev_type, &type2) {
+                    return true;
+                }
+                prev_type = AlgoItem::result_type(operator, &prev_type, &type2);
+            } else {
+                prev_type = _type;
+            }
+        }
+        false
+    }
+
     pub(crate) fn compute(
         &self,
         layouter: &mut impl Layouter<pallas::Base>,
@@ -537,6 +907,8 @@ impl Algo {
 
             let operand2 = if item.operand2.is_some() {
                 (item.name.clone(), _operand.to_type_string())
+            } else if item.operand1.1 == ATTRIBUTE_COMPUTED {
+                (item.operand1.0.clone(), _operand.to_type_string())
             } else {
                 item.operand1.clone()
             };
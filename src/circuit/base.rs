@@ -1,8 +1,12 @@
-use pasta_curves::{pallas, vesta};
+use group::{
+    ff::{Field, PrimeField},
+    Curve,
+};
+use pasta_curves::{arithmetic::CurveAffine, pallas, vesta};
 
 use halo2_proofs::{
     circuit::AssignedCell,
-    plonk::{Advice, Column, Instance as InstanceColumn, Selector},
+    plonk::{Advice, Column, ConstraintSystem, Fixed, Instance as InstanceColumn, Selector},
 };
 
 use halo2_gadgets::{
@@ -12,10 +16,17 @@ use halo2_gadgets::{
 };
 
 use funty::Signed;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::marker::PhantomData;
 
+use crate::consts::{
+    ATTRIBUTE_CELL, ATTRIBUTE_COMMIT_CELL, ATTRIBUTE_MERKLEPATH, ATTRIBUTE_POINT, DOMAIN_MERKLECRH,
+    MAX_GATE_DEGREE, SIGN_OF_CONSTRAINT, SIGN_OF_X, SIGN_OF_Y,
+};
 use crate::domains::*;
+use crate::global;
+use crate::primitives::value::ValueType;
 use crate::sinsemilla::config::*;
 use crate::types::*;
 
@@ -29,8 +40,163 @@ pub trait ICConfig: InstanceOrder {
     fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>);
 
     fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>>; //cell_name, attr_name, cell_type, col_type, col, row, width
-    fn get_commit_configs(
-    ) -> Option<Vec<(bool, String, (String, usize), Vec<(String, String)>, String)>>; // (is_short_commit, commit_name, (domain_name, num_window), input name list(name, type), random_name)
+    fn get_commit_configs() -> Option<Vec<CommitConfigTuple>> {
+        None
+    }
+
+    // the column groups of the commits of get_commit_configs by commit name, a commit not listed
+    // uses ColumnGroup::First. A CommitSpec of an overridden get_commit_specs declares its own
+    fn get_commit_column_groups() -> Vec<(String, ColumnGroup)> {
+        Vec::new()
+    }
+
+    // the commits used by ICCircuit, converted from get_commit_configs unless overridden
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        let column_groups = Self::get_commit_column_groups()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        Self::get_commit_configs()
+            .unwrap_or(Vec::new())
+            .iter()
+            .map(|config| {
+                let spec = CommitSpec::from(config);
+                CommitSpec {
+                    column_group: column_groups
+                        .get(&spec.output_name)
+                        .copied()
+                        .unwrap_or_default(),
+                    ..spec
+                }
+            })
+            .collect()
+    }
+
+    // the gates reserving their cells (rows and columns) for future constraints, a reserved gate
+    // has no algo and is constrained by q * 0 only, its cells are witnessed zero unless added.
+    // The column layout stays the same when the constraints are added later, but the VK does
+    // not: the gate polynomials are part of it, so the upgrade needs new keys (and instances of
+    // the same order), only the placement of the other gates and commits is kept
+    fn get_reserved_gates() -> Vec<String> {
+        Vec::new()
+    }
+
+    // the randomness names intentionally shared by several commits, any other randomness name
+    // must be used by one commit only, since commits sharing their randomness are not hiding
+    // independently (see ICCircuit::configure)
+    fn get_shared_randomness() -> Vec<String> {
+        Vec::new()
+    }
+
+    // groups of gate names sharing one selector, the gates of a group are always assigned in
+    // one region once the inputs of all of them are available (the cells computed before are
+    // copied into it), so their cells must not overlap
+    fn get_selector_groups() -> Vec<Vec<String>> {
+        Vec::new()
+    }
+
+    // the max degree of every gate polynomial, a long chain of mul items should be split
+    fn get_max_gate_degree() -> usize {
+        MAX_GATE_DEGREE
+    }
+
+    // the point results of the constraints bound to both coordinates (<name>_x, <name>_y) by
+    // derived_instance_order, the other points are bound to their extracted x-coordinates
+    fn get_instance_points() -> Vec<String> {
+        Vec::new()
+    }
+
+    // the points (added by add_point) witnessed as NonIdentityPoint, so they are constrained in
+    // the circuit to be on the curve and not the identity, like the NIPoint witnesses. pallas has
+    // a prime order, then such a point is torsion-free as well
+    fn get_checked_points() -> Vec<String> {
+        Vec::new()
+    }
+
+    // the merkle paths whose anchors (the roots computed of the paths) are bound to instances
+    // directly, (path name, instance name). Several roots can be published this way, e.g. of a
+    // note commitment tree and of a nullifier tree, where one anchor only needs a gate
+    fn get_anchor_instances() -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    // the anchors constrained equal to another value, (path name, name) where name is another
+    // merkle path (e.g. both notes are in the same tree) or a cell, a gate cell or a result of
+    // the algos. Unlike get_anchor_instances, the value is not published
+    fn get_equal_anchors() -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    // the instance order derived from the configs: the Instance cells of the gates, the anchor
+    // instances, then the results of the constraints not named constraint*. It may be returned by
+    // get_instance_order to keep the two in sync, a manual order is used otherwise
+    fn derived_instance_order() -> Vec<String> {
+        let (gates, algos) = Self::get_ic_configs();
+        let instance_points = Self::get_instance_points();
+
+        let mut order: Vec<String> = Vec::new();
+        for (_, cells) in &gates {
+            for (name, _, celltype, _, _, _, _) in cells {
+                if celltype == "Instance" && !order.contains(name) {
+                    order.push(name.clone());
+                }
+            }
+        }
+
+        for (_, name) in Self::get_anchor_instances() {
+            if !order.contains(&name) {
+                order.push(name);
+            }
+        }
+
+        for (_, _, items) in algos.iter().skip(gates.len()).flatten() {
+            let name = &items[items.len() - 1].1;
+            if name.starts_with(SIGN_OF_CONSTRAINT) || order.contains(name) {
+                continue;
+            }
+            if instance_points.contains(name) {
+                order.push(format!("{}{}", name, SIGN_OF_X));
+                order.push(format!("{}{}", name, SIGN_OF_Y));
+            } else {
+                order.push(name.clone());
+            }
+        }
+        order
+    }
+
+    // extra named poseidon configs, used by the "poseidon:<name>" operator while "poseidon" uses
+    // P128Pow5T3, e.g. PoseidonChip::configure::<MySpec>(meta, state, partial_sbox, rc_a, rc_b).
+    // The spec must be of width 3 and rate 2 as the operator hashes its two operands
+    fn configure_poseidon_specs(
+        _meta: &mut ConstraintSystem<pallas::Base>,
+        _state: [Column<Advice>; 3],
+        _partial_sbox: Column<Advice>,
+        _rc_a: [Column<Fixed>; 3],
+        _rc_b: [Column<Fixed>; 3],
+    ) -> Vec<(String, PoseidonConfig<pallas::Base, 3, 2>)> {
+        Vec::new()
+    }
+}
+
+// a witness of a note, loaded into the circuit by the name of its field
+#[derive(Clone, Debug)]
+pub enum NoteField<S: Signed> {
+    Field(pallas::Base),
+    Point(pallas::Point),
+    NIPoint(pallas::Point),
+    Scalar(pallas::Scalar),
+    Values((ValueType<S>, ValueType<S>)), // old, new
+}
+
+pub trait NoteFields<S: Signed> {
+    fn get_note_fields(&self) -> Vec<(String, NoteField<S>)>; // (field name, witness)
+}
+
+// the values bound to instances and the point results of the constraints, computed by a
+// mock synthesis of a circuit with witnesses
+#[derive(Clone, Debug, Default)]
+pub struct ComputedOutputs {
+    pub instances: BTreeMap<String, pallas::Base>, // instance name => value
+    pub points: BTreeMap<String, (pallas::Base, pallas::Base)>, // constraint name => (x, y)
 }
 
 pub(crate) type CellValues = BTreeMap<
@@ -48,9 +214,12 @@ pub struct ConfigData {
     pub(crate) primary: Column<InstanceColumn>,
     pub(crate) instance_info: BTreeMap<String, usize>, // offset
     pub(crate) qs: Vec<Selector>,
+    pub(crate) is_zero_q: Option<Selector>, // only if the is_zero operator is used
+    pub(crate) arith_qs: Option<(Selector, Selector)>, // (add, mul), only if an unnamed item adds or multiplies cells
+    pub(crate) sign_q: Option<Selector>,               // only if any value is configured
     pub(crate) advices: [Column<Advice>; 10],
     pub(crate) ecc_config: EccConfig<DomainFixedBases>,
-    pub(crate) poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    pub(crate) poseidon_configs: BTreeMap<String, PoseidonConfig<pallas::Base, 3, 2>>, // "" => P128Pow5T3
     pub(crate) merkle_config_1: MerkleConfig<BaseHashDomains, HashDomainsType, DomainFixedBases>,
     pub(crate) merkle_config_2: MerkleConfig<BaseHashDomains, HashDomainsType, DomainFixedBases>,
     pub(crate) sinsemilla_config_1:
@@ -67,8 +236,216 @@ pub struct ConfigData {
             ),
         )>,
     >, // commit_name, (config, input name list(name, type), random_name)
+    pub(crate) constraint_expressions: Vec<String>, // recorded by create_gate
+}
+
+impl ConfigData {
+    // the structural differences of the gates, algos, instance order and commit configs
+    pub fn diff(&self, other: &ConfigData) -> Vec<String> {
+        let mut diffs = Vec::new();
+
+        let gates = |config: &ConfigData| {
+            config
+                .gates
+                .iter()
+                .map(|gate| (gate.name.to_string(), format!("{:?}", gate.cells)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        Self::diff_items("gate", &gates(self), &gates(other), &mut diffs);
+
+        let algos = |config: &ConfigData| {
+            config
+                .algos
+                .iter()
+                .enumerate()
+                .flat_map(|(i, algos)| {
+                    algos.iter().enumerate().map(move |(j, algo)| {
+                        (
+                            format!("[{}][{}] {}", i, j, algo.name),
+                            format!("{:?}", algo.items),
+                        )
+                    })
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+        Self::diff_items("algo", &algos(self), &algos(other), &mut diffs);
+
+        let instances = |config: &ConfigData| {
+            config
+                .instance_info
+                .iter()
+                .map(|(name, offset)| (name.clone(), offset.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        Self::diff_items("instance", &instances(self), &instances(other), &mut diffs);
+
+        let commits = |config: &ConfigData| {
+            config
+                .commit_configs
+                .iter()
+                .flatten()
+                .map(
+                    |(name, (is_short_commit, commit_config, domain, inputs, rname))| {
+                        (
+                            name.clone(),
+                            format!(
+                                "{:?}",
+                                (is_short_commit, domain, inputs, rname, &commit_config.gates)
+                            ),
+                        )
+                    },
+                )
+                .collect::<BTreeMap<_, _>>()
+        };
+        Self::diff_items("commit", &commits(self), &commits(other), &mut diffs);
+
+        diffs
+    }
+
+    // the domains the circuit references: the merkle hash domain, the domains of the merkle
+    // paths and of the commits, and the domains of the fixed bases used by the algos, e.g. for
+    // global::unused_domains
+    pub fn referenced_domains(&self) -> BTreeSet<String> {
+        let mut used = BTreeSet::new();
+        used.insert(global::get_domain_name(DOMAIN_MERKLECRH));
+        for gate in &self.gates {
+            for cell in &gate.cells {
+                let attr = cell.attr.clone().unwrap_or("".to_string());
+                let attr = attr.trim();
+                if attr.starts_with(ATTRIBUTE_MERKLEPATH) {
+                    let attrs: Vec<_> = attr[ATTRIBUTE_MERKLEPATH.len()..].split("#").collect();
+                    if attrs.len() > 1 {
+                        used.insert(attrs[1].trim().to_string());
+                    }
+                }
+            }
+        }
+        for algos in &self.algos {
+            for algo in algos {
+                for (_, item) in &algo.items {
+                    for operand in std::iter::once(&item.operand1).chain(item.operand2.as_ref()) {
+                        if let Some(domain) = global::fixed_base_domain(&operand.0) {
+                            used.insert(domain);
+                        }
+                    }
+                }
+            }
+        }
+        for (_, (_, _, commit_domain, _, _)) in self.commit_configs.iter().flatten() {
+            used.insert(commit_domain.domain.clone());
+        }
+        used
+    }
+
+    // the polynomial of every gate algo as created in configure, in terms of the cell names, e.g.
+    // "[gate] desc: q * ((old_v - new_v) - (magnitude_v * sign_v))"
+    pub fn constraint_expressions(&self) -> Vec<String> {
+        self.constraint_expressions.clone()
+    }
+
+    // the computation plan of the constraint algos (see Algo::trace), the operand types left
+    // unspecified are resolved by the gate cells and the commit configs, with the instance rows
+    // the results are bound to
+    pub fn print_plan(&self) {
+        let resolve = |operand: &(String, String)| {
+            if operand.1 != "" {
+                return operand.clone();
+            }
+            let is_cell = self
+                .gates
+                .iter()
+                .any(|gate| gate.cells.iter().any(|cell| cell.name == operand.0));
+            let commit = self
+                .commit_configs
+                .iter()
+                .flatten()
+                .find(|(name, _)| name == &operand.0);
+            let celltype = match commit {
+                Some((_, (true, ..))) => ATTRIBUTE_COMMIT_CELL,
+                Some((_, (false, ..))) => ATTRIBUTE_POINT,
+                None if is_cell => ATTRIBUTE_CELL,
+                None => "",
+            };
+            (operand.0.clone(), celltype.to_string())
+        };
+
+        for i in self.gates.len()..self.algos.len() {
+            let mut algo = self.algos[i][0].clone();
+            for (_, item) in algo.items.iter_mut() {
+                item.operand1 = resolve(&item.operand1);
+                item.operand2 = item.operand2.as_ref().map(|operand| resolve(operand));
+            }
+
+            println!("[{}] {}", algo.name, algo.desc);
+            for line in algo.trace() {
+                println!("    {}", line);
+            }
+
+            let name = &algo.items[algo.items.len() - 1].1.name;
+            let (name_x, name_y) = (name.clone() + SIGN_OF_X, name.clone() + SIGN_OF_Y);
+            if let Some(row) = self.instance_info.get(name) {
+                println!("    => [{}] == instances[{}]", name, row);
+            } else if let (Some(row_x), Some(row_y)) = (
+                self.instance_info.get(&name_x),
+                self.instance_info.get(&name_y),
+            ) {
+                println!(
+                    "    => [{}] == (instances[{}], instances[{}])",
+                    name, row_x, row_y
+                );
+            } else if name.starts_with(SIGN_OF_CONSTRAINT) {
+                println!("    => [{}] == the witnessed point", name);
+            } else {
+                println!("    => [{}] not bound", name);
+            }
+        }
+    }
+
+    fn diff_items(
+        kind: &str,
+        items: &BTreeMap<String, String>,
+        others: &BTreeMap<String, String>,
+        diffs: &mut Vec<String>,
+    ) {
+        for (name, item) in items {
+            match others.get(name) {
+                None => diffs.push(format!("{} [{}] removed", kind, name)),
+                Some(other) if other != item => diffs.push(format!(
+                    "{} [{}] changed: {} => {}",
+                    kind, name, item, other
+                )),
+                _ => {}
+            }
+        }
+        for name in others.keys().filter(|name| !items.contains_key(*name)) {
+            diffs.push(format!("{} [{}] added", kind, name));
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstanceError {
+    Missing(String),      // in T::get_instance_order but not set
+    Unknown(String),      // set but not in T::get_instance_order
+    NonCanonical(String), // the bytes are not less than the modulus
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceError::Missing(name) => write!(f, "instance [{}] is not set", name),
+            InstanceError::Unknown(name) => {
+                write!(f, "instance [{}] is not in the instance order", name)
+            }
+            InstanceError::NonCanonical(name) => {
+                write!(f, "instance [{}] is not a canonical field element", name)
+            }
+        }
+    }
 }
 
+impl std::error::Error for InstanceError {}
+
 #[derive(Clone, Debug, Default)]
 pub struct Instance<T: InstanceOrder> {
     pub enables: BTreeMap<String, bool>,
@@ -113,7 +490,71 @@ impl<T: InstanceOrder> Instance<T> {
         vec![i]
     }
 
+    // a point result of a constraint, published as <name>_x and <name>_y if both are in the
+    // instance order, or as <name> (the extracted x-coordinate) to halve its public inputs
+    pub fn set_point(&mut self, name: &str, point: &pallas::Point) {
+        let (x, y) = point
+            .to_affine()
+            .coordinates()
+            .map(|p| (*p.x(), *p.y()))
+            .unwrap_or((pallas::Base::zero(), pallas::Base::zero()));
+
+        let order = T::get_instance_order();
+        let name_x = format!("{}{}", name, SIGN_OF_X);
+        if order.contains(&name_x) {
+            self.fields.insert(name_x, x);
+            self.fields.insert(format!("{}{}", name, SIGN_OF_Y), y);
+        } else {
+            assert!(
+                order.contains(&name.to_string()),
+                "[Instance::set_point] neither [{}] nor [{}] is in the instance order",
+                name,
+                name_x
+            );
+            self.fields.insert(name.to_string(), x);
+        }
+    }
+
+    // a field from its little-endian encoding received from an untrusted source, a non-canonical
+    // encoding (not less than the modulus) is rejected rather than reduced. The fields set as
+    // pallas::Base are always canonical
+    pub fn set_field_bytes(&mut self, name: &str, bytes: &[u8; 32]) -> Result<(), InstanceError> {
+        let v: Option<pallas::Base> = pallas::Base::from_repr(*bytes).into();
+        let v = v.ok_or(InstanceError::NonCanonical(name.to_string()))?;
+        self.fields.insert(name.to_string(), v);
+        Ok(())
+    }
+
+    // the instances not set are padded with zero
     pub fn to_halo2_instance(&self) -> Vec<Vec<vesta::Scalar>> {
         Self::instances_to_halo2_instance(&self.to_instances())
     }
+
+    // every instance in T::get_instance_order must be set, and only those
+    pub fn check(&self) -> Result<(), InstanceError> {
+        let instances = self.to_instances();
+        let order = T::get_instance_order();
+        if let Some(name) = order.iter().find(|name| !instances.contains_key(*name)) {
+            return Err(InstanceError::Missing(name.clone()));
+        }
+        if let Some(name) = instances.keys().find(|name| !order.contains(name)) {
+            return Err(InstanceError::Unknown(name.clone()));
+        }
+        Ok(())
+    }
+
+    // the names (in T::get_instance_order) whose values differ from the derived instance
+    // (see ICCircuit::derive_instance), the ones not derived by the circuit are not compared
+    pub fn diff_against(&self, derived: &Instance<T>) -> Vec<String> {
+        let (instances, derived) = (self.to_instances(), derived.to_instances());
+        T::get_instance_order()
+            .into_iter()
+            .filter(|name| derived.contains_key(name) && instances.get(name) != derived.get(name))
+            .collect()
+    }
+
+    pub fn to_checked_halo2_instance(&self) -> Result<Vec<Vec<vesta::Scalar>>, InstanceError> {
+        self.check()?;
+        Ok(self.to_halo2_instance())
+    }
 }
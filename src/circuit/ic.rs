@@ -1,8 +1,9 @@
-use group::Curve;
+use group::{Curve, Group};
 use pasta_curves::pallas;
 
 use halo2_proofs::{
     circuit::{floor_planner, AssignedCell, Layouter},
+    dev::{MockProver, VerifyFailure},
     plonk::{self, Expression},
     poly::Rotation,
 };
@@ -18,10 +19,12 @@ use halo2_gadgets::{
     utilities::{lookup_range_check::LookupRangeCheckConfig, UtilitiesInstructions},
 };
 
-use std::collections::{BTreeMap, HashMap};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryInto;
+use std::sync::{Arc, Mutex};
 
-use super::{algo::*, base::*, synthesize::*};
+use super::{algo::*, base::*, proof::CircuitStats, synthesize::*};
 use crate::{
     base,
     consts::*,
@@ -32,6 +35,29 @@ use crate::{
     types::*,
 };
 
+#[derive(Debug)]
+pub enum MockVerifyError {
+    Synthesis(plonk::Error),
+    Unsatisfied(Vec<VerifyFailure>),
+}
+
+impl std::fmt::Display for MockVerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MockVerifyError::Synthesis(e) => write!(f, "synthesis failed: {:?}", e),
+            MockVerifyError::Unsatisfied(failures) => {
+                write!(f, "[{}] constraints not satisfied", failures.len())?;
+                for failure in failures {
+                    write!(f, "\n{}", failure)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MockVerifyError {}
+
 #[derive(Clone, Debug, Default)]
 pub struct ICCircuit<T: Default + Clone + ICConfig> {
     pub(crate) paths:
@@ -43,8 +69,9 @@ pub struct ICCircuit<T: Default + Clone + ICConfig> {
     pub(crate) scalars: Option<BTreeMap<String, Option<pallas::Scalar>>>,
     pub(crate) values: Option<BTreeMap<String, Option<(ValueType<T::Value>, ValueType<T::Value>)>>>, //old, new
 
-    constraint_points: BTreeMap<String, Option<pallas::Point>>,
+    pub(crate) constraint_points: BTreeMap<String, Option<pallas::Point>>,
     is_with_witnesses: bool,
+    outputs: Option<Arc<Mutex<ComputedOutputs>>>, // only set by compute_outputs
 }
 
 impl<T: Default + Clone + ICConfig> ICCircuit<T> {
@@ -59,6 +86,57 @@ impl<T: Default + Clone + ICConfig> ICCircuit<T> {
         configs.iter().map(|config| Algo::from(config)).collect()
     }
 
+    // prod(value - c_i) for the allowed constants "c_0,c_1,..." configured as operand2
+    fn one_of_expression(
+        value: Expression<pallas::Base>,
+        operand2: &Option<(String, String)>,
+    ) -> Expression<pallas::Base> {
+        assert!(
+            operand2.is_some() && operand2.as_ref().unwrap().1 == "Constants",
+            "(ICCircuit::configure) one_of: operand2 should be the allowed constants: [{:?}]",
+            operand2
+        );
+
+        let constants = &operand2.as_ref().unwrap().0;
+        constants
+            .split(',')
+            .map(|c| {
+                let c = c.trim().parse::<u64>().unwrap_or_else(|_| {
+                    panic!(
+                        "(ICCircuit::configure) one_of: invalid constant [{}] in [{}]",
+                        c, constants
+                    )
+                });
+                value.clone() - Expression::Constant(pallas::Base::from(c))
+            })
+            .fold(Expression::Constant(pallas::Base::one()), |acc, e| acc * e)
+    }
+
+    // the queried value of an operand in a gate, the operand must be one of the gate's Input
+    // or Instance cells
+    fn gate_value(
+        values: &BTreeMap<String, Expression<pallas::Base>>,
+        gate_name: &str,
+        operator: &str,
+        operand: Option<&(String, String)>,
+    ) -> Expression<pallas::Base> {
+        let operand = operand.unwrap_or_else(|| {
+            panic!(
+                "(ICCircuit::configure) gate [{}]: operator [{}] requires two operands",
+                gate_name, operator
+            )
+        });
+        values
+            .get(&operand.0)
+            .unwrap_or_else(|| {
+                panic!(
+                    "(ICCircuit::configure) gate [{}]: operand [{}] of [{}] is not an Input or Instance cell of the gate",
+                    gate_name, operand.0, operator
+                )
+            })
+            .clone()
+    }
+
     pub(crate) fn to_algos_config(configs: &Vec<Vec<AlgoConfig>>) -> Vec<Vec<Algo>> {
         configs
             .iter()
@@ -121,6 +199,40 @@ impl<T: Default + Clone + ICConfig> ICCircuit<T> {
         self.is_with_witnesses = true;
     }
 
+    // the witnesses added, none if not added
+    pub fn field(&self, name: &str) -> Option<pallas::Base> {
+        self.fields.as_ref()?.get(name)?.0
+    }
+
+    pub fn point(&self, name: &str) -> Option<pallas::Point> {
+        *self.points.as_ref()?.get(name)?
+    }
+
+    pub fn nipoint(&self, name: &str) -> Option<pallas::Point> {
+        *self.nipoints.as_ref()?.get(name)?
+    }
+
+    pub fn scalar(&self, name: &str) -> Option<pallas::Scalar> {
+        *self.scalars.as_ref()?.get(name)?
+    }
+
+    pub fn value(&self, name: &str) -> Option<(ValueType<T::Value>, ValueType<T::Value>)> {
+        *self.values.as_ref()?.get(name)?
+    }
+
+    // adds all the witnesses of a note in one call
+    pub fn load_note<N: NoteFields<T::Value>>(&mut self, note: &N) {
+        for (name, field) in note.get_note_fields() {
+            match field {
+                NoteField::Field(v) => self.add_field(&name, &v),
+                NoteField::Point(v) => self.add_point(&name, &v),
+                NoteField::NIPoint(v) => self.add_nipoint(&name, &v),
+                NoteField::Scalar(v) => self.add_scalar(&name, &v),
+                NoteField::Values(v) => self.add_values(&name, &v),
+            }
+        }
+    }
+
     pub fn add_merkle_data(
         &mut self,
         name: &str,
@@ -145,10 +257,131 @@ impl<T: Default + Clone + ICConfig> ICCircuit<T> {
         self.is_with_witnesses = true;
     }
 
+    // (path name, leaf name) of the merkle paths added whose leaf is neither a gate cell nor an
+    // added field or point, synthesize fails with these
+    pub fn missing_merkle_leaves(&self) -> Vec<(String, String)> {
+        let (gates, _) = &T::get_ic_configs();
+        let cells = Self::to_gates_config(&gates)
+            .iter()
+            .flat_map(|gate| gate.cells.iter().map(|cell| cell.name.clone()))
+            .collect::<BTreeSet<_>>();
+        let is_added = |leaf_name: &String| {
+            cells.contains(leaf_name)
+                || self.fields.iter().any(|v| v.contains_key(leaf_name))
+                || self.points.iter().any(|v| v.contains_key(leaf_name))
+                || self.nipoints.iter().any(|v| v.contains_key(leaf_name))
+        };
+
+        self.paths
+            .iter()
+            .flatten()
+            .filter(|(_, (_, leaf_name, path))| path.is_some() && !is_added(leaf_name))
+            .map(|(name, (_, leaf_name, _))| (name.clone(), leaf_name.clone()))
+            .collect()
+    }
+
     pub fn add_constraint_point(&mut self, name: &str, v: &pallas::Point) {
         self.constraint_points.insert(name.to_string(), Some(*v));
     }
 
+    // runs the witness computation by a mock synthesis without proving, returns the values
+    // bound to instances and the point results of the constraints
+    pub fn compute_outputs(&self) -> Result<ComputedOutputs, plonk::Error> {
+        let outputs = Arc::new(Mutex::new(ComputedOutputs::default()));
+        let mut circuit = self.clone();
+        circuit.outputs = Some(outputs.clone());
+
+        // the instances are unknown, zeros are used since the constraints are not verified
+        let instance = vec![pallas::Base::zero(); T::get_instance_order().len()];
+
+        // from the smallest k the instance fits in, up to the rows the regions need
+        let min_k = CircuitStats::build::<T>().min_k(instance.len());
+        let mut result = Err(plonk::Error::Synthesis);
+        for k in min_k..=MAX_MOCK_K {
+            result = MockProver::run(k, &circuit, vec![instance.clone()]).map(|_| ());
+            match result {
+                Err(plonk::Error::NotEnoughRowsAvailable { .. })
+                | Err(plonk::Error::InstanceTooLarge) => {}
+                _ => break,
+            }
+        }
+        result?;
+
+        let outputs = outputs.lock().unwrap().clone();
+        Ok(outputs)
+    }
+
+    // the instance of the values computed by the circuit (see compute_outputs), the instances
+    // the circuit only copies (e.g. the enables) are not set
+    pub fn derive_instance(&self) -> Result<Instance<T>, plonk::Error> {
+        let outputs = self.compute_outputs()?;
+        Ok(Instance {
+            fields: outputs.instances,
+            ..Default::default()
+        })
+    }
+
+    // a synthesis error (e.g. a rejected identity point or too few rows for k) is returned as
+    // MockVerifyError::Synthesis instead of panicking, the failed constraints as Unsatisfied
+    pub fn mock_verify(&self, k: u32, instance: &Instance<T>) -> Result<(), MockVerifyError> {
+        MockProver::run(k, self, instance.to_halo2_instance())
+            .map_err(MockVerifyError::Synthesis)?
+            .verify()
+            .map_err(MockVerifyError::Unsatisfied)
+    }
+
+    // a copy with the Field witness [name] replaced, for negative tests asserting mock_verify
+    // rejects it
+    #[cfg(any(test, feature = "test-dependencies"))]
+    pub fn with_tampered(&self, name: &str, value: pallas::Base) -> Self {
+        let mut circuit = self.clone();
+        let field = circuit
+            .fields
+            .as_mut()
+            .and_then(|fields| fields.get_mut(name));
+        assert!(
+            field.is_some(),
+            "[ICCircuit::with_tampered] [{}] is not a Field witness",
+            name
+        );
+        field.unwrap().0 = Some(value);
+        circuit
+    }
+
+    // the raw advice column matrix of the witness (by column, then by row) for external tools,
+    // the cells copied from the instance are None
+    pub fn witness_columns(&self, k: u32) -> Result<Vec<Vec<Option<pallas::Base>>>, plonk::Error> {
+        halo2api::witness_columns(k, self)
+    }
+
+    #[cfg(feature = "dev-graph")]
+    pub fn render_layout(&self, k: u32, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        halo2api::render_layout("ICCircuit Layout", k, self, path)
+    }
+
+    // gates whose cells are never the result of a constraint algo, they are only assigned
+    // with witnesses at the end of synthesize, which usually indicates a config mistake
+    pub fn unused_gates(&self) -> Vec<String> {
+        let (gates, algos) = &T::get_ic_configs();
+        let gates = Self::to_gates_config(&gates);
+        let algos = Self::to_algos_config(&algos);
+
+        let results = algos
+            .iter()
+            .skip(gates.len())
+            .flatten()
+            .flat_map(|algo| algo.items.iter())
+            .filter(|(_, item)| item.name != "")
+            .map(|(_, item)| item.name.clone())
+            .collect::<BTreeSet<_>>();
+
+        gates
+            .iter()
+            .filter(|gate| !gate.cells.iter().any(|cell| results.contains(&cell.name)))
+            .map(|gate| gate.name.to_string())
+            .collect()
+    }
+
     pub fn copy_none_values(&mut self, other: &Self) {
         self.fields = other.fields.as_ref().map(|values| {
             values
@@ -220,17 +453,141 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             algos.len()
         );
 
+        // the Instance cells of the gates are copied from the instance column by the row in
+        // the instance order
+        let instance_order = T::get_instance_order();
+        for gate in &gates {
+            for cell in gate
+                .cells
+                .iter()
+                .filter(|cell| cell.celltype == CellType::Instance)
+            {
+                assert!(
+                    instance_order.contains(&cell.name),
+                    "(ICCircuit::configure) gate [{}]: instance cell [{}] not in the instance order",
+                    gate.name,
+                    cell.name
+                );
+                assert!(
+                    cell.coltype == ColType::Advice && cell.row != RowType::Prev,
+                    "(ICCircuit::configure) gate [{}]: instance cell [{}] should be an Advice cell at the Cur or Next row",
+                    gate.name,
+                    cell.name
+                );
+            }
+        }
+
+        // the result of a constraint is bound to the instance by its name (or <name>_x and
+        // <name>_y), the results named constraint* are constraint points, and the results only
+        // used as the operands of the other constraints or commits are intermediate
+        let used_names = algos
+            .iter()
+            .skip(gates.len())
+            .flatten()
+            .flat_map(|algo| algo.items.iter())
+            .flat_map(|(_, item)| std::iter::once(&item.operand1).chain(item.operand2.as_ref()))
+            .map(|operand| operand.0.clone())
+            .chain(
+                T::get_commit_specs()
+                    .into_iter()
+                    .flat_map(|spec| spec.inputs.into_iter().map(|input| input.0)),
+            )
+            .collect::<BTreeSet<_>>();
+        for algo in algos.iter().skip(gates.len()).flatten() {
+            let name = &algo.items[algo.items.len() - 1].1.name;
+            if name.starts_with(SIGN_OF_CONSTRAINT)
+                || used_names.contains(name)
+                || instance_order.contains(name)
+            {
+                continue;
+            }
+            assert!(
+                instance_order.contains(&format!("{}{}", name, SIGN_OF_X))
+                    && instance_order.contains(&format!("{}{}", name, SIGN_OF_Y)),
+                "(ICCircuit::configure) constraint [{}]: the result [{}] is not in the instance order as [{}] or [{}{}] and [{}{}]",
+                algo.desc,
+                name,
+                name,
+                name,
+                SIGN_OF_X,
+                name,
+                SIGN_OF_Y
+            );
+        }
+
+        let selector_groups = T::get_selector_groups();
+        let mut gate_groups = BTreeMap::new(); // gate name => group index
+        for (i, group) in selector_groups.iter().enumerate() {
+            let mut positions = BTreeMap::new(); // (col, row) => gate name
+            for name in group {
+                let gate = gates.iter().find(|gate| &gate.name == name);
+                assert!(
+                    gate.is_some(),
+                    "(ICCircuit::configure) selector group[{}]: gate [{}] not configured",
+                    i,
+                    name
+                );
+                assert!(
+                    gate_groups.insert(name.as_str(), i).is_none(),
+                    "(ICCircuit::configure) gate [{}] is in more than one selector group",
+                    name
+                );
+
+                for cell in &gate.unwrap().cells {
+                    let other =
+                        positions.insert((cell.col, format!("{:?}", cell.row)), name.clone());
+                    assert!(
+                        other.is_none(),
+                        "(ICCircuit::configure) selector group[{}]: cell [{}] of gate [{}] conflicts with gate [{}]",
+                        i,
+                        cell.name,
+                        name,
+                        other.unwrap_or_default()
+                    );
+                }
+            }
+        }
+        let group_qs = selector_groups
+            .iter()
+            .map(|_| meta.selector())
+            .collect::<Vec<_>>();
+
+        let reserved_gates = T::get_reserved_gates();
+        for name in &reserved_gates {
+            let i = gates.iter().position(|gate| &gate.name == name);
+            assert!(
+                i.is_some(),
+                "(ICCircuit::configure) reserved gate [{}] not configured",
+                name
+            );
+            assert!(
+                algos[i.unwrap()].is_empty(),
+                "(ICCircuit::configure) reserved gate [{}] must have no algo",
+                name
+            );
+        }
+
+        let mut constraint_expressions = Vec::new();
         let qs = gates
             .iter()
             .zip(&algos[0..gates.len()])
             .map(|(gate, algos)| {
-                let q = meta.selector();
-
-                desc = format!("(ICCircuit::configure) create_gate: [{}]", gate.name);
-                meta.create_gate(base::string_to_static_str(&desc), |meta| {
+                let q = gate_groups
+                    .get(gate.name.as_ref())
+                    .map_or_else(|| meta.selector(), |i| group_qs[*i]);
+
+                // a literal gate name is used directly
+                let name = match &gate.name {
+                    Cow::Borrowed(name) => *name,
+                    Cow::Owned(name) => {
+                        desc = format!("(ICCircuit::configure) create_gate: [{}]", name);
+                        base::string_to_static_str(&desc)
+                    }
+                };
+                meta.create_gate(name, |meta| {
                     let q = meta.query_selector(q);
 
-                    let values = gate
+                    let mut values = gate
                         .cells
                         .iter()
                         .filter_map(|cell| {
@@ -258,17 +615,37 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                             }
                         })
                         .collect::<BTreeMap<_, _>>();
+                    // the rendering of every value, see ConfigData::constraint_expressions
+                    let mut names = values.keys().map(|name| (name.clone(), name.clone())).collect::<BTreeMap<_, _>>();
 
                     let wholes = algos.iter().map(|algo| {
                         let mut whole = Expression::Constant(pallas::Base::zero());
+                        let mut whole_desc: Option<String> = None;
 
                         for (operator, item) in &algo.items {
+                            // binds the operand to a new name for the later items, no constraint
+                            if item.operator == "alias" {
+                                let value = Self::gate_value(&values, &gate.name, &item.operator, Some(&item.operand1));
+                                assert!(item.name != "", "(ICCircuit::configure) gate [{}]: alias of [{}] has no name", gate.name, item.operand1.0);
+                                values.insert(item.name.clone(), value);
+                                names.insert(item.name.clone(), names[&item.operand1.0].clone());
+                                continue;
+                            }
+
                             let item_result = match item.operator.as_str() {
-                                "add" => values[&item.operand1.0].clone() + values[&item.operand2.clone().unwrap().0].clone(),
-                                "sub" => values[&item.operand1.0].clone() - values[&item.operand2.clone().unwrap().0].clone(),
-                                "mul" => values[&item.operand1.0].clone() * values[&item.operand2.clone().unwrap().0].clone(),
-                                "boolean_neg" => Expression::Constant(pallas::Base::one()) - values[&item.operand1.0].clone(),
-                                _ => values[&item.operand1.0].clone(),
+                                "add" | "sub" | "mul" => {
+                                    let value1 = Self::gate_value(&values, &gate.name, &item.operator, Some(&item.operand1));
+                                    // an instance as operand2 is copied into its advice cell by assign_region
+                                    let value2 = Self::gate_value(&values, &gate.name, &item.operator, item.operand2.as_ref());
+                                    match item.operator.as_str() {
+                                        "add" => value1 + value2,
+                                        "sub" => value1 - value2,
+                                        _ => value1 * value2,
+                                    }
+                                }
+                                "boolean_neg" => Expression::Constant(pallas::Base::one()) - Self::gate_value(&values, &gate.name, &item.operator, Some(&item.operand1)),
+                                "one_of" => Self::one_of_expression(Self::gate_value(&values, &gate.name, &item.operator, Some(&item.operand1)), &item.operand2),
+                                _ => Self::gate_value(&values, &gate.name, &item.operator, Some(&item.operand1)),
                             };
 
                             //just for debug
@@ -282,11 +659,65 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                                 "mul" => whole = whole * item_result,
                                 _ => panic!("(ICCircuit::configure) Invalid operator: [{}]", operator),
                             }
+
+                            // rendered as built above, the operands are checked by then
+                            let name1 = &names[&item.operand1.0];
+                            let item_desc = match item.operator.as_str() {
+                                "add" | "sub" | "mul" => {
+                                    let name2 = &names[&item.operand2.as_ref().unwrap().0];
+                                    let op = match item.operator.as_str() {
+                                        "add" => "+",
+                                        "sub" => "-",
+                                        _ => "*",
+                                    };
+                                    format!("({} {} {})", name1, op, name2)
+                                }
+                                "boolean_neg" => format!("(1 - {})", name1),
+                                "one_of" => item.operand2.as_ref().unwrap().0
+                                    .split(',')
+                                    .map(|c| format!("({} - {})", name1, c.trim()))
+                                    .collect::<Vec<_>>()
+                                    .join(" * "),
+                                _ => name1.clone(),
+                            };
+                            whole_desc = Some(match (whole_desc, operator.as_str()) {
+                                (None, _) => item_desc,
+                                (Some(desc), "add") => format!("({} + {})", desc, item_desc),
+                                (Some(desc), "sub") => format!("({} - {})", desc, item_desc),
+                                (Some(desc), _) => format!("({} * {})", desc, item_desc),
+                            });
                         }
 
+                        constraint_expressions.push(format!(
+                            "[{}] {}: q * {}",
+                            gate.name,
+                            algo.desc,
+                            whole_desc.unwrap_or("0".to_string())
+                        ));
                         (algo.desc.clone(), whole)
                     }).collect::<Vec<_>>();
 
+                    // a reserved gate imposes no constraint, see get_reserved_gates
+                    let wholes = if reserved_gates.iter().any(|name| name == &gate.name) {
+                        constraint_expressions.push(format!("[{}] reserved: q * 0", gate.name));
+                        vec![("reserved".to_string(), Expression::Constant(pallas::Base::zero()))]
+                    } else {
+                        wholes
+                    };
+
+                    let max_degree = T::get_max_gate_degree();
+                    for (name, poly) in &wholes {
+                        let degree = (q.clone() * poly.clone()).degree();
+                        assert!(
+                            degree <= max_degree,
+                            "(ICCircuit::configure) gate [{}]: the degree[{}] of algo [{}] exceeds the max[{}], it should be split",
+                            gate.name,
+                            degree,
+                            name,
+                            max_degree
+                        );
+                    }
+
                     wholes.iter().map(move |(_name, poly)| q.clone() * poly.clone())
                         .collect::<Vec<_>>()
                         .into_iter()
@@ -295,6 +726,40 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             })
             .collect::<Vec<_>>();
 
+        // the is_zero gate is configured only if used, so the VK is unchanged otherwise
+        let is_zero_q = if algos.iter().flatten().any(|algo| {
+            algo.items
+                .iter()
+                .any(|(_, item)| item.operator == "is_zero")
+        }) {
+            Some(configure_is_zero(meta, &advices))
+        } else {
+            None
+        };
+
+        // the arith gate is configured only if used as well
+        let arith_qs = if algos
+            .iter()
+            .skip(gates.len())
+            .flatten()
+            .any(|algo| algo.has_unnamed_cell_arith())
+        {
+            Some(configure_arith(meta, &advices))
+        } else {
+            None
+        };
+
+        // the sign of every v_net is constrained to -1 or 1, configured only if any value is
+        let sign_q = if gates.iter().flat_map(|gate| gate.cells.iter()).any(|cell| {
+            cell.attr
+                .as_ref()
+                .map_or(false, |attr| attr.trim() == ATTRIBUTE_VALUE)
+        }) {
+            Some(configure_sign_check(meta, &advices))
+        } else {
+            None
+        };
+
         let table_idx = meta.lookup_table_column();
         let lookup = (
             table_idx,
@@ -336,6 +801,31 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             rc_a,
             rc_b,
         );
+        let mut poseidon_configs = BTreeMap::from([("".to_string(), poseidon_config)]);
+        for (name, poseidon_config) in T::configure_poseidon_specs(
+            meta,
+            advices[6..9].try_into().unwrap(),
+            advices[5],
+            rc_a,
+            rc_b,
+        ) {
+            assert!(
+                name != "" && !poseidon_configs.contains_key(&name),
+                "the poseidon spec name [{}] is empty or duplicated",
+                name
+            );
+            poseidon_configs.insert(name, poseidon_config);
+        }
+        for (_, item) in algos.iter().flatten().flat_map(|algo| algo.items.iter()) {
+            if let Some(spec) = poseidon_spec_name(&item.operator) {
+                assert!(
+                    poseidon_configs.contains_key(spec),
+                    "[{}]: the poseidon spec [{}] is not configured",
+                    item.name,
+                    spec
+                );
+            }
+        }
 
         let (sinsemilla_config_1, merkle_config_1) = {
             let sinsemilla_config_1 = SinsemillaChip::configure(
@@ -365,54 +855,115 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             (sinsemilla_config_2, merkle_config_2)
         };
 
-        let commit_configs = T::get_commit_configs();
-        let commit_configs = if commit_configs.is_some()
-            && commit_configs.as_ref().unwrap().len() > 0
-        {
-            Some(
-                commit_configs
+        let commit_specs = T::get_commit_specs();
+
+        // a computed cell (e.g. a poseidon result) can be a commit input as well as a witnessed
+        // one, but the constraint-commit algos are computed after the commits
+        let computed_after_commits = algos
+            .iter()
+            .skip(gates.len())
+            .flatten()
+            .filter(|algo| algo.name == SIGN_OF_CONSTRAINT_COMMIT)
+            .flat_map(|algo| algo.items.iter())
+            .filter(|(_, item)| item.name != "")
+            .map(|(_, item)| item.name.clone())
+            .collect::<BTreeSet<_>>();
+        for spec in &commit_specs {
+            for (name, _) in &spec.inputs {
+                assert!(
+                    !computed_after_commits.contains(name),
+                    "(ICCircuit::configure) commit [{}]: input [{}] is computed by a constraint-commit algo, after the commit",
+                    spec.output_name,
+                    name
+                );
+            }
+        }
+
+        // a value is committed with L_VALUE bits, a full width input is for a field element
+        let value_cells = gates
+            .iter()
+            .flat_map(|gate| gate.cells.iter())
+            .filter(|cell| {
+                cell.attr
                     .as_ref()
-                    .unwrap()
+                    .map_or(false, |attr| attr.trim() == ATTRIBUTE_VALUE)
+            })
+            .map(|cell| cell.name.clone())
+            .collect::<BTreeSet<_>>();
+        for spec in &commit_specs {
+            for gate in &Self::get_commit_gate_config(&spec.domain) {
+                let input = &gate.cells[0];
+                assert!(
+                    !(CellType::is_input_cell(input.celltype)
+                        && value_cells.contains(&input.name)
+                        && input.width == FIELD_SIZE),
+                    "(ICCircuit::configure) commit [{}]: the value [{}] is declared FIELD_SIZE wide, it should be L_VALUE[{}] bits",
+                    spec.output_name,
+                    input.name,
+                    L_VALUE
+                );
+            }
+        }
+
+        // e.g. a copied commit config whose rcm is not renamed, the deterministic commits (without
+        // randomness) are not checked
+        let shared_randomness = T::get_shared_randomness();
+        let mut randomness_users = BTreeMap::new();
+        for spec in &commit_specs {
+            if spec.randomness_name == "" || shared_randomness.contains(&spec.randomness_name) {
+                continue;
+            }
+            if let Some(other) =
+                randomness_users.insert(spec.randomness_name.clone(), spec.output_name.clone())
+            {
+                panic!(
+                    "(ICCircuit::configure) commits [{}] and [{}] share the randomness [{}], see get_shared_randomness",
+                    other, spec.output_name, spec.randomness_name
+                );
+            }
+        }
+
+        let commit_configs = if commit_specs.len() > 0 {
+            Some(
+                commit_specs
                     .iter()
-                    .map(
-                        |(
-                            is_short_commit,
-                            commit_name,
-                            (domain_name, num_window),
-                            inputs,
-                            rname,
-                        )| {
-                            let commit_domain = HashDomainsType {
-                                domain: domain_name.clone(),
-                                num_windows: *num_window,
-                                is_hash_domain: false,
-                            };
-                            let gates = &Self::get_commit_gate_config(&domain_name);
+                    .map(|spec| {
+                        let commit_domain = HashDomainsType {
+                            domain: spec.domain.clone(),
+                            num_windows: spec.num_windows,
+                            is_hash_domain: false,
+                        };
+                        let gates = &Self::get_commit_gate_config(&spec.domain);
+
+                        assert!(
+                            spec.personalization.len() * 8 <= MAX_PIECE_WIDTH,
+                            "(ICCircuit::configure) commit [{}]: the personalization should not be longer than [{}] bytes",
+                            spec.output_name,
+                            MAX_PIECE_WIDTH / 8
+                        );
 
-                            let sinsemilla_config = if commit_name.starts_with(SIGN_OF_NEW_VALUE) {
-                                &sinsemilla_config_2
-                            } else {
-                                &sinsemilla_config_1
-                            };
-                            let commit_config = CommitConfig::configure(
-                                meta,
-                                advices,
-                                sinsemilla_config.clone(),
-                                commit_domain.clone(),
-                                gates,
-                            );
+                        let sinsemilla_config = match spec.column_group {
+                            ColumnGroup::First => &sinsemilla_config_1,
+                            ColumnGroup::Second => &sinsemilla_config_2,
+                        };
+                        let commit_config = CommitConfig::configure(
+                            meta,
+                            advices,
+                            sinsemilla_config.clone(),
+                            commit_domain.clone(),
+                            gates,
+                        );
+                        (
+                            spec.output_name.clone(),
                             (
-                                commit_name.clone(),
-                                (
-                                    *is_short_commit,
-                                    commit_config,
-                                    commit_domain,
-                                    inputs.clone(),
-                                    rname.clone(),
-                                ),
-                            )
-                        },
-                    )
+                                spec.is_short,
+                                commit_config,
+                                commit_domain,
+                                spec.inputs.clone(),
+                                spec.randomness_name.clone(),
+                            ),
+                        )
+                    })
                     .collect::<Vec<_>>(),
             )
         } else {
@@ -424,6 +975,14 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             .enumerate()
             .map(|(i, name)| (name.clone(), i))
             .collect::<BTreeMap<_, _>>();
+        for (path_name, name) in T::get_anchor_instances() {
+            assert!(
+                instance_info.contains_key(&name),
+                "(ICCircuit::configure) the anchor instance [{}] of merkle path [{}] is not in the instance order",
+                name,
+                path_name
+            );
+        }
 
         ConfigData {
             gates: gates.to_vec(),
@@ -431,13 +990,17 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             primary,
             instance_info,
             qs,
+            is_zero_q,
+            arith_qs,
+            sign_q,
             advices,
             ecc_config,
-            poseidon_config,
+            poseidon_configs,
             merkle_config_1,
             merkle_config_2,
             sinsemilla_config_1,
             commit_configs,
+            constraint_expressions,
         }
     }
 
@@ -450,7 +1013,7 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
         SinsemillaChip::load(config.sinsemilla_config_1.clone(), &mut layouter)?;
         let ecc_chip = EccChip::construct(config.ecc_config.clone());
 
-        let mut _debug_info = if !self.is_with_witnesses && cfg!(feature = "debug") {
+        let mut _debug_info = if !self.is_with_witnesses && cfg!(feature = "debug-info") {
             Some(BTreeMap::new())
         } else {
             None
@@ -467,6 +1030,32 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
 
         let commit_configs = config.commit_configs.clone().unwrap_or(Vec::default());
 
+        // the commits configured must still be the ones of T, by name, domain, inputs and random
+        let declared = T::get_commit_specs();
+        assert_eq_synthesize_error!(
+            declared.len(),
+            commit_configs.len(),
+            "[ICCircuit::synthesize] the number of commits differs from the one configured"
+        );
+        for (spec, configured) in declared.iter().zip(commit_configs.iter()) {
+            let (
+                configured_name,
+                (configured_short, _, commit_domain, configured_inputs, configured_rname),
+            ) = configured;
+            assert_synthesize_error!(
+                &spec.output_name == configured_name
+                    && spec.is_short == *configured_short
+                    && spec.domain == commit_domain.domain
+                    && spec.num_windows == commit_domain.num_windows
+                    && &spec.inputs == configured_inputs
+                    && &spec.randomness_name == configured_rname,
+                &format!(
+                    "[ICCircuit::synthesize] the commit [{}] differs from the configured [{}]",
+                    spec.output_name, configured_name
+                )
+            );
+        }
+
         let mut eccpoint_values = HashMap::new();
         let mut nipoint_values = HashMap::new();
         let mut net_values = HashMap::new();
@@ -475,6 +1064,25 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
         let mut operands = BTreeMap::new();
         let mut cell_info = BTreeMap::new();
 
+        // the cells of the reserved gates not added are zero
+        let reserved_gates = T::get_reserved_gates();
+        for gate in config
+            .gates
+            .iter()
+            .filter(|gate| reserved_gates.iter().any(|name| name == &gate.name))
+        {
+            for cell in gate.cells.iter().filter(|cell| {
+                cell.celltype == CellType::Input
+                    && cell.attr.as_ref().map_or(true, |attr| attr.trim() == "")
+            }) {
+                fields.entry(cell.name.clone()).or_insert((
+                    Some(pallas::Base::zero()),
+                    None,
+                    false,
+                ));
+            }
+        }
+
         for i in 0..config.gates.len() {
             let gate = &config.gates[i];
             cell_info.append(
@@ -506,14 +1114,37 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                             points.entry(attrs[2].to_string()).or_insert(None);
                         }
 
-                        (cell.name.clone(), (gate.name.clone(), cell.clone(), i))
+                        (cell.name.clone(), (gate.name.to_string(), cell.clone(), i))
                     })
                     .collect::<BTreeMap<_, _>>(),
             );
         }
 
+        // the names of the items computed by the constraints before the commits (phase 0) and
+        // after them (phase 2), by the order of computation
+        let phase_of = |algo: &Algo| {
+            if algo.name == SIGN_OF_CONSTRAINT {
+                0
+            } else {
+                2
+            }
+        };
+        let computed = (config.gates.len()..config.algos.len())
+            .flat_map(|i| {
+                let algo = &config.algos[i][0];
+                algo.items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, item))| item.name != "")
+                    .map(move |(j, (_, item))| (item.name.clone(), (phase_of(algo), i, j)))
+            })
+            .fold(BTreeMap::new(), |mut computed, (name, at)| {
+                computed.entry(name).or_insert(at);
+                computed
+            });
+
         {
-            let mut insert_none_value = |operand: &(String, String)| {
+            let mut insert_none_value = |operand: &(String, String), at: (usize, usize, usize)| {
                 assert_synthesize_error!(operand.0 != "", "operand name configured must not be ''");
 
                 match operand.1.as_str() {
@@ -558,7 +1189,25 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                         let v = global::get_fixedpointshort(&operand.0).clone().unwrap();
                         operands.insert(operand.0.clone(), Operand::ShortField(v));
                     }
-                    "MagnitudeSign" | "CommitCell" => {}
+                    // produced during synthesis, by the v_net of a value and by a commit
+                    "MagnitudeSign" => {
+                        assert_synthesize_error!(
+                            values.contains_key(&operand.0),
+                            &format!("[ICCircuit::synthesize] the MagnitudeSign operand [{}] is not a value of any gate cell with the attribute [{}]", operand.0, ATTRIBUTE_VALUE)
+                        );
+                    }
+                    "CommitCell" => {
+                        assert_synthesize_error!(
+                            commit_configs.iter().any(|(commit_name, _)| commit_name == &operand.0),
+                            &format!("[ICCircuit::synthesize] the CommitCell operand [{}] is not produced by any commit config", operand.0)
+                        );
+                    }
+                    ATTRIBUTE_COMPUTED => {
+                        assert_synthesize_error!(
+                            computed.get(&operand.0).map_or(false, |computed_at| *computed_at < at),
+                            &format!("[ICCircuit::synthesize] the Computed operand [{}] is not computed by any item before", operand.0)
+                        );
+                    }
                     _ => {
                         assert_synthesize_error_and_panic!(false, &format!("[ICCircuit::synthesize] Invalid operand type configured: [{}] for [{}]", operand.1, operand.0));
                     }
@@ -576,10 +1225,11 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                     )
                 );
 
-                for (_operator, item) in &algo.items {
-                    insert_none_value(&item.operand1)?;
+                for (j, (_operator, item)) in algo.items.iter().enumerate() {
+                    let at = (phase_of(algo), i, j);
+                    insert_none_value(&item.operand1, at)?;
                     if item.operand2.is_some() {
-                        insert_none_value(item.operand2.as_ref().unwrap())?;
+                        insert_none_value(item.operand2.as_ref().unwrap(), at)?;
                     }
 
                     if !self.is_with_witnesses {
@@ -594,10 +1244,12 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                 for (_, (_, _, _, inputs, rname)) in &commit_configs {
                     for input in inputs {
                         if input.1 != "" {
-                            insert_none_value(&input)?;
+                            insert_none_value(&input, (1, 0, 0))?;
                         }
                     }
-                    insert_none_value(&(rname.clone(), "Scalar".to_string()))?;
+                    if rname != "" {
+                        insert_none_value(&(rname.clone(), "Scalar".to_string()), (1, 0, 0))?;
+                    }
                 }
             }
         }
@@ -621,7 +1273,7 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             let v0 = halo2api::load_private(
                 self,
                 &mut layouter,
-                &desc,
+                base::region_name(&desc),
                 &config.advices,
                 0,
                 &v.0,
@@ -638,7 +1290,7 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                 let v1 = halo2api::load_private(
                     self,
                     &mut layouter,
-                    &desc,
+                    base::region_name(&desc),
                     &config.advices,
                     0,
                     &v.1,
@@ -650,28 +1302,55 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             }
         }
 
+        let checked_points = T::get_checked_points();
         for (name, p) in &points {
             let desc = format!(
                 "[ICCircuit::synthesize] Convert point[{}] to eccpoint",
                 name
             );
-            let p = Point::new(
-                ecc_chip.clone(),
-                layouter.namespace(|| &desc),
-                p.as_ref().map(|p| p.to_affine()),
-            )?;
+            let p = if checked_points.contains(name) {
+                assert_synthesize_error!(
+                    p.map_or(true, |p| !bool::from(p.is_identity())),
+                    &format!(
+                        "[ICCircuit::synthesize] checked point[{}] is the identity",
+                        name
+                    )
+                );
+                NonIdentityPoint::new(
+                    ecc_chip.clone(),
+                    layouter.namespace(|| base::region_name(&desc)),
+                    p.as_ref().map(|p| p.to_affine()),
+                )?
+                .into()
+            } else {
+                Point::new(
+                    ecc_chip.clone(),
+                    layouter.namespace(|| base::region_name(&desc)),
+                    p.as_ref().map(|p| p.to_affine()),
+                )?
+            };
             eccpoint_values.insert(name.clone(), Some(p));
         }
 
+        // NonIdentityPoint::new constrains the coordinates to be on the curve, and (0, 0) which
+        // encodes the identity is not, so a nipoint is never trusted as witnessed
         for (name, p) in &nipoints {
             let desc = format!(
                 "[ICCircuit::synthesize] Convert nipoint[{}] to NonIdentityPoint",
                 name
             );
+            assert_synthesize_error!(
+                p.map_or(true, |p| !bool::from(p.is_identity())),
+                &format!(
+                    "[ICCircuit::synthesize] nipoint[{}] is the identity, should be NIPoint",
+                    name
+                )
+            );
+
             let _p = p.clone();
             let p = NonIdentityPoint::new(
                 ecc_chip.clone(),
-                layouter.namespace(|| &desc),
+                layouter.namespace(|| base::region_name(&desc)),
                 p.map(|p| p.to_affine()),
             )?;
 
@@ -730,7 +1409,7 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                 let magnitude = halo2api::load_private(
                     self,
                     &mut layouter,
-                    &desc,
+                    base::region_name(&desc),
                     &config.advices,
                     9,
                     &magnitude_sign.map(|m_s| m_s.0),
@@ -741,13 +1420,15 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                 let sign = halo2api::load_private(
                     self,
                     &mut layouter,
-                    &desc,
+                    base::region_name(&desc),
                     &config.advices,
                     9,
                     &magnitude_sign.map(|m_s| m_s.1),
                     (&mut _debug_info, &sign_name),
                 )?;
 
+                assign_sign_check(&mut layouter, &config, &sign_name, &sign, &mut _debug_info)?;
+
                 cell_values.insert(magnitude_name, (Some(magnitude.clone()), None));
                 cell_values.insert(sign_name, (Some(sign.clone()), None));
 
@@ -758,6 +1439,9 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
         }
 
         // merkle path validity check.
+        let anchor_instances = T::get_anchor_instances()
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
         for (name, (domain_name, leaf_name, path)) in &paths {
             let anchor = {
                 let path: Option<[pallas::Base; MERKLE_DEPTH]> =
@@ -775,22 +1459,47 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
 
                 let v = cell_values.get(leaf_name);
                 let leaf = if v.is_some() {
+                    assert_synthesize_error!(
+                        v.unwrap().0.is_some(),
+                        &format!(
+                            "[ICCircuit::synthesize] the leaf [{}] of merkle path [{}] is not assigned",
+                            leaf_name, name
+                        )
+                    );
                     v.unwrap().0.clone().unwrap()
                 } else {
                     let v = eccpoint_values.get(leaf_name);
                     assert_synthesize_error!(
                         v.is_some() && v.unwrap().is_some(),
                         &format!(
-                            "[ICCircuit::synthesize] [{}] not in eccpoint_values",
-                            leaf_name
+                            "[ICCircuit::synthesize] the leaf [{}] of merkle path [{}] is not added, see missing_merkle_leaves",
+                            leaf_name, name
                         )
                     );
                     v.unwrap().as_ref().unwrap().extract_p().inner().clone()
                 };
 
                 let desc = format!("[ICCircuit::synthesize] MerkleCRH[{}]", name);
-                merkle_inputs.calculate_root(layouter.namespace(|| &desc), leaf)?
+                merkle_inputs
+                    .calculate_root(layouter.namespace(|| base::region_name(&desc)), leaf)?
             };
+
+            if let Some(instance_name) = anchor_instances.get(name) {
+                if let (Some(outputs), Some(v)) = (&self.outputs, anchor.value()) {
+                    outputs
+                        .lock()
+                        .unwrap()
+                        .instances
+                        .insert(instance_name.clone(), *v);
+                }
+                halo2api::constrain_instance(
+                    &mut layouter,
+                    &anchor.cell(),
+                    &config.primary,
+                    config.instance_info[instance_name],
+                    (&mut _debug_info, instance_name),
+                )?;
+            }
             cell_values.insert(
                 SIGN_OF_ANCHOR.to_string() + &name.clone(),
                 (Some(anchor), None),
@@ -844,11 +1553,20 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             &cell_info,
             &mut (
                 &mut _debug_info,
-                &config.poseidon_config.clone(),
+                &config.poseidon_configs.clone(),
                 &constraint_points,
             ),
+            &self.outputs,
         )?;
 
+        // one sinsemilla chip per sinsemilla config (the column group of the commit spec, see
+        // configure), shared by the commits instead of being constructed by each of them
+        let specs = declared
+            .into_iter()
+            .map(|spec| (spec.output_name.clone(), spec))
+            .collect::<BTreeMap<_, _>>();
+        let mut sinsemilla_chips: BTreeMap<ColumnGroup, SinsemillaChipType> = BTreeMap::new();
+
         // check commits
         for (commit_name, (is_short_commit, commit_config, commit_domain, inputs, rname)) in
             &commit_configs
@@ -897,10 +1615,14 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                             },
                         )
                     }
+                    // a witnessed or a computed cell, copied into the input cell of the commit
                     Operand::Cell(Some(v)) => {
                         input_map.insert(name.clone(), (Some(v.clone()), None, 0));
                         (v.value().map(|v| v.clone()), None)
                     }
+                    Operand::Cell(None) => {
+                        assert_synthesize_error_and_panic!(false, &format!("[ICCircuit::synthesize] the cell of commit input name[{}] is not assigned or computed by a constraint algo", name));
+                    }
                     _ => {
                         assert_synthesize_error_and_panic!(false, &format!("[ICCircuit::synthesize] the operand[{}] of commit input name[{}] is invalid or None", v.to_type_string(), name));
                     }
@@ -908,14 +1630,34 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                 input_list.push((name.clone(), v.0, v.1));
             }
 
-            let circuit = SinsemillaCircuit::<Self>::new(
-                *is_short_commit,
-                &commit_domain.domain,
-                &input_list,
-                random,
-                &Vec::default(),
-            );
+            // a deterministic commit is the hash point only, a witnessed r = 0 would not be
+            // constrained
+            let circuit = if rname == "" {
+                SinsemillaCircuit::<Self>::new_deterministic(
+                    *is_short_commit,
+                    &commit_domain.domain,
+                    &input_list,
+                    &Vec::default(),
+                )
+            } else {
+                SinsemillaCircuit::<Self>::new(
+                    *is_short_commit,
+                    &commit_domain.domain,
+                    &input_list,
+                    random,
+                    &Vec::default(),
+                )
+            };
+            let circuit = SinsemillaCircuit {
+                personalization: specs[commit_name].personalization.clone(),
+                ..circuit
+            };
 
+            let sinsemilla_chip = sinsemilla_chips
+                .entry(specs[commit_name].column_group)
+                .or_insert_with(|| {
+                    SinsemillaChip::construct(commit_config.sinsemilla_config.clone())
+                });
             let cm = circuit.do_synthesize(
                 &(
                     commit_config.clone(),
@@ -923,20 +1665,23 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
                     commit_config.gates.clone(),
                 ),
                 &ecc_chip,
+                Some(&*sinsemilla_chip),
                 &mut layouter,
                 &mut input_map,
                 self.is_with_witnesses,
+                Some((&mut _debug_info, commit_name.as_str())),
             )?;
 
             match cm {
                 PointResult::X(x) => {
-                    operands.insert(
-                        commit_name.clone(),
+                    bind_operand(
+                        &mut operands,
+                        commit_name,
                         Operand::Cell(x.map(|x| x.inner().clone())),
                     );
                 }
                 PointResult::Point(p) => {
-                    operands.insert(commit_name.clone(), Operand::Point(p));
+                    bind_operand(&mut operands, commit_name, Operand::Point(p));
                 }
             }
         }
@@ -952,14 +1697,15 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             &cell_info,
             &mut (
                 &mut _debug_info,
-                &config.poseidon_config.clone(),
+                &config.poseidon_configs.clone(),
                 &constraint_points,
             ),
+            &self.outputs,
         )?;
 
         for i in 0..config.gates.len() {
             let gate = &config.gates[i];
-            if !gate_states.contains_key(&gate.name) {
+            if !gate_states.contains_key(gate.name.as_ref()) {
                 assign_region(
                     &mut layouter,
                     &config,
@@ -972,7 +1718,62 @@ impl<T: Default + Clone + ICConfig> plonk::Circuit<pallas::Base> for ICCircuit<T
             }
         }
 
-        #[cfg(feature = "debug")]
+        // the anchors constrained equal, see get_equal_anchors
+        for (path_name, name) in T::get_equal_anchors() {
+            let anchor_name = SIGN_OF_ANCHOR.to_string() + &path_name;
+            let other_name = if paths.contains_key(&name) {
+                SIGN_OF_ANCHOR.to_string() + &name
+            } else {
+                name.clone()
+            };
+            let get_cell = |name: &String| match operands.get(name) {
+                Some(Operand::Cell(Some(cell))) => Some(cell.clone()),
+                _ => cell_values.get(name).and_then(|v| v.0.clone()),
+            };
+
+            let anchor = get_cell(&anchor_name);
+            assert_synthesize_error!(
+                anchor.is_some(),
+                &format!(
+                    "[ICCircuit::synthesize] the anchor of merkle path [{}] is not computed",
+                    path_name
+                )
+            );
+            let other = get_cell(&other_name);
+            assert_synthesize_error!(
+                other.is_some(),
+                &format!(
+                    "[ICCircuit::synthesize] [{}] constrained equal to the anchor of merkle path [{}] is not assigned",
+                    name, path_name
+                )
+            );
+
+            let desc = format!(
+                "[ICCircuit::synthesize] anchor[{}] == [{}]",
+                path_name, name
+            );
+            layouter.assign_region(
+                || base::region_name(&desc),
+                |mut region| {
+                    region.constrain_equal(
+                        anchor.as_ref().unwrap().cell(),
+                        other.as_ref().unwrap().cell(),
+                    )
+                },
+            )?;
+
+            #[cfg(feature = "debug-info")]
+            if _debug_info.is_some() {
+                _debug_info
+                    .as_mut()
+                    .unwrap()
+                    .entry("constrain_equal".to_string())
+                    .or_insert(Vec::new())
+                    .push(format!("{} == {}", anchor_name, other_name));
+            }
+        }
+
+        #[cfg(feature = "debug-info")]
         halo2api::output_debug_info("ICCircuit", &_debug_info);
 
         Ok(())
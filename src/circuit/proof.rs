@@ -1,54 +1,296 @@
-use pasta_curves::vesta;
+use pasta_curves::{arithmetic::FieldExt, group::ff::PrimeField, vesta};
 
 use halo2_proofs::{
-    plonk::{self, SingleVerifier},
+    plonk::{self, Circuit, SingleVerifier},
     poly,
-    transcript::{Blake2bRead, Blake2bWrite},
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, Transcript},
 };
 
 use memuse::DynamicUsage;
-use rand::RngCore;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 
 use super::base::*;
 use super::ic::*;
+use crate::global;
+
+// the header of Proof::to_bytes: the format version and the circuit hash of the proving key
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+pub const PROOF_HEADER_SIZE: usize = 1 + 32;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub degree: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    pub gates: usize,
+    pub lookups: usize,
+    // the rows reserved at the end of every column for the blinding factors of zero-knowledge,
+    // fixed by halo2_proofs by the queries of the circuit, it has no knob to reduce them or to
+    // disable zk, so they have to be counted when K is chosen
+    pub blinding_rows: usize,
+}
+
+impl CircuitStats {
+    // reads the constraint system configured by ICCircuit<T>, before the selectors are compressed
+    pub fn build<T: Default + Clone + ICConfig>() -> Self {
+        let mut cs = plonk::ConstraintSystem::default();
+        ICCircuit::<T>::configure(&mut cs);
+
+        CircuitStats {
+            degree: cs.degree(),
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            gates: cs.gates().len(),
+            lookups: cs.lookups().len(),
+            blinding_rows: cs.blinding_factors() + 1,
+        }
+    }
+
+    // the smallest k whose rows hold the instance rows above the blinding rows, a lower k is
+    // rejected by halo2_proofs with InstanceTooLarge before any region is assigned
+    pub fn min_k(&self, instance_rows: usize) -> u32 {
+        let rows = instance_rows + self.blinding_rows;
+        rows.next_power_of_two().trailing_zeros().max(1)
+    }
+}
+
+// a parameter set with the k it is created with, which halo2_proofs does not expose
+#[derive(Clone, Debug)]
+pub struct KeyParams {
+    k: u32,
+    params: poly::commitment::Params<vesta::Affine>,
+}
+
+impl KeyParams {
+    pub fn new(k: u32) -> Self {
+        KeyParams {
+            k,
+            params: poly::commitment::Params::new(k),
+        }
+    }
+
+    // reads a parameter set written by write (or by halo2_proofs), k is the first field of it
+    pub fn read(reader: &mut impl io::Read) -> io::Result<Self> {
+        let mut k = [0u8; 4];
+        reader.read_exact(&mut k)?;
+        let params = poly::commitment::Params::read(&mut io::Read::chain(&k[..], reader))?;
+        Ok(KeyParams {
+            k: u32::from_le_bytes(k),
+            params,
+        })
+    }
+
+    pub fn write(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        self.params.write(writer)
+    }
+
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+}
 
 #[derive(Debug)]
 pub struct VerifyingKey {
+    k: u32,
+    fingerprint: [u8; 32],
+    circuit_hash: [u8; 32],
+    transcript_personalization: Option<vesta::Scalar>,
+    stats: CircuitStats,
     params: poly::commitment::Params<vesta::Affine>,
     vk: plonk::VerifyingKey<vesta::Affine>,
 }
 
 impl VerifyingKey {
     pub fn build<T: Default + Clone + ICConfig>(k: u32) -> Self {
-        let params = poly::commitment::Params::new(k);
+        Self::build_with_params::<T>(&KeyParams::new(k))
+    }
+
+    // builds with a parameter set created (or loaded) by the user
+    pub fn build_with_params<T: Default + Clone + ICConfig>(params: &KeyParams) -> Self {
+        let k = params.k;
+        let fingerprint = global::config_fingerprint_of(&referenced_domains::<T>());
+        let params = params.params.clone();
         let circuit: ICCircuit<T> = Default::default();
 
         let vk = plonk::keygen_vk(&params, &circuit).unwrap();
 
-        VerifyingKey { params, vk }
+        VerifyingKey {
+            k,
+            fingerprint,
+            circuit_hash: circuit_hash(&fingerprint, &vk),
+            transcript_personalization: None,
+            stats: CircuitStats::build::<T>(),
+            params,
+            vk,
+        }
+    }
+
+    // the fingerprint of the configuration (of the domains the circuit references) the key is
+    // built with, see global::config_fingerprint_of
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    // identifies the circuit (and the configuration) of the key, see Proof::to_bytes
+    pub fn circuit_hash(&self) -> [u8; 32] {
+        self.circuit_hash
+    }
+
+    pub fn stats(&self) -> CircuitStats {
+        self.stats
+    }
+
+    // absorbed into the transcript before verifying, see ProvingKey::with_transcript_personalization
+    pub fn with_transcript_personalization(mut self, personalization: Option<&[u8]>) -> Self {
+        self.transcript_personalization = personalization.map(transcript_personalization);
+        self
     }
 }
 
 #[derive(Debug)]
 pub struct ProvingKey {
+    k: u32,
+    domains: BTreeSet<String>, // the ones of the fingerprint
+    fingerprint: [u8; 32],
+    circuit_hash: [u8; 32],
+    key_fingerprint: [u8; 32],
+    transcript_personalization: Option<vesta::Scalar>,
     params: poly::commitment::Params<vesta::Affine>,
     pk: plonk::ProvingKey<vesta::Affine>,
 }
 
 impl ProvingKey {
     pub fn build<T: Default + Clone + ICConfig>(k: u32) -> Self {
-        let params = poly::commitment::Params::new(k);
+        Self::build_with_params::<T>(&KeyParams::new(k))
+    }
+
+    // builds with a parameter set created (or loaded) by the user
+    pub fn build_with_params<T: Default + Clone + ICConfig>(params: &KeyParams) -> Self {
+        let k = params.k;
+        let domains = referenced_domains::<T>();
+        let fingerprint = global::config_fingerprint_of(&domains);
+        let params = params.params.clone();
         let circuit: ICCircuit<T> = Default::default();
 
         let vk = plonk::keygen_vk(&params, &circuit).unwrap();
+        let circuit_hash = circuit_hash(&fingerprint, &vk);
         let pk = plonk::keygen_pk(&params, vk, &circuit).unwrap();
+        let key_fingerprint = key_fingerprint(k, &circuit_hash);
 
-        ProvingKey { params, pk }
+        ProvingKey {
+            k,
+            domains,
+            fingerprint,
+            circuit_hash,
+            key_fingerprint,
+            transcript_personalization: None,
+            params,
+            pk,
+        }
+    }
+
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    // the fingerprint of the configuration (of the domains the circuit references) the key is
+    // built with, the same as the one of VerifyingKey
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    // the fingerprint of the key itself, the same for the keys built independently of the same
+    // circuit with the same params by the same halo2 version, see key_fingerprint
+    pub fn key_fingerprint(&self) -> [u8; 32] {
+        self.key_fingerprint
+    }
+
+    // absorbed into the transcript before proving, so a proof of one deployment fails to verify
+    // with the verifying key of another. The keys themselves do not depend on it
+    pub fn with_transcript_personalization(mut self, personalization: Option<&[u8]>) -> Self {
+        self.transcript_personalization = personalization.map(transcript_personalization);
+        self
+    }
+}
+
+impl PartialEq for ProvingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key_fingerprint() == other.key_fingerprint()
+    }
+}
+
+impl Eq for ProvingKey {}
+
+// a proving key built on the first create (or get) and cached, so a run that never proves does
+// not pay for the key. The build happens under the lock, concurrent first uses wait for it
+pub struct LazyProvingKey<T> {
+    k: u32,
+    transcript_personalization: Option<Vec<u8>>,
+    pk: Mutex<Option<Arc<ProvingKey>>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Default + Clone + ICConfig> LazyProvingKey<T> {
+    pub fn new(k: u32) -> Self {
+        LazyProvingKey {
+            k,
+            transcript_personalization: None,
+            pk: Mutex::new(None),
+            _marker: PhantomData,
+        }
+    }
+
+    // the personalization of the key once built, see ProvingKey::with_transcript_personalization
+    pub fn with_transcript_personalization(mut self, personalization: Option<&[u8]>) -> Self {
+        self.transcript_personalization = personalization.map(|p| p.to_vec());
+        self
+    }
+
+    pub fn k(&self) -> u32 {
+        self.k
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.pk.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    // the key, built with ProvingKey::build on the first call
+    pub fn get(&self) -> Arc<ProvingKey> {
+        self.pk
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(|| {
+                Arc::new(
+                    ProvingKey::build::<T>(self.k).with_transcript_personalization(
+                        self.transcript_personalization.as_deref(),
+                    ),
+                )
+            })
+            .clone()
+    }
+
+    pub fn create(
+        &self,
+        circuits: &[ICCircuit<T>],
+        instances: &[Instance<T>],
+        rng: impl RngCore,
+    ) -> Result<Proof, plonk::Error> {
+        Proof::create(&self.get(), circuits, instances, rng)
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct Proof(Vec<u8>);
+pub struct Proof(Vec<u8>, Option<[u8; 32]>, Option<[u8; 32]>); // proof bytes, fingerprint and circuit hash of pk
 
 impl AsRef<[u8]> for Proof {
     fn as_ref(&self) -> &[u8] {
@@ -73,14 +315,34 @@ impl Proof {
         instances: &[Instance<T>],
         mut rng: impl RngCore,
     ) -> Result<Self, plonk::Error> {
-        let instances: Vec<_> = instances.iter().map(|i| i.to_halo2_instance()).collect();
+        // the domains configured meanwhile for other circuits do not matter
+        if pk.fingerprint != global::config_fingerprint_of(&pk.domains) {
+            println!("!the proving key is built with a different configuration");
+            return Err(plonk::Error::ConstraintSystemFailure);
+        }
+
+        #[cfg(feature = "check-proof-size")]
+        let typed_instances = instances;
+        let instances = instances
+            .iter()
+            .map(|i| i.to_checked_halo2_instance())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                println!("!{}", e);
+                plonk::Error::InvalidInstances
+            })?;
         let instances: Vec<Vec<_>> = instances
             .iter()
             .map(|i| i.iter().map(|c| &c[..]).collect())
             .collect();
         let instances: Vec<_> = instances.iter().map(|i| &i[..]).collect();
 
-        let mut transcript = Blake2bWrite::<_, vesta::Affine, _>::init(vec![]);
+        let mut transcript = Blake2bWrite::<_, vesta::Affine, Challenge255<_>>::init(vec![]);
+        if let Some(personalization) = pk.transcript_personalization {
+            transcript
+                .common_scalar(personalization)
+                .map_err(plonk::Error::Transcript)?;
+        }
         plonk::create_proof(
             &pk.params,
             &pk.pk,
@@ -89,15 +351,97 @@ impl Proof {
             &mut rng,
             &mut transcript,
         )?;
-        Ok(Proof(transcript.finalize()))
+        let proof = transcript.finalize();
+
+        // flags a regression of the transcript or of the encoding as soon as the proof is created
+        #[cfg(feature = "check-proof-size")]
+        {
+            let expected = Self::get_expected_proof_size(circuits, typed_instances, pk.k);
+            if proof.len() != expected {
+                println!(
+                    "!the proof has [{}] bytes, expected [{}]",
+                    proof.len(),
+                    expected
+                );
+            }
+        }
+
+        Ok(Proof(proof, Some(pk.fingerprint), Some(pk.circuit_hash)))
     }
 
+    // the same inputs and seed yield byte-identical proofs (the rng is the only randomness of
+    // create), prefer create with OsRng unless the proofs are to be cached or deduplicated
+    pub fn create_deterministic<T: Default + Clone + ICConfig>(
+        pk: &ProvingKey,
+        circuits: &[ICCircuit<T>],
+        instances: &[Instance<T>],
+        seed: [u8; 32],
+    ) -> Result<Self, plonk::Error> {
+        Self::create(pk, circuits, instances, StdRng::from_seed(seed))
+    }
+
+    // one proof per circuit, each is verified independently with its own instance
+    pub fn create_separate<T: Default + Clone + ICConfig>(
+        pk: &ProvingKey,
+        circuits: &[ICCircuit<T>],
+        instances: &[Instance<T>],
+        mut rng: impl RngCore,
+    ) -> Result<Vec<Self>, plonk::Error> {
+        if circuits.len() != instances.len() {
+            println!(
+                "![{}] circuits but [{}] instances",
+                circuits.len(),
+                instances.len()
+            );
+            return Err(plonk::Error::InvalidInstances);
+        }
+
+        circuits
+            .iter()
+            .zip(instances.iter())
+            .map(|(circuit, instance)| {
+                Self::create(
+                    pk,
+                    std::slice::from_ref(circuit),
+                    std::slice::from_ref(instance),
+                    &mut rng,
+                )
+            })
+            .collect()
+    }
+
+    // the proof bytes do not bind the instances (they are absorbed into the transcript when
+    // verifying only), so the instances must always be supplied, see instance_digest
     pub fn verify<T: ICConfig>(
         &self,
         vk: &VerifyingKey,
         instances: &[Instance<T>],
     ) -> Result<(), plonk::Error> {
-        let instances: Vec<_> = instances.iter().map(|i| i.to_halo2_instance()).collect();
+        if !self.is_compatible(vk) {
+            println!(
+                "!the proving key and the verifying key are built with different configurations"
+            );
+            return Err(plonk::Error::ConstraintSystemFailure);
+        }
+        if !self.is_for_circuit(vk) {
+            println!("!the proof is created for a different circuit");
+            return Err(plonk::Error::ConstraintSystemFailure);
+        }
+
+        // malformed instances are rejected before the verifier runs
+        check_instances(vk, instances).map_err(|e| {
+            println!("!{}", e);
+            plonk::Error::InvalidInstances
+        })?;
+
+        let instances = instances
+            .iter()
+            .map(|i| i.to_checked_halo2_instance())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                println!("!{}", e);
+                plonk::Error::InvalidInstances
+            })?;
         let instances: Vec<Vec<_>> = instances
             .iter()
             .map(|i| i.iter().map(|c| &c[..]).collect())
@@ -105,12 +449,76 @@ impl Proof {
         let instances: Vec<_> = instances.iter().map(|i| &i[..]).collect();
 
         let strategy = SingleVerifier::new(&vk.params);
-        let mut transcript = Blake2bRead::init(&self.0[..]);
+        let mut transcript = Blake2bRead::<_, vesta::Affine, Challenge255<_>>::init(&self.0[..]);
+        if let Some(personalization) = vk.transcript_personalization {
+            transcript
+                .common_scalar(personalization)
+                .map_err(plonk::Error::Transcript)?;
+        }
         plonk::verify_proof(&vk.params, &vk.vk, strategy, &instances, &mut transcript)
     }
 
+    // verifies the proofs one by one as they are yielded (see verify), without collecting them,
+    // returns the number of the proofs verified, or the index of the first failing one
+    pub fn verify_stream<T, I>(vk: &VerifyingKey, proofs: I) -> Result<usize, (usize, VerifyError)>
+    where
+        T: Default + Clone + ICConfig,
+        I: Iterator<Item = (Proof, Vec<Instance<T>>)>,
+    {
+        let mut n = 0;
+        for (proof, instances) in proofs {
+            verify(vk, &instances, &proof).map_err(|e| (n, e))?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    // a proof from bytes does not know its proving key, it is compatible with any verifying key
     pub fn new(bytes: Vec<u8>) -> Self {
-        Proof(bytes)
+        Proof(bytes, None, None)
+    }
+
+    // the proof bytes after the header of the format version and the circuit hash (zeros if
+    // unknown, which from_bytes rejects, so a proof of Proof::new is kept as its raw bytes). The transcript already writes every point compressed (32 bytes,
+    // the x-coordinate with the sign of y), so there is no more compact encoding of the proof
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PROOF_HEADER_SIZE + self.0.len());
+        bytes.push(PROOF_FORMAT_VERSION);
+        bytes.extend_from_slice(&self.2.unwrap_or([0u8; 32]));
+        bytes.extend_from_slice(&self.0);
+        bytes
+    }
+
+    // the circuit hash of the header is checked against the verifying key by verify. A zero hash
+    // is rejected, otherwise a proof would skip the check by zeroing its header
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VerifyError> {
+        if bytes.len() < PROOF_HEADER_SIZE {
+            return Err(VerifyError::ProofSize(PROOF_HEADER_SIZE, bytes.len()));
+        }
+        if bytes[0] != PROOF_FORMAT_VERSION {
+            return Err(VerifyError::ProofVersion(bytes[0]));
+        }
+
+        let circuit_hash: [u8; 32] = bytes[1..PROOF_HEADER_SIZE].try_into().unwrap();
+        if circuit_hash == [0u8; 32] {
+            return Err(VerifyError::NoCircuitHash);
+        }
+        Ok(Proof(
+            bytes[PROOF_HEADER_SIZE..].to_vec(),
+            None,
+            Some(circuit_hash),
+        ))
+    }
+
+    pub fn is_compatible(&self, vk: &VerifyingKey) -> bool {
+        self.1
+            .map_or(true, |fingerprint| fingerprint == vk.fingerprint)
+    }
+
+    // whether the proof is created with a proving key of the circuit of vk, true if unknown
+    pub fn is_for_circuit(&self, vk: &VerifyingKey) -> bool {
+        self.2
+            .map_or(true, |circuit_hash| circuit_hash == vk.circuit_hash)
     }
 
     pub fn get_expected_proof_size<T: Default + Clone + ICConfig>(
@@ -126,6 +534,180 @@ impl Proof {
     }
 }
 
+// a rough estimate (in bytes) of the peak allocation of Proof::create, every column is held as
+// lagrange values, coefficients and extended (by the degree) values. The fixed columns and the
+// selectors are in the proving key, the advice and instance columns and the lookups (5
+// polynomials each) are per circuit
+pub fn estimate_proving_memory<T: Default + Clone + ICConfig>(
+    k: u32,
+    num_circuits: usize,
+) -> usize {
+    let stats = CircuitStats::build::<T>();
+    let n = 1usize << k;
+    let extended_n = n * stats.degree.saturating_sub(1).max(1).next_power_of_two();
+    let polynomial = (2 * n + extended_n) * 32;
+
+    let shared = stats.fixed_columns + stats.selectors;
+    let per_circuit = stats.advice_columns + stats.instance_columns + 5 * stats.lookups;
+    (shared + num_circuits * per_circuit) * polynomial
+}
+
+// the hash of the verifying key (by its debug representation, which covers the constraint system,
+// the fixed commitments and the permutation) and of the configuration fingerprint.
+// The debug representation is not a stable encoding, it may change with the halo2 version (or
+// the pasta_curves version), so the hash only identifies a circuit among builds of one version:
+// a proof (whose header carries the hash) is rejected by a verifying key of an upgraded build
+fn circuit_hash(fingerprint: &[u8; 32], vk: &plonk::VerifyingKey<vesta::Affine>) -> [u8; 32] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2ex_circuit_")
+        .to_state()
+        .update(fingerprint)
+        .update(format!("{:?}", vk).as_bytes())
+        .finalize();
+    hash.as_bytes().try_into().unwrap()
+}
+
+// the hash of k and the circuit hash, which covers the fixed and the permutation commitments of
+// the verifying key (by the params they are committed with), so the proving key is not hashed:
+// it is derived from the verifying key and the circuit only, and formatting its polynomials
+// would cost far more than the keygen of a realistic k
+fn key_fingerprint(k: u32, circuit_hash: &[u8; 32]) -> [u8; 32] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2ex_pk_print")
+        .to_state()
+        .update(&k.to_le_bytes())
+        .update(circuit_hash)
+        .finalize();
+    hash.as_bytes().try_into().unwrap()
+}
+
+// the domains whose configuration the keys of ICCircuit<T> depend on
+fn referenced_domains<T: Default + Clone + ICConfig>() -> BTreeSet<String> {
+    ICCircuit::<T>::configure(&mut plonk::ConstraintSystem::default()).referenced_domains()
+}
+
+// the transcript personalization as a scalar to be absorbed
+fn transcript_personalization(personalization: &[u8]) -> vesta::Scalar {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(64)
+        .personal(b"halo2ex_transcri")
+        .hash(personalization);
+    vesta::Scalar::from_bytes_wide(hash.as_array())
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    NoInstance,
+    InstanceLength(usize, usize), // length, max length by k and the blinding rows of the verifying key
+    Instance(usize, InstanceError), // instance index, error
+    ProofSize(usize, usize),      // expected, actual
+    KeyMismatch,
+    CircuitMismatch,
+    ProofVersion(u8), // the format version of the header
+    NoCircuitHash,    // the header of the proof bytes has a zero circuit hash
+    InvalidProof(plonk::Error),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::NoInstance => write!(f, "no instance"),
+            VerifyError::InstanceLength(len, max) => {
+                write!(f, "instance has [{}] rows, at most [{}]", len, max)
+            }
+            VerifyError::Instance(i, e) => write!(f, "instance[{}]: {}", i, e),
+            VerifyError::ProofSize(expected, actual) => {
+                write!(f, "proof has [{}] bytes, expected [{}]", actual, expected)
+            }
+            VerifyError::KeyMismatch => write!(
+                f,
+                "the proving key and the verifying key are built with different configurations"
+            ),
+            VerifyError::CircuitMismatch => {
+                write!(f, "the proof is created for a different circuit")
+            }
+            VerifyError::ProofVersion(version) => write!(
+                f,
+                "proof format version [{}], expected [{}]",
+                version, PROOF_FORMAT_VERSION
+            ),
+            VerifyError::NoCircuitHash => write!(f, "the proof header has no circuit hash"),
+            VerifyError::InvalidProof(e) => write!(f, "invalid proof: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+// cheap checks of the instances against T::get_instance_order and the rows of the verifying
+// key, no cryptographic work is done
+pub fn check_instances<T: ICConfig>(
+    vk: &VerifyingKey,
+    instances: &[Instance<T>],
+) -> Result<(), VerifyError> {
+    if instances.is_empty() {
+        return Err(VerifyError::NoInstance);
+    }
+
+    let len = T::get_instance_order().len();
+    let max = (1usize << vk.k).saturating_sub(vk.stats.blinding_rows);
+    if len > max {
+        return Err(VerifyError::InstanceLength(len, max));
+    }
+
+    for (i, instance) in instances.iter().enumerate() {
+        instance.check().map_err(|e| VerifyError::Instance(i, e))?;
+    }
+    Ok(())
+}
+
+// a digest of the instances (padded as verified), to record which public inputs a proof is
+// verified with, e.g. in audit logs alongside the result of verify
+pub fn instance_digest<T: ICConfig>(instances: &[Instance<T>]) -> [u8; 32] {
+    let mut state = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2ex_instance")
+        .to_state();
+    state.update(&(instances.len() as u64).to_le_bytes());
+    for instance in instances {
+        for column in instance.to_halo2_instance() {
+            state.update(&(column.len() as u64).to_le_bytes());
+            for value in column {
+                state.update(value.to_repr().as_ref());
+            }
+        }
+    }
+    state.finalize().as_bytes().try_into().unwrap()
+}
+
+// checks the instances and the proof size against the expected one before verifying the proof
+pub fn verify<T: Default + Clone + ICConfig>(
+    vk: &VerifyingKey,
+    instances: &[Instance<T>],
+    proof: &Proof,
+) -> Result<(), VerifyError> {
+    check_instances(vk, instances)?;
+
+    if !proof.is_compatible(vk) {
+        return Err(VerifyError::KeyMismatch);
+    }
+    if !proof.is_for_circuit(vk) {
+        return Err(VerifyError::CircuitMismatch);
+    }
+
+    let expected_size =
+        Proof::get_expected_proof_size(&[ICCircuit::<T>::default()], instances, vk.k);
+    if proof.as_ref().len() != expected_size {
+        return Err(VerifyError::ProofSize(expected_size, proof.as_ref().len()));
+    }
+
+    proof
+        .verify(vk, instances)
+        .map_err(|e| VerifyError::InvalidProof(e))
+}
+
 #[cfg(feature = "dev-graph")]
 pub fn print_ic_circuit<T: Default + Clone + ICConfig>(title: &str, k: u32) {
     use plotters::prelude::*;
@@ -1,22 +1,251 @@
-use group::Curve;
+use ff::Field;
+use group::{Curve, Group};
 use pasta_curves::pallas;
 
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter},
-    plonk,
+    plonk::{self, Advice, Column, ConstraintSystem, Expression, Selector},
+    poly::Rotation,
 };
 
 use halo2_gadgets::ecc;
 
 use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 
 use super::algo::*;
 use super::base::*;
+use crate::base;
 use crate::consts::*;
 use crate::domains::*;
 use crate::halo2api;
 use crate::types::*;
 
+// is_zero = 1 if value == 0 else 0, constrained by
+// value * inv == 1 - is_zero and value * is_zero == 0
+pub(crate) fn configure_is_zero(
+    meta: &mut ConstraintSystem<pallas::Base>,
+    advices: &[Column<Advice>; 10],
+) -> Selector {
+    let q = meta.selector();
+    meta.create_gate("(ICCircuit::configure) create_gate: [is_zero]", |meta| {
+        let q = meta.query_selector(q);
+        let value = meta.query_advice(advices[0], Rotation::cur());
+        let inv = meta.query_advice(advices[1], Rotation::cur());
+        let is_zero = meta.query_advice(advices[2], Rotation::cur());
+        let one = Expression::Constant(pallas::Base::one());
+
+        vec![
+            q.clone() * (value.clone() * inv - (one - is_zero.clone())),
+            q * value * is_zero,
+        ]
+    });
+    q
+}
+
+pub(crate) fn assign_is_zero(
+    layouter: &mut impl Layouter<pallas::Base>,
+    config: &ConfigData,
+    name: &String,
+    cell: &AssignedCell<pallas::Base, pallas::Base>,
+    _debug_info: &mut Option<BTreeMap<String, Vec<String>>>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    assert_synthesize_error!(
+        config.is_zero_q.is_some(),
+        &format!(
+            "assign_is_zero: [{}]: the is_zero gate is not configured",
+            name
+        )
+    );
+    let q = config.is_zero_q.unwrap();
+    let advices = &config.advices;
+
+    let desc = format!("assign_is_zero: [{}]", name);
+    layouter.assign_region(
+        || base::region_name(&desc),
+        |mut region| {
+            q.enable(&mut region, 0)?;
+
+            let value = halo2api::copy_advice(
+                cell,
+                &mut region,
+                || "is_zero value",
+                advices,
+                0,
+                0,
+                (_debug_info, &format!("[is_zero]:  {}", name)),
+            )?;
+            let value = value.value().cloned();
+
+            halo2api::assign_advice(
+                &mut region,
+                || "is_zero inv",
+                advices,
+                1,
+                0,
+                || {
+                    value
+                        .map(|v| v.invert().unwrap_or(pallas::Base::zero()))
+                        .ok_or(plonk::Error::Synthesis)
+                },
+                (_debug_info, &format!("[is_zero]:  inv_{}", name)),
+            )?;
+            halo2api::assign_advice(
+                &mut region,
+                || "is_zero",
+                advices,
+                2,
+                0,
+                || {
+                    value
+                        .map(|v| pallas::Base::from(bool::from(v.is_zero()) as u64))
+                        .ok_or(plonk::Error::Synthesis)
+                },
+                (_debug_info, &format!("[is_zero]:  {}", name)),
+            )
+        },
+    )
+}
+
+// c = a + b or c = a * b of the cells of an unnamed item, constrained by q_add * (a + b - c) and
+// q_mul * (a * b - c), the named ones are constrained by the gates of their cells
+pub(crate) fn configure_arith(
+    meta: &mut ConstraintSystem<pallas::Base>,
+    advices: &[Column<Advice>; 10],
+) -> (Selector, Selector) {
+    let q_add = meta.selector();
+    let q_mul = meta.selector();
+    meta.create_gate("(ICCircuit::configure) create_gate: [arith]", |meta| {
+        let q_add = meta.query_selector(q_add);
+        let q_mul = meta.query_selector(q_mul);
+        let a = meta.query_advice(advices[0], Rotation::cur());
+        let b = meta.query_advice(advices[1], Rotation::cur());
+        let c = meta.query_advice(advices[2], Rotation::cur());
+
+        vec![
+            q_add * (a.clone() + b.clone() - c.clone()),
+            q_mul * (a * b - c),
+        ]
+    });
+    (q_add, q_mul)
+}
+
+pub(crate) fn assign_arith(
+    layouter: &mut impl Layouter<pallas::Base>,
+    config: &ConfigData,
+    operator: &String,
+    a: &AssignedCell<pallas::Base, pallas::Base>,
+    b: &AssignedCell<pallas::Base, pallas::Base>,
+    desc: &String,
+    _debug_info: &mut Option<BTreeMap<String, Vec<String>>>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    assert_synthesize_error!(
+        config.arith_qs.is_some(),
+        &format!("assign_arith: [{}]: the arith gate is not configured", desc)
+    );
+    let (q_add, q_mul) = config.arith_qs.unwrap();
+    let (q, c) = match operator.as_str() {
+        "add" => (q_add, a.value().zip(b.value()).map(|(a, b)| a + b)),
+        "mul" => (q_mul, a.value().zip(b.value()).map(|(a, b)| a * b)),
+        _ => {
+            assert_synthesize_error_and_panic!(
+                false,
+                &format!("assign_arith: [{}]: invalid operator [{}]", desc, operator)
+            );
+        }
+    };
+    let advices = &config.advices;
+
+    let region_desc = format!("assign_arith: [{}]", desc);
+    layouter.assign_region(
+        || base::region_name(&region_desc),
+        |mut region| {
+            q.enable(&mut region, 0)?;
+
+            halo2api::copy_advice(
+                a,
+                &mut region,
+                || "arith a",
+                advices,
+                0,
+                0,
+                (_debug_info, &format!("[arith]:  a of {}", desc)),
+            )?;
+            halo2api::copy_advice(
+                b,
+                &mut region,
+                || "arith b",
+                advices,
+                1,
+                0,
+                (_debug_info, &format!("[arith]:  b of {}", desc)),
+            )?;
+            halo2api::assign_advice(
+                &mut region,
+                || "arith c",
+                advices,
+                2,
+                0,
+                || c.ok_or(plonk::Error::Synthesis),
+                (_debug_info, &format!("[arith]:  {}", desc)),
+            )
+        },
+    )
+}
+
+// sign of the v_net of a value, constrained by sign^2 == 1
+pub(crate) fn configure_sign_check(
+    meta: &mut ConstraintSystem<pallas::Base>,
+    advices: &[Column<Advice>; 10],
+) -> Selector {
+    let q = meta.selector();
+    meta.create_gate("(ICCircuit::configure) create_gate: [sign]", |meta| {
+        let q = meta.query_selector(q);
+        let sign = meta.query_advice(advices[0], Rotation::cur());
+        let one = Expression::Constant(pallas::Base::one());
+
+        vec![q * (sign.clone() * sign - one)]
+    });
+    q
+}
+
+pub(crate) fn assign_sign_check(
+    layouter: &mut impl Layouter<pallas::Base>,
+    config: &ConfigData,
+    name: &String,
+    cell: &AssignedCell<pallas::Base, pallas::Base>,
+    _debug_info: &mut Option<BTreeMap<String, Vec<String>>>,
+) -> Result<(), plonk::Error> {
+    assert_synthesize_error!(
+        config.sign_q.is_some(),
+        &format!(
+            "assign_sign_check: [{}]: the sign gate is not configured",
+            name
+        )
+    );
+    let q = config.sign_q.unwrap();
+    let advices = &config.advices;
+
+    let desc = format!("assign_sign_check: [{}]", name);
+    layouter.assign_region(
+        || base::region_name(&desc),
+        |mut region| {
+            q.enable(&mut region, 0)?;
+
+            halo2api::copy_advice(
+                cell,
+                &mut region,
+                || "sign",
+                advices,
+                0,
+                0,
+                (_debug_info, &format!("[sign]:  {}", name)),
+            )?;
+            Ok(())
+        },
+    )
+}
+
 pub(crate) fn assign_region(
     layouter: &mut impl Layouter<pallas::Base>,
     config: &ConfigData,
@@ -28,8 +257,14 @@ pub(crate) fn assign_region(
 ) -> Result<BTreeMap<String, Option<AssignedCell<pallas::Base, pallas::Base>>>, plonk::Error> {
     let mut assginedcell_values = BTreeMap::new();
     let gate = &config.gates[gate_index];
-    let state = gate_states.get_mut(&gate.name);
+    let state = gate_states.get_mut(gate.name.as_ref());
     if state.is_some() {
+        // e.g. a gate of a group assigned, the cells already assigned are returned
+        for name in field_values.keys() {
+            if let Some((v, _)) = cell_values.get(name) {
+                assginedcell_values.insert(name.clone(), v.clone());
+            }
+        }
         return Ok(assginedcell_values);
     }
 
@@ -38,11 +273,50 @@ pub(crate) fn assign_region(
     let primary = &config.primary;
     let instance_info = &config.instance_info;
 
+    // the gates sharing the selector are assigned in the same region
+    let group = (0..config.gates.len())
+        .filter(|i| config.qs[*i] == *q)
+        .map(|i| &config.gates[i])
+        .collect::<Vec<_>>();
+
+    // the group is assigned once the inputs of all its gates are available, the fields computed
+    // before are witnessed in their own regions and copied into the group region later
+    let is_group_ready = group
+        .iter()
+        .flat_map(|gate| gate.cells.iter())
+        .filter(|cell| cell.celltype == CellType::Input)
+        .all(|cell| field_values.contains_key(&cell.name) || cell_values.contains_key(&cell.name));
+    if !is_group_ready && group.len() > 1 {
+        for (name, field) in field_values {
+            let desc = format!("assign_region: witness[{}]", name);
+            let v = layouter.assign_region(
+                || base::region_name(&desc),
+                |mut region| {
+                    halo2api::assign_advice(
+                        &mut region,
+                        || &desc,
+                        advices,
+                        0,
+                        0,
+                        || field.ok_or(plonk::Error::Synthesis),
+                        (_debug_info, &format!("[{}]:  {}", gate.name, name)),
+                    )
+                },
+            )?;
+            cell_values.insert(name.clone(), (Some(v.clone()), None));
+            assginedcell_values.insert(name.clone(), Some(v));
+        }
+        return Ok(assginedcell_values);
+    }
+
     let desc = format!("assign_region: [{}]", gate.name);
     layouter.assign_region(
-        || &desc,
+        || base::region_name(&desc),
         |mut region| {
-            for cell in &gate.cells {
+            for (gate, cell) in group
+                .iter()
+                .flat_map(|gate| gate.cells.iter().map(move |cell| (gate, cell)))
+            {
                 let row = match cell.row {
                     RowType::Cur => 0,
                     RowType::Next => 1,
@@ -119,7 +393,9 @@ pub(crate) fn assign_region(
         },
     )?;
 
-    gate_states.insert(gate.name.clone(), true);
+    for gate in group {
+        gate_states.insert(gate.name.to_string(), true);
+    }
     Ok(assginedcell_values)
 }
 
@@ -133,11 +409,23 @@ pub(crate) fn compute_and_constraint(
     cell_values: &mut CellValues,
     cell_info: &BTreeMap<String, (String, CellInfo, usize)>,
     context: &mut ICContext,
+    outputs: &Option<Arc<Mutex<ComputedOutputs>>>,
 ) -> Result<(), plonk::Error> {
+    // the values are none in the measurement pass of the floor planner, they are not recorded
+    let record_instance = |name: &str, cell: &AssignedCell<pallas::Base, pallas::Base>| {
+        if let (Some(outputs), Some(v)) = (outputs, cell.value()) {
+            outputs
+                .lock()
+                .unwrap()
+                .instances
+                .insert(name.to_string(), *v);
+        }
+    };
+
     for i in config.gates.len()..config.algos.len() {
         let algo = &config.algos[i][0];
 
-        if algo.name.trim() == algo_type {
+        if algo.name == algo_type {
             let (_operand, _scalar) = algo.compute(
                 layouter,
                 ecc_chip,
@@ -149,6 +437,8 @@ pub(crate) fn compute_and_constraint(
                 context,
             )?;
 
+            // a point result is published with both coordinates if <name>_x is in the instance
+            // order, with the extracted x-coordinate only if <name> is, see Instance::set_point
             let constraint_name = algo.items[algo.items.len() - 1].1.name.clone(); //name fromt the last item
             let (constraint_name, instance_name_y, is_two) =
                 if config.instance_info.contains_key(&constraint_name) {
@@ -162,10 +452,24 @@ pub(crate) fn compute_and_constraint(
                     }
                 };
 
+            if let (Some(outputs), Operand::Point(Some(point))) = (outputs, &_operand) {
+                let point = point.inner();
+                if let (Some(x), Some(y)) = (point.x().value(), point.y().value()) {
+                    outputs
+                        .lock()
+                        .unwrap()
+                        .points
+                        .insert(algo.items[algo.items.len() - 1].1.name.clone(), (*x, *y));
+                }
+            }
+
             if config.instance_info.contains_key(&constraint_name) {
                 match _operand {
                     Operand::Point(point) => {
                         if is_two {
+                            let p = point.as_ref().unwrap().inner();
+                            record_instance(&constraint_name, &p.x());
+                            record_instance(&instance_name_y, &p.y());
                             halo2api::constrain_instance(
                                 layouter,
                                 &point.clone().unwrap().inner().x().cell(),
@@ -181,9 +485,11 @@ pub(crate) fn compute_and_constraint(
                                 (context.0, &instance_name_y),
                             )?;
                         } else {
+                            let x = point.unwrap().extract_p().inner().clone();
+                            record_instance(&constraint_name, &x);
                             halo2api::constrain_instance(
                                 layouter,
-                                &point.unwrap().extract_p().inner().cell(),
+                                &x.cell(),
                                 &config.primary,
                                 config.instance_info[&constraint_name],
                                 (context.0, &constraint_name),
@@ -191,9 +497,11 @@ pub(crate) fn compute_and_constraint(
                         }
                     }
                     Operand::Cell(cell) => {
+                        let cell = cell.unwrap();
+                        record_instance(&constraint_name, &cell);
                         halo2api::constrain_instance(
                             layouter,
-                            &cell.unwrap().cell(),
+                            &cell.cell(),
                             &config.primary,
                             config.instance_info[&constraint_name],
                             (context.0, &constraint_name),
@@ -206,18 +514,28 @@ pub(crate) fn compute_and_constraint(
 
                 match _operand {
                     Operand::Point(Some(point)) => {
+                        assert_synthesize_error!(
+                            constraint_points[&constraint_name]
+                                .map_or(true, |p| !bool::from(p.is_identity())),
+                            &format!(
+                                "[constraint] point[{}] is the identity, should be NIPoint",
+                                constraint_name
+                            )
+                        );
+
                         let desc = format!("witness [{}]", constraint_name);
 
                         let p = halo2_gadgets::ecc::NonIdentityPoint::new(
                             ecc_chip.clone(),
-                            layouter.namespace(|| &desc),
+                            layouter.namespace(|| base::region_name(&desc)),
                             constraint_points[&constraint_name].map(|p| p.to_affine()),
                         )?;
 
                         let desc = &format!("[{}] equality", constraint_name);
-                        point.constrain_equal(layouter.namespace(|| desc), &p)?;
+                        point
+                            .constrain_equal(layouter.namespace(|| base::region_name(desc)), &p)?;
 
-                        #[cfg(feature = "debug")]
+                        #[cfg(feature = "debug-info")]
                         if context.0.is_some() {
                             context
                                 .0
@@ -1,12 +1,45 @@
 #![allow(dead_code)]
 
+use pasta_curves::{arithmetic::FieldExt, pallas};
+
+// q = 2^254 + T_Q is the order of pallas::Scalar, p = 2^254 + T_P is the order of pallas::Base
 pub(crate) const T_Q: u128 = 45560315531506369815346746415080538113;
 pub(crate) const T_P: u128 = 45560315531419706090280762371685220353;
 
-pub const FILED_SIZE: usize = 255;
+pub const FIELD_SIZE: usize = 255;
+// the misspelled name of FIELD_SIZE, kept for compatibility
+#[deprecated(note = "use FIELD_SIZE")]
+pub const FILED_SIZE: usize = FIELD_SIZE;
+
+/// T_P as a base field element, as used by the built-in canonicity checks.
+///
+/// A 255-bit decomposed value is accepted as canonical iff it is < 2^254 + 2^126
+/// and, when bit 254 is set, its low 130 (or 140) bits of `value + 2^130 - T_P`
+/// (`2^140` if the low part spans two pieces) are zero.
+pub fn t_p_base() -> pallas::Base {
+    pallas::Base::from_u128(T_P)
+}
+
+/// T_Q as a base field element, the canonicity bound for a 255-bit decomposition of a
+/// pallas::Scalar value witnessed in the base field, e.g. a scalar for a variable-base mul.
+///
+/// Such a value is canonical iff it is < q = 2^254 + T_Q. The high part is checked as for
+/// [`t_p_base`], the low 130 (or 140) bits are offset by `2^130 - T_Q` instead of `2^130 - T_P`.
+pub fn t_q_base() -> pallas::Base {
+    pallas::Base::from_u128(T_Q)
+}
 
+pub const L_VALUE: usize = 64;
 pub const L_MERKLE: usize = 255;
 pub const MERKLE_DEPTH: usize = 32;
+// the max message bits of one sinsemilla commit, K * C
+pub const MAX_COMMIT_BITS: usize = 10 * 253;
+
+// the default max degree of a gate polynomial (with its selector) of ICCircuit
+pub const MAX_GATE_DEGREE: usize = 16;
+
+// the largest k tried by ICCircuit::compute_outputs
+pub const MAX_MOCK_K: u32 = 18;
 
 pub const DOMAIN_MERKLECRH: &'static str = "domain_merklecrh";
 
@@ -17,9 +50,13 @@ pub const ATTRIBUTE_FIELD: &'static str = "Field";
 pub const ATTRIBUTE_POINT: &'static str = "Point";
 pub const ATTRIBUTE_NIPOINT: &'static str = "NIPoint";
 pub const ATTRIBUTE_SCALAR: &'static str = "Scalar";
+// commit input attribute, the input is range checked to its width
+pub const ATTRIBUTE_RANGE_CHECK: &'static str = "RangeCheck";
 // intermedium attributes
 pub const ATTRIBUTE_CELL: &'static str = "Cell";
 pub const ATTRIBUTE_COMMIT_CELL: &'static str = "CommitCell";
+// the named result of an item computed by an earlier constraint, of the type it is computed to
+pub const ATTRIBUTE_COMPUTED: &'static str = "Computed";
 
 // signs
 pub const SIGN_OF_OLD_VALUE: &'static str = "old_";
@@ -29,6 +66,8 @@ pub const SIGN_OF_SIGN: &'static str = "sign_";
 pub const SIGN_OF_ANCHOR: &'static str = "anchor_";
 pub const SIGN_OF_X: &'static str = "_x";
 pub const SIGN_OF_Y: &'static str = "_y";
+// the prefix of the points kept in the operands to be reused, see AlgoItem::compute_two
+pub const SIGN_OF_MEMO: &'static str = "memo:";
 
 pub const SIGN_OF_CONSTRAINT: &'static str = "constraint";
 pub const SIGN_OF_CONSTRAINT_COMMIT: &'static str = "constraint-commit";
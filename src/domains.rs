@@ -1,4 +1,5 @@
 use ff::PrimeFieldBits;
+use group::Curve;
 use pasta_curves::{arithmetic::CurveAffine, group::ff::PrimeField, pallas};
 
 use halo2_gadgets::{
@@ -6,7 +7,7 @@ use halo2_gadgets::{
         chip::{BaseFieldElem, FixedPoint, FullScalar, ShortScalar},
         FixedPoints,
     },
-    primitives::sinsemilla::CommitDomain,
+    primitives::sinsemilla::{CommitDomain, K},
     sinsemilla::{CommitDomains, HashDomains},
     utilities::bitrange_subset,
 };
@@ -14,6 +15,7 @@ use halo2_gadgets::{
 use crate::base;
 use crate::consts::*;
 use crate::global;
+use crate::sinsemilla::config::MAX_PIECE_WIDTH;
 use crate::types::*;
 
 pub mod global_domain {
@@ -162,13 +164,47 @@ impl CommitDomains<pallas::Affine, DomainFixedBases, BaseHashDomains> for HashDo
     }
 }
 
+// the personalization of a commit as a field element and the number of K-bit words, zero padded
+// to times of K bits. It is the first message piece, so that commits of one domain can be told apart
+pub(crate) fn personalization_piece(personalization: &[u8]) -> Option<(pallas::Base, usize)> {
+    if personalization.len() == 0 {
+        return None;
+    }
+
+    assert!(
+        personalization.len() * 8 <= MAX_PIECE_WIDTH,
+        "[Sinsemilla] personalization must not be longer than [{}] bytes",
+        MAX_PIECE_WIDTH / 8
+    );
+
+    let mut bytes = [0u8; 32];
+    bytes[..personalization.len()].copy_from_slice(personalization);
+    let num_words = (personalization.len() * 8 + K - 1) / K;
+    Some((pallas::Base::from_repr(bytes).unwrap(), num_words))
+}
+
+pub(crate) fn personalization_bits(personalization: &[u8]) -> Vec<bool> {
+    personalization_piece(personalization)
+        .map(|(value, num_words)| {
+            value
+                .to_le_bits()
+                .iter()
+                .by_val()
+                .take(num_words * K)
+                .collect()
+        })
+        .unwrap_or(Vec::new())
+}
+
+// the personalization (see CommitSpec::personalization) is absorbed before the inputs, b"" for none
 pub fn compute_commit_value(
     is_short_commit: bool,
     commit_domain_name: &str,
+    personalization: &[u8],
     input_r: &pallas::Scalar,
     inputs: &CommitInputs,
 ) -> CommitResult {
-    let mut bits = Vec::new();
+    let mut bits = personalization_bits(personalization);
 
     let lsb = |y_lsb: pallas::Base| y_lsb == pallas::Base::one();
     for (_name, width, x, y) in inputs {
@@ -177,7 +213,7 @@ pub fn compute_commit_value(
                 .to_le_bits()
                 .iter()
                 .by_val()
-                .take(std::cmp::min(*width, FILED_SIZE)),
+                .take(std::cmp::min(*width, FIELD_SIZE)),
         );
 
         if y.is_some() {
@@ -203,3 +239,122 @@ pub fn compute_commit_value(
         CommitResult::Point(Some(p))
     }
 }
+
+// a commit without randomness, [0] R is the identity so the result is the hash point only
+pub fn compute_deterministic_commit_value(
+    is_short_commit: bool,
+    commit_domain_name: &str,
+    personalization: &[u8],
+    inputs: &CommitInputs,
+) -> CommitResult {
+    compute_commit_value(
+        is_short_commit,
+        commit_domain_name,
+        personalization,
+        &pallas::Scalar::zero(),
+        inputs,
+    )
+}
+
+// compute_commit_value of every independent commit (is_short_commit, commit domain name,
+// personalization, r, inputs) in the order of the commits, in parallel with the rayon feature
+pub fn compute_commit_values_batch(
+    commits: &[(bool, &str, &[u8], pallas::Scalar, CommitInputs)],
+) -> Vec<CommitResult> {
+    let compute = |commit: &(bool, &str, &[u8], pallas::Scalar, CommitInputs)| {
+        compute_commit_value(commit.0, commit.1, commit.2, &commit.3, &commit.4)
+    };
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        return commits.par_iter().map(compute).collect();
+    }
+    #[cfg(not(feature = "rayon"))]
+    commits.iter().map(compute).collect()
+}
+
+// the message bits of the inputs as committed by compute_commit_value
+fn commit_input_bits(input: &(String, usize, Option<pallas::Base>, Option<pallas::Base>)) -> usize {
+    std::cmp::min(input.1, FIELD_SIZE) + if input.3.is_some() { 1 } else { 0 }
+}
+
+// splits the inputs of a message longer than one commit into the inputs of chained commits, every
+// link but the first one reserves the room of the previous result (see compute_chained_commit_value)
+pub fn split_commit_inputs(
+    is_short_commit: bool,
+    personalization: &[u8],
+    inputs: &CommitInputs,
+) -> Vec<CommitInputs> {
+    let chain_bits = FIELD_SIZE + if is_short_commit { 0 } else { 1 };
+    let capacity = MAX_COMMIT_BITS - personalization_bits(personalization).len();
+
+    let mut links: Vec<CommitInputs> = vec![Vec::new()];
+    let mut bits = 0;
+    for input in inputs {
+        let input_bits = commit_input_bits(input);
+        assert!(
+            input_bits + chain_bits <= capacity,
+            "[Sinsemilla] the input [{}] is too long for a chained commit",
+            input.0
+        );
+
+        if bits + input_bits > capacity {
+            links.push(Vec::new());
+            bits = chain_bits;
+        }
+        bits += input_bits;
+        links.last_mut().unwrap().push(input.clone());
+    }
+    links
+}
+
+// commits a message longer than one commit Merkle-Damgard style: the result of every link is the
+// first input (named by the domain of the link) of the next one. In-circuit, every link is a
+// CommitSpec of get_commit_specs in the order of the chain, the first input of a link is the
+// output_name of the previous one, with a FIELD_SIZE wide input gate. Every link absorbs the same
+// personalization
+pub fn compute_chained_commit_value(
+    is_short_commit: bool,
+    personalization: &[u8],
+    links: &[(&str, pallas::Scalar, CommitInputs)], // (commit domain name, r, inputs)
+) -> CommitResult {
+    assert!(links.len() > 0, "[Sinsemilla] no link to commit");
+
+    let mut result: Option<(String, CommitResult)> = None;
+    for (commit_domain_name, input_r, inputs) in links {
+        let mut link_inputs = CommitInputs::new();
+        match result {
+            Some((name, CommitResult::X(x))) => link_inputs.push((name, FIELD_SIZE, x, None)),
+            Some((name, CommitResult::Point(p))) => {
+                let x = p.map(|p| *p.to_affine().coordinates().unwrap().x());
+                let y_lsb =
+                    p.map(|p| bitrange_subset(p.to_affine().coordinates().unwrap().y(), 0..1));
+                link_inputs.push((name, FIELD_SIZE, x, y_lsb));
+            }
+            None => {}
+        }
+        link_inputs.extend(inputs.iter().cloned());
+
+        let link_bits = personalization_bits(personalization).len()
+            + link_inputs.iter().map(commit_input_bits).sum::<usize>();
+        assert!(
+            link_bits <= MAX_COMMIT_BITS,
+            "[Sinsemilla] [{}] bits of the link [{}] exceed the commit capacity",
+            link_bits,
+            commit_domain_name
+        );
+
+        result = Some((
+            commit_domain_name.to_string(),
+            compute_commit_value(
+                is_short_commit,
+                commit_domain_name,
+                personalization,
+                input_r,
+                &link_inputs,
+            ),
+        ));
+    }
+    result.unwrap().1
+}
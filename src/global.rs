@@ -1,10 +1,12 @@
 use ff::PrimeField;
 use group::GroupEncoding;
-use halo2_proofs::arithmetic::CurveExt;
+use halo2_gadgets::ecc::chip::{constants::NUM_WINDOWS, H};
 use lazy_static::lazy_static;
 use pasta_curves::arithmetic::CurveAffine;
 use pasta_curves::pallas;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
 use std::sync::Mutex;
 
 use super::base;
@@ -27,9 +29,389 @@ lazy_static! {
         Mutex::new(HashMap::new());
     static ref FIXED_POINT_SHORTS: Mutex<HashMap<String, Option<DomainShort>>> =
         Mutex::new(HashMap::new());
+    static ref CONFIG_RECORDS: Mutex<BTreeMap<String, Vec<u8>>> = Mutex::new(BTreeMap::new());
+}
+
+// every config_* call is recorded (the values computed lazily are not), so that keys built
+// with different configurations can be told apart by config_fingerprint
+fn record_config(kind: &str, key: &str, value: Vec<u8>) {
+    CONFIG_RECORDS
+        .lock()
+        .unwrap()
+        .insert(format!("{}:{}", kind, key), value);
+}
+
+fn generator_bytes(generator: &Option<TGenerator>) -> Vec<u8> {
+    match generator {
+        Some((x, y)) => [&x[..], &y[..]].concat(),
+        None => Vec::new(),
+    }
+}
+
+fn zs_and_us_bytes(zs_and_us: &Option<TZsUs>) -> Vec<u8> {
+    match zs_and_us {
+        Some((zs, us)) => std::iter::empty()
+            .chain(zs.iter().flat_map(|z| z.to_le_bytes()))
+            .chain(us.iter().flatten().flatten().cloned())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+pub fn config_fingerprint() -> [u8; 32] {
+    fingerprint_records(CONFIG_RECORDS.lock().unwrap().iter())
+}
+
+// the fingerprint of the records of the domains only (e.g. ConfigData::referenced_domains), so
+// that a key is not told apart by the domains configured for other circuits meanwhile
+pub fn config_fingerprint_of(domains: &BTreeSet<String>) -> [u8; 32] {
+    let records = CONFIG_RECORDS.lock().unwrap();
+    fingerprint_records(records.iter().filter(|(record, value)| {
+        let (kind, key) = record.split_once(':').unwrap_or(("", ""));
+        let domain = match kind {
+            "fixedbasefull" | "fixedpointbasefield" | "fixedpointshort" => {
+                &value[..value.len().saturating_sub(USIZE_LEN)]
+            }
+            "domain_name" => &value[..],
+            _ => key.as_bytes(),
+        };
+        std::str::from_utf8(domain).map_or(false, |domain| domains.contains(domain))
+    }))
+}
+
+fn fingerprint_records<'a>(records: impl Iterator<Item = (&'a String, &'a Vec<u8>)>) -> [u8; 32] {
+    let mut state = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2ex_config__")
+        .to_state();
+    for (key, value) in records {
+        state.update(&(key.len() as u64).to_le_bytes());
+        state.update(key.as_bytes());
+        state.update(&(value.len() as u64).to_le_bytes());
+        state.update(value);
+    }
+
+    let mut fingerprint = [0u8; 32];
+    fingerprint.copy_from_slice(state.finalize().as_bytes());
+    fingerprint
+}
+
+const CONFIG_MAGIC: &[u8] = b"halo2ex_config";
+const USIZE_LEN: usize = std::mem::size_of::<usize>();
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+// saves all the config_* calls (as recorded for config_fingerprint), so that the prover and the
+// verifier can load the identical configuration by load_config
+pub fn save_config(writer: &mut impl Write) -> io::Result<()> {
+    let records = CONFIG_RECORDS.lock().unwrap().clone();
+
+    writer.write_all(CONFIG_MAGIC)?;
+    writer.write_all(&(records.len() as u64).to_le_bytes())?;
+    for (key, value) in &records {
+        write_bytes(writer, key.as_bytes())?;
+        write_bytes(writer, value)?;
+    }
+    Ok(())
+}
+
+// the bounds of a saved configuration, so that a corrupted length can not force an allocation
+const MAX_CONFIG_RECORDS: u64 = 1 << 16;
+const MAX_CONFIG_RECORD_LEN: u64 = 1 << 20;
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 8];
+    reader.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+    if len > MAX_CONFIG_RECORD_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("config record of [{}] bytes is too long", len),
+        ));
+    }
+
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// replays the config_* calls saved by save_config, config_fingerprint is then the same as the
+// one of the saved configuration. All the records are validated first, nothing is configured if
+// any of them is invalid
+pub fn load_config(reader: &mut impl Read) -> io::Result<()> {
+    let invalid = |key: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid config record [{}]", key),
+        )
+    };
+
+    let mut magic = vec![0u8; CONFIG_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != CONFIG_MAGIC {
+        return Err(invalid("magic"));
+    }
+
+    let mut len = [0u8; 8];
+    reader.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+    if len > MAX_CONFIG_RECORDS {
+        return Err(invalid("number of records"));
+    }
+    let mut records = BTreeMap::new();
+    for _ in 0..len {
+        let key = String::from_utf8(read_bytes(reader)?).map_err(|_| invalid("key"))?;
+        let value = read_bytes(reader)?;
+        records.insert(key, value);
+    }
+
+    // a generator is a point on the curve
+    let generator = |record: &str, value: &[u8]| match value.len() {
+        0 => Ok(None),
+        64 => {
+            let generator: TGenerator = (
+                value[..32].try_into().unwrap(),
+                value[32..].try_into().unwrap(),
+            );
+            let x: Option<pallas::Base> = pallas::Base::from_repr(generator.0).into();
+            let y: Option<pallas::Base> = pallas::Base::from_repr(generator.1).into();
+            match (x, y) {
+                (Some(x), Some(y)) if bool::from(pallas::Affine::from_xy(x, y).is_some()) => {
+                    Ok(Some(generator))
+                }
+                _ => Err(invalid(record)),
+            }
+        }
+        _ => Err(invalid(record)),
+    };
+    // the R of a domain as get_generator_r (or the one derived from the name) once loaded
+    let generator_r = |domain: &str| -> io::Result<pallas::Affine> {
+        let record = format!("generator_r:{}", domain);
+        let loaded = match records.get(&record) {
+            Some(value) => generator(&record, value)?,
+            None => None,
+        };
+        Ok(match loaded {
+            Some((x, y)) => pallas::Affine::from_xy(
+                pallas::Base::from_repr(x).unwrap(),
+                pallas::Base::from_repr(y).unwrap(),
+            )
+            .unwrap(),
+            None => base::generator_r(domain),
+        })
+    };
+
+    let mut calls: Vec<Box<dyn FnOnce()>> = Vec::new();
+    for (record, value) in &records {
+        let (kind, key) = record.split_once(':').ok_or_else(|| invalid(record))?;
+        let key = key.to_string();
+
+        let domain_and_windows = || {
+            if value.len() < USIZE_LEN {
+                return Err(invalid(record));
+            }
+            let (domain, num_windows) = value.split_at(value.len() - USIZE_LEN);
+            let domain = String::from_utf8(domain.to_vec()).map_err(|_| invalid(record))?;
+            let num_windows = usize::from_le_bytes(num_windows.try_into().unwrap());
+            if num_windows == 0 || num_windows > NUM_WINDOWS {
+                return Err(invalid(record));
+            }
+            Ok((domain, num_windows))
+        };
+        // the zs and us are of the R of the domain
+        let zs_and_us = || {
+            let window_len = 8 + 32 * H;
+            if value.len() % window_len != 0 {
+                return Err(invalid(record));
+            }
+            if value.is_empty() {
+                return Ok(None);
+            }
+
+            let num_windows = value.len() / window_len;
+            let (zs, us) = value.split_at(8 * num_windows);
+            let zs = zs
+                .chunks(8)
+                .map(|z| u64::from_le_bytes(z.try_into().unwrap()))
+                .collect::<TZs>();
+            let us = us
+                .chunks(32 * H)
+                .map(|u| {
+                    let mut window = [[0u8; 32]; H];
+                    for (i, coordinate) in u.chunks(32).enumerate() {
+                        window[i].copy_from_slice(coordinate);
+                    }
+                    window
+                })
+                .collect::<TUs>();
+            if !base::check_zs_and_us(&generator_r(&key)?, &zs, &us) {
+                return Err(invalid(record));
+            }
+            Ok(Some((zs, us)))
+        };
+
+        match kind {
+            "fixedbasefull" => {
+                let (domain, num_windows) = domain_and_windows()?;
+                calls.push(Box::new(move || {
+                    config_fixedbasefull(&key, &domain, num_windows)
+                }));
+            }
+            "fixedpointbasefield" => {
+                let (domain, num_windows) = domain_and_windows()?;
+                calls.push(Box::new(move || {
+                    config_fixedpointbasefield(&key, &domain, num_windows)
+                }));
+            }
+            "fixedpointshort" => {
+                let (domain, num_windows) = domain_and_windows()?;
+                calls.push(Box::new(move || {
+                    config_fixedpointshort(&key, &domain, num_windows)
+                }));
+            }
+            "generator_q" => {
+                let generator = generator(record, value)?;
+                calls.push(Box::new(move || config_generator_q(&key, &generator)));
+            }
+            "generator_r" => {
+                let generator = generator(record, value)?;
+                calls.push(Box::new(move || config_generator_r(&key, &generator)));
+            }
+            "zs_and_us" => {
+                let zs_and_us = zs_and_us()?;
+                calls.push(Box::new(move || config_zs_and_us(&key, &zs_and_us)));
+            }
+            "zs_and_us_short" => {
+                let zs_and_us = zs_and_us()?;
+                calls.push(Box::new(move || config_zs_and_us_short(&key, &zs_and_us)));
+            }
+            "domain_name" => {
+                let name = String::from_utf8(value.clone()).map_err(|_| invalid(record))?;
+                calls.push(Box::new(move || config_domain_name(&key, &name)));
+            }
+            "base_point" => {
+                let bp: [u8; 32] = value[..].try_into().map_err(|_| invalid(record))?;
+                if bool::from(pallas::Affine::from_bytes(&bp).is_none()) {
+                    return Err(invalid(record));
+                }
+                calls.push(Box::new(move || config_base_point(&key, &bp)));
+            }
+            _ => return Err(invalid(record)),
+        }
+    }
+
+    for call in calls {
+        call();
+    }
+    Ok(())
+}
+
+// the configured (by the config_* calls, not computed lazily) items of a domain
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct DomainStatus {
+    pub generator_q: bool,
+    pub generator_r: bool,
+    pub zs_and_us: bool,
+    pub zs_and_us_short: bool,
+    pub fixed_base: bool, // as the domain of any full, base field or short fixed base
+}
+
+impl DomainStatus {
+    pub fn is_configured(&self) -> bool {
+        *self != DomainStatus::default()
+    }
+}
+
+pub fn is_configured(domain: &str) -> DomainStatus {
+    let records = CONFIG_RECORDS.lock().unwrap();
+    let is_recorded = |kind: &str| records.contains_key(&format!("{}:{}", kind, domain));
+    let is_domain = |d: &String| d == domain;
+
+    DomainStatus {
+        generator_q: is_recorded("generator_q"),
+        generator_r: is_recorded("generator_r"),
+        zs_and_us: is_recorded("zs_and_us"),
+        zs_and_us_short: is_recorded("zs_and_us_short"),
+        fixed_base: FIXED_BASE_FULLS
+            .lock()
+            .unwrap()
+            .values()
+            .any(|v| v.as_ref().map_or(false, |v| is_domain(&v.domain)))
+            || FIXED_POINT_BASE_FIELDS
+                .lock()
+                .unwrap()
+                .values()
+                .any(|v| v.as_ref().map_or(false, |v| is_domain(&v.domain)))
+            || FIXED_POINT_SHORTS
+                .lock()
+                .unwrap()
+                .values()
+                .any(|v| v.as_ref().map_or(false, |v| is_domain(&v.domain))),
+    }
+}
+
+// the domains configured (by a generator, zs and us or a fixed base) but not
+// in the referenced ones, see ConfigData::referenced_domains
+pub fn unused_domains(referenced: &BTreeSet<String>) -> Vec<String> {
+    let mut configured = CONFIG_RECORDS
+        .lock()
+        .unwrap()
+        .keys()
+        .filter_map(|key| {
+            let (kind, domain) = key.split_once(':')?;
+            match kind {
+                "generator_q" | "generator_r" | "zs_and_us" | "zs_and_us_short" => {
+                    Some(domain.to_string())
+                }
+                _ => None,
+            }
+        })
+        .collect::<BTreeSet<_>>();
+    configured.extend(fixed_base_aliases().into_values());
+
+    configured.difference(referenced).cloned().collect()
+}
+
+// the domain of the fixed base (full, base field or short) configured as [alias]
+pub(crate) fn fixed_base_domain(alias: &str) -> Option<String> {
+    fixed_base_aliases().remove(alias)
+}
+
+fn fixed_base_aliases() -> BTreeMap<String, String> {
+    std::iter::empty()
+        .chain(fixed_base_domains(&FIXED_BASE_FULLS.lock().unwrap(), |v| {
+            &v.domain
+        }))
+        .chain(fixed_base_domains(
+            &FIXED_POINT_BASE_FIELDS.lock().unwrap(),
+            |v| &v.domain,
+        ))
+        .chain(fixed_base_domains(
+            &FIXED_POINT_SHORTS.lock().unwrap(),
+            |v| &v.domain,
+        ))
+        .collect()
+}
+
+// (alias, domain) of the configured fixed bases
+fn fixed_base_domains<V>(
+    map: &HashMap<String, Option<V>>,
+    domain: impl Fn(&V) -> &String,
+) -> Vec<(String, String)> {
+    map.iter()
+        .filter_map(|(alias, v)| v.as_ref().map(|v| (alias.clone(), domain(v).clone())))
+        .collect()
 }
 
 pub fn config_fixedbasefull(name: &str, domain: &str, num_windows: usize) {
+    record_config(
+        "fixedbasefull",
+        name,
+        [domain.as_bytes(), &num_windows.to_le_bytes()].concat(),
+    );
     FIXED_BASE_FULLS.lock().unwrap().insert(
         name.to_string(),
         Some(DomainFullWidth {
@@ -48,7 +430,101 @@ pub fn get_fixedbasefull(name: &str) -> Option<DomainFullWidth> {
     }
 }
 
+// validates the zs and us (of num_windows windows) against the generator
+fn check_fixed_base(
+    domain: &str,
+    num_windows: usize,
+    generator: &TGenerator,
+    zs: &TZs,
+    us: &TUs,
+) -> pallas::Affine {
+    let point = pallas::Affine::from_xy(
+        pallas::Base::from_repr(generator.0).unwrap(),
+        pallas::Base::from_repr(generator.1).unwrap(),
+    );
+    assert!(
+        bool::from(point.is_some()),
+        "[{}] generator is not on the curve",
+        domain
+    );
+    let point = point.unwrap();
+
+    assert!(
+        zs.len() == num_windows && us.len() == num_windows,
+        "[{}] zs[{}] or us[{}] does not match num_windows[{}]",
+        domain,
+        zs.len(),
+        us.len(),
+        num_windows
+    );
+    assert!(
+        base::check_zs_and_us(&point, zs, us),
+        "[{}] zs and us are not computed from the generator",
+        domain
+    );
+
+    point
+}
+
+// configures the alias, generator, zs and us (and optionally the base point named
+// base_point) of a full fixed base, all of them are validated before being configured
+pub fn config_full_fixed_base(
+    alias: &str,
+    domain: &str,
+    num_windows: usize,
+    generator: &TGenerator,
+    zs: &TZs,
+    us: &TUs,
+    base_point: Option<&str>,
+) {
+    let point = check_fixed_base(domain, num_windows, generator, zs, us);
+
+    config_fixedbasefull(alias, domain, num_windows);
+    config_generator(domain, &Some(*generator));
+    config_zs_and_us(domain, &Some((zs.clone(), us.clone())));
+    if let Some(name) = base_point {
+        config_base_point(name, &point.to_bytes());
+    }
+}
+
+// same as config_full_fixed_base, for a base field fixed base
+pub fn config_base_field_fixed_base(
+    alias: &str,
+    domain: &str,
+    num_windows: usize,
+    generator: &TGenerator,
+    zs: &TZs,
+    us: &TUs,
+) {
+    check_fixed_base(domain, num_windows, generator, zs, us);
+
+    config_fixedpointbasefield(alias, domain, num_windows);
+    config_generator(domain, &Some(*generator));
+    config_zs_and_us(domain, &Some((zs.clone(), us.clone())));
+}
+
+// same as config_full_fixed_base, for a short fixed base (with the short zs and us)
+pub fn config_short_fixed_base(
+    alias: &str,
+    domain: &str,
+    num_windows_short: usize,
+    generator: &TGenerator,
+    zs: &TZs,
+    us: &TUs,
+) {
+    check_fixed_base(domain, num_windows_short, generator, zs, us);
+
+    config_fixedpointshort(alias, domain, num_windows_short);
+    config_generator(domain, &Some(*generator));
+    config_zs_and_us_short(domain, &Some((zs.clone(), us.clone())));
+}
+
 pub fn config_fixedpointbasefield(name: &str, domain: &str, num_windows: usize) {
+    record_config(
+        "fixedpointbasefield",
+        name,
+        [domain.as_bytes(), &num_windows.to_le_bytes()].concat(),
+    );
     FIXED_POINT_BASE_FIELDS.lock().unwrap().insert(
         name.to_string(),
         Some(DomainBaseField {
@@ -68,6 +544,11 @@ pub fn get_fixedpointbasefield(name: &str) -> Option<DomainBaseField> {
 }
 
 pub fn config_fixedpointshort(name: &str, domain: &str, num_windows_short: usize) {
+    record_config(
+        "fixedpointshort",
+        name,
+        [domain.as_bytes(), &num_windows_short.to_le_bytes()].concat(),
+    );
     FIXED_POINT_SHORTS.lock().unwrap().insert(
         name.to_string(),
         Some(DomainShort {
@@ -87,6 +568,7 @@ pub fn get_fixedpointshort(name: &str) -> Option<DomainShort> {
 }
 
 pub fn config_generator_q(domain: &str, generator: &Option<TGenerator>) {
+    record_config("generator_q", domain, generator_bytes(generator));
     GENERATOR_Q_MAP
         .lock()
         .unwrap()
@@ -94,6 +576,7 @@ pub fn config_generator_q(domain: &str, generator: &Option<TGenerator>) {
 }
 
 pub fn config_generator_r(domain: &str, generator: &Option<TGenerator>) {
+    record_config("generator_r", domain, generator_bytes(generator));
     GENERATOR_R_MAP
         .lock()
         .unwrap()
@@ -122,6 +605,20 @@ pub(crate) fn get_generator_r(domain: &str) -> Option<TGenerator> {
     }
 }
 
+// derives Q and R of a commit domain from its name as CommitDomain::new(name) does,
+// Q = hash_to_curve("z.cash:SinsemillaQ")(name + "-M") and R = hash_to_curve(name + "-r")(&[]),
+// and computes the zs and us of R
+pub fn config_commit_domain_auto(name: &str, num_windows: usize) {
+    let q = base::generate_q_commit_domain(name);
+    let r = base::generate_r(name);
+    config_generator_q(name, &Some(q));
+    config_generator_r(name, &Some(r));
+
+    let zs_and_us = base::get_zs_and_us(base::point_to_affine(&r), num_windows);
+    let zs_and_us = base::convert_zs_us(zs_and_us.unwrap());
+    config_zs_and_us(name, &Some(zs_and_us));
+}
+
 pub fn is_exist_zs_and_us(domain: &str) -> bool {
     ZSUS_MAP.lock().unwrap().contains_key(domain)
 }
@@ -131,13 +628,23 @@ pub fn is_exist_zs_and_us_short(domain: &str) -> bool {
 }
 
 pub fn config_zs_and_us(domain: &str, zs_and_us: &Option<TZsUs>) {
+    record_config("zs_and_us", domain, zs_and_us_bytes(zs_and_us));
+    cache_zs_and_us(domain, zs_and_us);
+}
+
+pub fn config_zs_and_us_short(domain: &str, zs_and_us: &Option<TZsUs>) {
+    record_config("zs_and_us_short", domain, zs_and_us_bytes(zs_and_us));
+    cache_zs_and_us_short(domain, zs_and_us);
+}
+
+fn cache_zs_and_us(domain: &str, zs_and_us: &Option<TZsUs>) {
     ZSUS_MAP
         .lock()
         .unwrap()
         .insert(domain.to_string(), zs_and_us.clone());
 }
 
-pub fn config_zs_and_us_short(domain: &str, zs_and_us: &Option<TZsUs>) {
+fn cache_zs_and_us_short(domain: &str, zs_and_us: &Option<TZsUs>) {
     ZSUS_MAP_SHORT
         .lock()
         .unwrap()
@@ -181,7 +688,7 @@ pub(crate) fn u(domain: &str, num_windows: usize) -> TUs {
         let basepoint = generator(domain);
         let zs_and_us = base::get_zs_and_us(basepoint, num_windows);
         let zs_and_us = base::convert_zs_us(zs_and_us.unwrap());
-        config_zs_and_us(domain, &Some(zs_and_us));
+        cache_zs_and_us(domain, &Some(zs_and_us));
     }
 
     let zs_and_us = get_zs_and_us(domain).unwrap();
@@ -193,7 +700,7 @@ pub(crate) fn u_short(domain: &str, num_windows_short: usize) -> TUs {
         let basepoint = generator(domain);
         let zs_and_us_short = base::get_zs_and_us(basepoint, num_windows_short);
         let zs_and_us_short = base::convert_zs_us(zs_and_us_short.unwrap());
-        config_zs_and_us_short(domain, &Some(zs_and_us_short));
+        cache_zs_and_us_short(domain, &Some(zs_and_us_short));
     }
 
     let zs_and_us_short = get_zs_and_us_short(domain).unwrap();
@@ -205,7 +712,7 @@ pub(crate) fn z(domain: &str, num_windows: usize) -> TZs {
         let basepoint = generator(domain);
         let zs_and_us = base::get_zs_and_us(basepoint, num_windows);
         let zs_and_us = base::convert_zs_us(zs_and_us.unwrap());
-        config_zs_and_us(domain, &Some(zs_and_us));
+        cache_zs_and_us(domain, &Some(zs_and_us));
     }
 
     let zs_and_us = get_zs_and_us(domain).unwrap();
@@ -217,7 +724,7 @@ pub(crate) fn z_short(domain: &str, num_windows_short: usize) -> TZs {
         let basepoint = generator(domain);
         let zs_and_us_short = base::get_zs_and_us(basepoint, num_windows_short);
         let zs_and_us_short = base::convert_zs_us(zs_and_us_short.unwrap());
-        config_zs_and_us_short(domain, &Some(zs_and_us_short));
+        cache_zs_and_us_short(domain, &Some(zs_and_us_short));
     }
 
     let zs_and_us_short = get_zs_and_us_short(domain).unwrap();
@@ -225,6 +732,7 @@ pub(crate) fn z_short(domain: &str, num_windows_short: usize) -> TZs {
 }
 
 pub fn config_domain_name(domain: &str, value: &str) {
+    record_config("domain_name", domain, value.as_bytes().to_vec());
     DOMAINS
         .lock()
         .unwrap()
@@ -240,6 +748,7 @@ pub(crate) fn get_domain_name(domain: &str) -> String {
 }
 
 pub fn config_base_point(name: &str, bp: &[u8; 32]) {
+    record_config("base_point", name, bp.to_vec());
     BASE_POINT_MAP
         .lock()
         .unwrap()
@@ -249,9 +758,97 @@ pub fn config_base_point(name: &str, bp: &[u8; 32]) {
 pub(crate) fn get_base_point(name: &str, h: &[u8; 1]) -> [u8; 32] {
     let mut map = BASE_POINT_MAP.lock().unwrap();
     if !map.contains_key(name) {
-        let bp = pallas::Point::hash_to_curve(name)(h).to_bytes();
+        let bp = base::domain_generator(name, h).to_bytes();
         map.insert(name.to_string(), bp.clone());
     }
 
     map[name]
 }
+
+// a fixed base of the ecc chip, see config_full_fixed_base (the base point is configured for the
+// full ones only), config_base_field_fixed_base and config_short_fixed_base
+#[derive(Clone, Debug)]
+pub struct FixedBaseSetup {
+    pub alias: String,
+    pub domain: String,
+    pub num_windows: usize, // the short num_windows for a short fixed base
+    pub generator: TGenerator,
+    pub zs: TZs,
+    pub us: TUs,
+    pub base_point: Option<String>,
+}
+
+// a sinsemilla commit domain, the short zs and us are only needed by the short commits
+#[derive(Clone, Debug)]
+pub struct CommitDomainSetup {
+    pub domain: String,
+    pub generator_q: TGenerator,
+    pub generator_r: TGenerator,
+    pub zs_and_us: TZsUs,
+    pub zs_and_us_short: Option<TZsUs>,
+}
+
+// the whole domain setup of an IC circuit, configured by apply
+#[derive(Clone, Debug, Default)]
+pub struct ICDomainConfig {
+    pub domain_names: Vec<(String, String)>, // (domain, name), see config_domain_name
+    pub hash_generators: Vec<(String, TGenerator)>, // (hash domain, Q), e.g. of the merkle hash
+    pub full_fixed_bases: Vec<FixedBaseSetup>,
+    pub base_field_fixed_bases: Vec<FixedBaseSetup>,
+    pub short_fixed_bases: Vec<FixedBaseSetup>,
+    pub commit_domains: Vec<CommitDomainSetup>,
+}
+
+impl ICDomainConfig {
+    // the domain names are configured first since the generators are looked up by them, then
+    // the fixed bases and the commit domains, every fixed base being validated
+    pub fn apply(&self) {
+        for (domain, name) in &self.domain_names {
+            config_domain_name(domain, name);
+        }
+        for (domain, q) in &self.hash_generators {
+            config_generator_q(domain, &Some(*q));
+        }
+
+        for base in &self.full_fixed_bases {
+            config_full_fixed_base(
+                &base.alias,
+                &base.domain,
+                base.num_windows,
+                &base.generator,
+                &base.zs,
+                &base.us,
+                base.base_point.as_deref(),
+            );
+        }
+        for base in &self.base_field_fixed_bases {
+            config_base_field_fixed_base(
+                &base.alias,
+                &base.domain,
+                base.num_windows,
+                &base.generator,
+                &base.zs,
+                &base.us,
+            );
+        }
+        for base in &self.short_fixed_bases {
+            config_short_fixed_base(
+                &base.alias,
+                &base.domain,
+                base.num_windows,
+                &base.generator,
+                &base.zs,
+                &base.us,
+            );
+        }
+
+        for commit in &self.commit_domains {
+            config_generator_q(&commit.domain, &Some(commit.generator_q));
+            config_generator_r(&commit.domain, &Some(commit.generator_r));
+            config_zs_and_us(&commit.domain, &Some(commit.zs_and_us.clone()));
+            if let Some(zs_and_us_short) = &commit.zs_and_us_short {
+                config_zs_and_us_short(&commit.domain, &Some(zs_and_us_short.clone()));
+            }
+        }
+    }
+}
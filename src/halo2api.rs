@@ -27,7 +27,7 @@ pub(crate) fn load_private(
         value.clone(),
     )?;
 
-    #[cfg(feature = "debug")]
+    #[cfg(feature = "debug-info")]
     if _debug_info.0.is_some() {
         _debug_info
             .0
@@ -55,7 +55,7 @@ where
 {
     let ret = cell.copy_advice(annotation, region, columns[col], offset)?;
 
-    #[cfg(feature = "debug")]
+    #[cfg(feature = "debug-info")]
     if _debug_info.0.is_some() {
         _debug_info
             .0
@@ -85,7 +85,7 @@ where
 {
     let ret = region.assign_advice(annotation, columns[col], offset, to)?;
 
-    #[cfg(feature = "debug")]
+    #[cfg(feature = "debug-info")]
     if _debug_info.0.is_some() {
         _debug_info
             .0
@@ -115,7 +115,7 @@ where
     let ret =
         region.assign_advice_from_instance(annotation, *instance, row, columns[col], offset)?;
 
-    #[cfg(feature = "debug")]
+    #[cfg(feature = "debug-info")]
     if _debug_info.0.is_some() {
         _debug_info
             .0
@@ -140,7 +140,7 @@ pub(crate) fn constrain_instance(
 ) -> Result<(), Error> {
     let ret = layouter.constrain_instance(*cell, *instance, row)?;
 
-    #[cfg(feature = "debug")]
+    #[cfg(feature = "debug-info")]
     if _debug_info.0.is_some() {
         _debug_info
             .0
@@ -153,7 +153,25 @@ pub(crate) fn constrain_instance(
     Ok(ret)
 }
 
-#[cfg(feature = "debug")]
+// merges the child's debug info into the parent's, the child's names are prefixed with [prefix]
+#[cfg(feature = "debug-info")]
+pub(crate) fn merge_debug_info(
+    parent: &mut Option<BTreeMap<String, Vec<String>>>,
+    prefix: &str,
+    child: &Option<BTreeMap<String, Vec<String>>>,
+) {
+    if parent.is_some() && child.is_some() {
+        let parent = parent.as_mut().unwrap();
+        for (name, info) in child.as_ref().unwrap() {
+            parent
+                .entry(format!("[{}] {}", prefix, name))
+                .or_insert(Vec::new())
+                .extend(info.iter().cloned());
+        }
+    }
+}
+
+#[cfg(feature = "debug-info")]
 pub(crate) fn output_debug_info(name: &str, _debug_info: &Option<BTreeMap<String, Vec<String>>>) {
     use std::io::Write;
 
@@ -176,3 +194,148 @@ pub(crate) fn output_debug_info(name: &str, _debug_info: &Option<BTreeMap<String
         f.write("}\r\n\r\n".as_bytes()).expect(error_info);
     }
 }
+
+// renders the layout (regions and column usage) of the circuit to an SVG file
+#[cfg(feature = "dev-graph")]
+pub(crate) fn render_layout<C: plonk::Circuit<pallas::Base>>(
+    title: &str,
+    k: u32,
+    circuit: &C,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use plotters::prelude::*;
+
+    let root = SVGBackend::new(path, (1024, 3096)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let root = root.titled(title, ("sans-serif", 60))?;
+
+    halo2_proofs::dev::CircuitLayout::default()
+        .view_height(0..(1 << k))
+        .render(k, circuit, &root)?;
+    root.present()?;
+    Ok(())
+}
+
+// an assignment keeping the values of the advice columns only, the instance is unknown so the
+// cells copied from it (and the values derived from them) are None
+struct WitnessAssignment {
+    k: u32,
+    advices: Vec<Vec<Option<pallas::Base>>>,
+}
+
+impl plonk::Assignment<pallas::Base> for WitnessAssignment {
+    fn enter_region<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        _annotation: A,
+        _selector: &plonk::Selector,
+        _row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn query_instance(
+        &self,
+        _column: Column<InstanceColumn>,
+        _row: usize,
+    ) -> Result<Option<pallas::Base>, Error> {
+        Ok(None)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<pallas::Base>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if row >= 1 << self.k {
+            return Err(Error::NotEnoughRowsAvailable { current_k: self.k });
+        }
+        self.advices[column.index()][row] = to().ok().map(|v| v.into().evaluate());
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        _column: Column<plonk::Fixed>,
+        row: usize,
+        _to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<pallas::Base>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        if row >= 1 << self.k {
+            return Err(Error::NotEnoughRowsAvailable { current_k: self.k });
+        }
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        _left_column: Column<plonk::Any>,
+        _left_row: usize,
+        _right_column: Column<plonk::Any>,
+        _right_row: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _column: Column<plonk::Fixed>,
+        _row: usize,
+        _to: Option<Assigned<pallas::Base>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _gadget_name: Option<String>) {}
+}
+
+// the advice columns (by column index, then by row) assigned by the synthesis of the circuit
+pub(crate) fn witness_columns<C: plonk::Circuit<pallas::Base>>(
+    k: u32,
+    circuit: &C,
+) -> Result<Vec<Vec<Option<pallas::Base>>>, Error> {
+    use halo2_proofs::circuit::FloorPlanner;
+
+    let mut cs = plonk::ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+
+    let mut assignment = WitnessAssignment {
+        k,
+        advices: vec![vec![None; 1 << k]; cs.num_advice_columns()],
+    };
+    C::FloorPlanner::synthesize(&mut assignment, circuit, config, cs.constants().clone())?;
+    Ok(assignment.advices)
+}
@@ -18,6 +18,7 @@ pub mod primitives {
     pub mod nullifier;
     pub mod tree;
     pub mod value;
+    pub mod vesta;
 }
 
 pub mod sinsemilla {
@@ -35,6 +36,12 @@ pub mod circuit {
 
 #[cfg(test)]
 pub mod test {
+    pub mod primitives {
+        pub mod utils;
+        pub mod value;
+        pub mod vesta;
+    }
+
     pub mod sinsemilla {
         pub mod commit;
         pub(crate) mod constants;
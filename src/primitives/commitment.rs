@@ -3,7 +3,7 @@
 #![allow(dead_code)]
 
 use bitvec::{array::BitArray, order::Lsb0, store::BitStore};
-use group::ff::{PrimeField, PrimeFieldBits};
+use group::ff::{Field, PrimeField, PrimeFieldBits};
 use group::Group;
 use pasta_curves::pallas;
 use rand::RngCore;
@@ -13,14 +13,22 @@ use halo2_gadgets::primitives::sinsemilla;
 
 use funty::Signed;
 
+use super::nippoint::NonIdentityPallasPoint;
 use super::utils::*;
 use super::value::*;
 use crate::consts::*;
+use crate::domains::compute_commit_value;
+use crate::sinsemilla::circuit::CommitInputBuilder;
+use crate::types::CommitResult;
 
 #[derive(Clone, Debug)]
 pub struct CommitTrapdoor(pub(super) pallas::Scalar);
 
 impl CommitTrapdoor {
+    pub fn random(rng: impl RngCore) -> Self {
+        CommitTrapdoor(pallas::Scalar::random(rng))
+    }
+
     pub fn value(&self) -> pallas::Scalar {
         self.0
     }
@@ -44,6 +52,24 @@ impl Commitment {
         self.0
     }
 
+    // the full commit of the point (x and the lsb of y) with the randomness rcm, the same as the
+    // result of a full commit whose gates take the point as the only YInput, e.g. cm_old of a leaf
+    pub fn from_value_and_randomness(
+        domain: &str,
+        value: &pallas::Point,
+        rcm: &CommitTrapdoor,
+    ) -> Self {
+        let mut inputs = CommitInputBuilder::new();
+        inputs.add_point("value", value);
+
+        match compute_commit_value(false, domain, b"", &rcm.0, &inputs.commit_inputs()) {
+            CommitResult::Point(Some(p)) => Commitment(p),
+            _ => panic!(
+                "[Commitment::from_value_and_randomness] the commit result should be a point"
+            ),
+        }
+    }
+
     pub fn derive<S: Signed>(
         domain: &str,
         bits: &Vec<[u8; 32]>,
@@ -62,12 +88,36 @@ impl Commitment {
             chains.extend(value.to_le_bits().iter().by_val());
         }
         for point in points {
-            chains.extend(point.to_le_bits().iter().by_val().take(FILED_SIZE));
+            chains.extend(point.to_le_bits().iter().by_val().take(FIELD_SIZE));
         }
 
         let domain = sinsemilla::CommitDomain::new(domain);
         domain.commit(chains.into_iter(), &rcm.0).map(Commitment)
     }
+
+    // the note commitment of the inputs in the order of the commit gates: g_d, pk_d (x and the
+    // lsb of y), value (L_VALUE bits), nf and psi (FIELD_SIZE bits)
+    pub fn derive_note<S: Signed>(
+        domain: &str,
+        g_d: &NonIdentityPallasPoint,
+        pk_d: &NonIdentityPallasPoint,
+        value: ValueType<S>,
+        nf: pallas::Base,
+        psi: pallas::Base,
+        rcm: &pallas::Scalar,
+    ) -> ExtractedCommitment {
+        let mut inputs = CommitInputBuilder::new();
+        inputs.add_nipoint("g_d", g_d);
+        inputs.add_nipoint("pk_d", pk_d);
+        inputs.add_value("v", value);
+        inputs.add_field("nf", &nf);
+        inputs.add_field("psi", &psi);
+
+        match compute_commit_value(false, domain, b"", rcm, &inputs.commit_inputs()) {
+            CommitResult::Point(Some(p)) => Commitment(p).into(),
+            _ => panic!("[Commitment::derive_note] the commit result should be a point"),
+        }
+    }
 }
 
 impl From<Commitment> for ExtractedCommitment {
@@ -91,6 +141,11 @@ impl ExtractedCommitment {
     pub fn to_bytes(self) -> [u8; 32] {
         self.0.to_repr()
     }
+
+    // whether it is the x-coordinate of cm (0 for the identity, as extract_p)
+    pub fn matches(&self, cm: &Commitment) -> bool {
+        self.0.ct_eq(&extract_p(&cm.0)).into()
+    }
 }
 
 impl From<&ExtractedCommitment> for [u8; 32] {
@@ -112,3 +167,15 @@ impl PartialEq for ExtractedCommitment {
 }
 
 impl Eq for ExtractedCommitment {}
+
+// the digest of the note commitments, chained by poseidon_hash: cmx_0 for one note, otherwise
+// poseidon_hash(..poseidon_hash(cmx_0, cmx_1).., cmx_n), as constrained by bundle_algo_config
+pub fn bundle_commitment(commitments: &[ExtractedCommitment]) -> pallas::Base {
+    assert!(commitments.len() > 0, "[bundle_commitment] no commitment");
+
+    commitments[1..]
+        .iter()
+        .fold(commitments[0].0, |digest, cmx| {
+            poseidon_hash(&[digest, cmx.0])
+        })
+}
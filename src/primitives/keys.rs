@@ -58,6 +58,8 @@ impl<T: Randomizer> From<&ValidatingKey<T>> for pallas::Point {
 }
 
 impl<T: Randomizer> ValidatingKey<T> {
+    // the base point configured for the domain, e.g. by the base_point of the full fixed base
+    // (see global::config_full_fixed_base) so that it is the base used in circuit
     pub fn basepoint(domain_name: &str, h: &[u8; 1]) -> pallas::Point {
         pallas::Point::from_bytes(&global::get_base_point(domain_name, h)).unwrap()
     }
@@ -13,26 +13,43 @@
 // refer https://github.com/zcash/orchard/blob/main/src/note/nullifier.rs
 
 use group::{ff::PrimeField, Group};
+use halo2_gadgets::primitives::poseidon;
+use halo2_proofs::arithmetic::CurveExt;
 use pasta_curves::pallas;
 use rand::RngCore;
 use subtle::CtOption;
 
 use super::utils::*;
 
+// a nullifier of either curve of the cycle, see primitives::vesta for the vesta one
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Nullifier(pub pallas::Base);
+pub struct Nullifier<F = pallas::Base>(pub F);
 
-impl Nullifier {
+impl<F: CycleBase> Nullifier<F> {
     pub(crate) fn dummy(rng: &mut impl RngCore) -> Self {
-        Nullifier(extract_p(&pallas::Point::random(rng)))
+        Nullifier(extract_p(&F::Point::random(rng)))
     }
 
-    pub fn value(&self) -> pallas::Base {
+    // nf = Extract_P([(poseidon_hash(inputs) + addend) mod q] K + cm), K =
+    // hash_to_curve(domain_name)(k_tag) must be the generator the in-circuit NullifierK fixed base
+    // is configured with
+    pub fn derive(domain_name: &str, k_tag: &[u8], inputs: &[F; 2], addend: F, cm: F::Point) -> Self
+    where
+        poseidon::P128Pow5T3: poseidon::Spec<F, 3, 2>,
+    {
+        let k = F::Point::hash_to_curve(domain_name)(k_tag);
+
+        Nullifier(extract_p(
+            &(k * mod_r_p(poseidon_hash(inputs) + addend) + cm),
+        ))
+    }
+
+    pub fn value(&self) -> F {
         self.0
     }
 
     pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
-        pallas::Base::from_repr(*bytes).map(Nullifier)
+        F::from_repr(*bytes).map(Nullifier)
     }
 
     pub fn to_bytes(self) -> [u8; 32] {
@@ -10,6 +10,7 @@ use rand::RngCore;
 use serde::de::{Deserializer, Error};
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::iter;
 use subtle::{Choice, ConditionallySelectable, CtOption};
 
@@ -137,6 +138,31 @@ impl Anchor {
     }
 }
 
+#[derive(Debug)]
+pub enum MerklePathError {
+    Length(usize), // the number of siblings
+    Position(u32), // the position out of the tree
+}
+
+impl fmt::Display for MerklePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerklePathError::Length(len) => write!(
+                f,
+                "MerklePath has [{}] siblings, expected [{}]",
+                len, MERKLE_DEPTH
+            ),
+            MerklePathError::Position(position) => write!(
+                f,
+                "MerklePath position [{}] is out of a tree of depth [{}]",
+                position, MERKLE_DEPTH
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MerklePathError {}
+
 #[derive(Debug)]
 pub struct MerklePath {
     position: u32,
@@ -169,11 +195,38 @@ impl MerklePath {
         }
     }
 
+    // builds a path from the siblings stored externally, from the leaf up to the root
+    pub fn from_siblings(
+        position: u32,
+        siblings: &[DomainMerkleHash],
+    ) -> Result<Self, MerklePathError> {
+        if siblings.len() != MERKLE_DEPTH {
+            return Err(MerklePathError::Length(siblings.len()));
+        }
+        if (position as u64) >> MERKLE_DEPTH != 0 {
+            return Err(MerklePathError::Position(position));
+        }
+
+        Ok(Self::from_parts(
+            position,
+            gen_const_array_with_default(DomainMerkleHash::empty_leaf(), |i| siblings[i]),
+        ))
+    }
+
     pub fn root(&self, cmx: ExtractedCommitment) -> Anchor {
+        self.root_from_leaf(DomainMerkleHash::from_cmx(&cmx)).into()
+    }
+
+    // checks the path off-circuit as merklecrh does in-circuit, the leaf is the x-coordinate of cm
+    pub fn verify(&self, leaf: pallas::Base, anchor: pallas::Base) -> bool {
+        self.root_from_leaf(DomainMerkleHash(leaf)).value() == anchor
+    }
+
+    fn root_from_leaf(&self, leaf: DomainMerkleHash) -> DomainMerkleHash {
         self.auth_path
             .iter()
             .enumerate()
-            .fold(DomainMerkleHash::from_cmx(&cmx), |node, (l, sibling)| {
+            .fold(leaf, |node, (l, sibling)| {
                 let l = l as u8;
                 if self.position & (1 << l) == 0 {
                     DomainMerkleHash::combine(l.into(), &node, sibling)
@@ -181,7 +234,6 @@ impl MerklePath {
                     DomainMerkleHash::combine(l.into(), sibling, &node)
                 }
             })
-            .into()
     }
 
     pub fn position(&self) -> u32 {
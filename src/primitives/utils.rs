@@ -1,16 +1,19 @@
 // from https://github.com/zcash/orchard/blob/main/src/constants/util.rs
 
-use ff::PrimeField;
-use group::Curve;
+use ff::{Field, PrimeField};
+use group::{Curve, GroupEncoding};
 use halo2_gadgets::{
     primitives::{poseidon, sinsemilla::K},
     utilities::i2lebsp,
     utilities::lebs2ip,
 };
-use halo2_proofs::arithmetic::{CurveAffine, FieldExt};
-use pasta_curves::pallas;
+use halo2_proofs::arithmetic::{CurveAffine, CurveExt, FieldExt};
+use pasta_curves::{pallas, vesta};
+use std::fmt;
 use subtle::CtOption;
 
+pub use crate::base::domain_generator;
+
 /// Takes in an FnMut closure and returns a constant-length array with elements of
 /// type `Output`.
 pub fn gen_const_array<Output: Copy + Default, const LEN: usize>(
@@ -30,25 +33,38 @@ pub fn gen_const_array_with_default<Output: Copy, const LEN: usize>(
     ret
 }
 
+// the base field of a curve of the pallas-vesta cycle, which is the scalar field of the other curve
+pub trait CycleBase: FieldExt + PrimeField<Repr = [u8; 32]> {
+    type Point: CurveExt<Base = Self> + GroupEncoding<Repr = [u8; 32]>;
+}
+
+impl CycleBase for pallas::Base {
+    type Point = pallas::Point;
+}
+
+impl CycleBase for vesta::Base {
+    type Point = vesta::Point;
+}
+
 // public methods
 pub fn i2lebsp_k(int: usize) -> [bool; K] {
     assert!(int < (1 << K));
     i2lebsp(int as u64)
 }
 
-pub fn extract_p(point: &pallas::Point) -> pallas::Base {
+pub fn extract_p<C: CurveExt>(point: &C) -> C::Base {
     point
         .to_affine()
         .coordinates()
         .map(|c| *c.x())
-        .unwrap_or_else(pallas::Base::zero)
+        .unwrap_or_else(C::Base::zero)
 }
 
 pub fn lebs2ip_field<F: PrimeField, const L: usize>(bits: &[bool; L]) -> F {
     F::from(lebs2ip::<L>(bits))
 }
 
-pub fn extract_p_bottom(point: CtOption<pallas::Point>) -> CtOption<pallas::Base> {
+pub fn extract_p_bottom<C: CurveExt>(point: CtOption<C>) -> CtOption<C::Base> {
     point.map(|p| extract_p(&p))
 }
 
@@ -60,12 +76,71 @@ pub fn to_base(x: [u8; 64]) -> pallas::Base {
     pallas::Base::from_bytes_wide(&x)
 }
 
-pub fn mod_r_p(x: pallas::Base) -> pallas::Scalar {
-    pallas::Scalar::from_repr(x.to_repr()).unwrap()
+// x is reduced only on vesta, as the pallas base field is smaller than its scalar field
+pub fn mod_r_p<F: CycleBase>(x: F) -> <F::Point as CurveExt>::ScalarExt {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&x.to_repr());
+    <<F::Point as CurveExt>::ScalarExt as FieldExt>::from_bytes_wide(&bytes)
 }
 
 // N is supposed to be 2, other number might cause lower performance
-pub fn poseidon_hash<const N: usize>(inputs: &[pallas::Base; N]) -> pallas::Base {
+pub fn poseidon_hash<F: FieldExt, const N: usize>(inputs: &[F; N]) -> F
+where
+    poseidon::P128Pow5T3: poseidon::Spec<F, 3, 2>,
+{
     poseidon::Hash::<_, poseidon::P128Pow5T3, poseidon::ConstantLength<N>, 3, 2>::init()
         .hash(*inputs)
 }
+
+#[derive(Debug)]
+pub enum ParseBaseError {
+    Empty,
+    Digit(char),   // the invalid digit
+    Range(String), // the string not less than the modulus
+}
+
+impl fmt::Display for ParseBaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBaseError::Empty => write!(f, "no digits to parse into pallas::Base"),
+            ParseBaseError::Digit(c) => write!(f, "invalid digit [{}] for pallas::Base", c),
+            ParseBaseError::Range(s) => {
+                write!(f, "[{}] is out of the range of pallas::Base", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseBaseError {}
+
+// parses a decimal or 0x-prefixed hex string into pallas::Base, the value should be less than the
+// modulus
+pub fn base_from_str(s: &str) -> Result<pallas::Base, ParseBaseError> {
+    let (digits, radix) = match s.strip_prefix("0x").or(s.strip_prefix("0X")) {
+        Some(digits) => (digits, 16),
+        None => (s, 10),
+    };
+    if digits.is_empty() {
+        return Err(ParseBaseError::Empty);
+    }
+
+    // little-endian u64 limbs
+    let mut limbs = [0u64; 4];
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix).ok_or(ParseBaseError::Digit(c))? as u128;
+        for limb in limbs.iter_mut() {
+            let v = (*limb as u128) * (radix as u128) + carry;
+            *limb = v as u64;
+            carry = v >> 64;
+        }
+        if carry != 0 {
+            return Err(ParseBaseError::Range(s.to_string()));
+        }
+    }
+
+    let mut repr = [0u8; 32];
+    for (i, limb) in limbs.iter().enumerate() {
+        repr[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_le_bytes());
+    }
+    Option::from(pallas::Base::from_repr(repr)).ok_or(ParseBaseError::Range(s.to_string()))
+}
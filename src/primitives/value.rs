@@ -1,13 +1,16 @@
 // refer https://github.com/zcash/orchard/blob/main/src/value.rs
 
-use ff::Field;
+use ff::{Field, PrimeField};
 use group::{Curve, Group, GroupEncoding};
-use pasta_curves::{arithmetic::CurveAffine, pallas};
+use pasta_curves::{
+    arithmetic::{CurveAffine, CurveExt},
+    pallas,
+};
 
 use rand::RngCore;
 use subtle::CtOption;
 
-use std::convert::From;
+use std::convert::{From, TryInto};
 use std::fmt::{self, Debug};
 use std::iter::Sum;
 use std::num::ParseIntError;
@@ -72,6 +75,25 @@ impl<S: Signed> ValueType<S> {
         ValueType(S::ZERO)
     }
 
+    // the magnitude as a scalar, negated for a negative value, the same as the multiplication of
+    // the ShortField magnitude and sign in-circuit, e.g. v of [v] V in a value commitment
+    pub fn to_signed_scalar(self) -> pallas::Scalar {
+        self.to_signed_field()
+    }
+
+    // to_signed_scalar in any prime field, e.g. the vesta scalar field. The magnitude is taken
+    // unsigned, S::MIN (a valid sum) has no positive counterpart, and it is wider than u64 for i128
+    pub fn to_signed_field<F: PrimeField>(self) -> F {
+        let magnitude = self.0.as_i128().unsigned_abs();
+        let magnitude = F::from((magnitude >> 64) as u64) * F::from(1u64 << 32).square()
+            + F::from(magnitude as u64);
+        if self.0.is_negative() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
     pub fn from_bytes(bytes: S::Bytes) -> Self {
         ValueType(S::from_le_bytes(bytes))
     }
@@ -80,6 +102,25 @@ impl<S: Signed> ValueType<S> {
         self.0.to_le_bytes()
     }
 
+    // the 64-bit little-endian encoding, the same as the value committed in a note
+    pub fn to_le_bytes(self) -> Result<[u8; 8], ParseIntError> {
+        Ok(self.to_u64()?.to_le_bytes())
+    }
+
+    pub fn from_le_bytes(bytes: &[u8]) -> Result<Self, OverflowError> {
+        let bytes: [u8; 8] = bytes.try_into().map_err(|_| OverflowError)?;
+        let value = S::try_from(u64::from_le_bytes(bytes)).map_err(|_| OverflowError)?;
+        Self::from(value)
+    }
+
+    pub fn from_base(base: pallas::Base) -> Result<Self, OverflowError> {
+        let repr = base.to_repr();
+        if repr[8..].iter().any(|b| *b != 0) {
+            return Err(OverflowError);
+        }
+        Self::from_le_bytes(&repr[..8])
+    }
+
     pub fn to_le_bits(self) -> BitArray<Lsb0, S::Bytes>
     where
         S::Bytes: BitStore,
@@ -186,87 +227,102 @@ impl<S: Signed> Sum<ValueType<S>> for Result<ValueType<S>, OverflowError> {
     }
 }
 
+// the trapdoor and the commitment are over either curve of the cycle, the scalar field and the
+// point of pallas by default, see primitives::vesta for the vesta ones
 #[derive(Clone, Debug)]
-pub struct ValueCommitTrapdoor(pallas::Scalar);
+pub struct ValueCommitTrapdoor<F = pallas::Scalar>(F);
 
-impl Add<&ValueCommitTrapdoor> for ValueCommitTrapdoor {
-    type Output = ValueCommitTrapdoor;
+impl<F: Field> Add<&ValueCommitTrapdoor<F>> for ValueCommitTrapdoor<F> {
+    type Output = ValueCommitTrapdoor<F>;
 
     fn add(self, rhs: &Self) -> Self::Output {
         ValueCommitTrapdoor(self.0 + rhs.0)
     }
 }
 
-impl<'a> Sum<&'a ValueCommitTrapdoor> for ValueCommitTrapdoor {
-    fn sum<I: Iterator<Item = &'a ValueCommitTrapdoor>>(iter: I) -> Self {
+impl<'a, F: Field> Sum<&'a ValueCommitTrapdoor<F>> for ValueCommitTrapdoor<F> {
+    fn sum<I: Iterator<Item = &'a ValueCommitTrapdoor<F>>>(iter: I) -> Self {
         iter.fold(ValueCommitTrapdoor::zero(), |acc, cv| acc + cv)
     }
 }
 
-impl ValueCommitTrapdoor {
+impl<F: Field> ValueCommitTrapdoor<F> {
     /// Generates a new value commitment trapdoor.
     pub fn random(rng: impl RngCore) -> Self {
-        ValueCommitTrapdoor(pallas::Scalar::random(rng))
+        ValueCommitTrapdoor(F::random(rng))
     }
 
     /// Returns the zero trapdoor, which provides no blinding.
     pub fn zero() -> Self {
-        ValueCommitTrapdoor(pallas::Scalar::zero())
+        ValueCommitTrapdoor(F::zero())
     }
 
-    pub fn value(&self) -> pallas::Scalar {
+    pub fn value(&self) -> F {
         self.0
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct ValueCommitment(pallas::Point);
+pub struct ValueCommitment<C = pallas::Point>(C);
 
-impl From<pallas::Point> for ValueCommitment {
-    fn from(p: pallas::Point) -> Self {
+impl<C: CurveExt> From<C> for ValueCommitment<C> {
+    fn from(p: C) -> Self {
         ValueCommitment(p)
     }
 }
 
-impl Add<&ValueCommitment> for ValueCommitment {
-    type Output = ValueCommitment;
+impl<C: CurveExt> Add<&ValueCommitment<C>> for ValueCommitment<C> {
+    type Output = ValueCommitment<C>;
 
     fn add(self, rhs: &Self) -> Self::Output {
         ValueCommitment(self.0 + rhs.0)
     }
 }
 
-impl Sub for ValueCommitment {
-    type Output = ValueCommitment;
+impl<C: CurveExt> Sub for ValueCommitment<C> {
+    type Output = ValueCommitment<C>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         ValueCommitment(self.0 - rhs.0)
     }
 }
 
-impl Sum for ValueCommitment {
+impl<C: CurveExt> Sum for ValueCommitment<C> {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.fold(ValueCommitment(pallas::Point::identity()), |acc, cv| {
-            acc + &cv
-        })
+        iter.fold(ValueCommitment(C::identity()), |acc, cv| acc + &cv)
     }
 }
 
-impl<'a> Sum<&'a ValueCommitment> for ValueCommitment {
-    fn sum<I: Iterator<Item = &'a ValueCommitment>>(iter: I) -> Self {
-        iter.fold(ValueCommitment(pallas::Point::identity()), |acc, cv| {
-            acc + cv
-        })
+impl<'a, C: CurveExt> Sum<&'a ValueCommitment<C>> for ValueCommitment<C> {
+    fn sum<I: Iterator<Item = &'a ValueCommitment<C>>>(iter: I) -> Self {
+        iter.fold(ValueCommitment(C::identity()), |acc, cv| acc + cv)
     }
 }
 
-impl ValueCommitment {
+impl<C: CurveExt + GroupEncoding<Repr = [u8; 32]>> ValueCommitment<C> {
+    // cv = [v] V + [rcv] R, V = hash_to_curve(domain)("v") and R = hash_to_curve(domain)("r"), a
+    // zero value commits to [rcv] R only, this is relied on by dummy notes (old_v = 0)
+    #[allow(non_snake_case)]
+    pub fn derive<S: Signed>(
+        domain: &str,
+        value: ValueType<S>,
+        rcv: ValueCommitTrapdoor<C::ScalarExt>,
+    ) -> Self {
+        debug_assert!(ValueType::<S>::is_valid_range(value.value(), true));
+
+        let hasher = C::hash_to_curve(domain);
+        let V = hasher(&*b"v");
+        let R = hasher(&*b"r");
+
+        ValueCommitment(V * value.to_signed_field::<C::ScalarExt>() + R * rcv.value())
+    }
+
     /// Deserialize a value commitment from its byte representation
-    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<ValueCommitment> {
-        pallas::Point::from_bytes(bytes).map(ValueCommitment)
+    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<ValueCommitment<C>> {
+        C::from_bytes(bytes).map(ValueCommitment)
     }
 
-    pub fn value(&self) -> pallas::Point {
+    pub fn value(&self) -> C {
         self.0
     }
 
@@ -276,18 +332,18 @@ impl ValueCommitment {
     }
 
     /// x-coordinate of this value commitment.
-    pub fn x(&self) -> pallas::Base {
-        if self.0 == pallas::Point::identity() {
-            pallas::Base::zero()
+    pub fn x(&self) -> C::Base {
+        if self.0 == C::identity() {
+            C::Base::zero()
         } else {
             *self.0.to_affine().coordinates().unwrap().x()
         }
     }
 
     /// y-coordinate of this value commitment.
-    pub fn y(&self) -> pallas::Base {
-        if self.0 == pallas::Point::identity() {
-            pallas::Base::zero()
+    pub fn y(&self) -> C::Base {
+        if self.0 == C::identity() {
+            C::Base::zero()
         } else {
             *self.0.to_affine().coordinates().unwrap().y()
         }
@@ -0,0 +1,13 @@
+// the vesta counterparts of the value and nullifier primitives, for the other half of the
+// pallas-vesta cycle. the note commitment is pallas only, as it depends on sinsemilla, and
+// extract_p, mod_r_p and poseidon_hash of utils are generic over the cycle
+
+use pasta_curves::vesta;
+
+use super::{nullifier, value};
+
+pub type Nullifier = nullifier::Nullifier<vesta::Base>;
+
+pub type ValueCommitTrapdoor = value::ValueCommitTrapdoor<vesta::Scalar>;
+
+pub type ValueCommitment = value::ValueCommitment<vesta::Point>;
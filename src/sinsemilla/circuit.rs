@@ -19,21 +19,85 @@ use halo2_gadgets::{
         NonIdentityPoint,
     },
     sinsemilla::chip::SinsemillaChip,
-    utilities::{lookup_range_check::LookupRangeCheckConfig, UtilitiesInstructions},
+    utilities::{
+        bitrange_subset, lookup_range_check::LookupRangeCheckConfig, UtilitiesInstructions,
+    },
 };
 
+use funty::Signed;
 use std::{collections::BTreeMap, convert::TryInto, marker::PhantomData};
 
 use super::config::*;
 use crate::base;
+use crate::consts::*;
 use crate::domains::*;
 use crate::halo2api;
+use crate::primitives::{
+    commitment::Commitment, nippoint::NonIdentityPallasPoint, value::ValueType,
+};
 use crate::types::*;
 
 type SinsemillaConfigType = (CommitConfig, EccConfig<DomainFixedBases>, Vec<GateInfo>);
+pub(crate) type SinsemillaChipType =
+    SinsemillaChip<BaseHashDomains, HashDomainsType, DomainFixedBases>;
 type CommitInputMap = BTreeMap<String, ((Option<pallas::Base>, usize), Option<pallas::Base>)>;
 pub(crate) type CommitInputVec = Vec<(String, Option<pallas::Base>, Option<pallas::Base>)>;
 
+// lowers typed values to commit inputs, in the order they are added
+#[derive(Clone, Debug, Default)]
+pub struct CommitInputBuilder {
+    inputs: Vec<(String, usize, Option<pallas::Base>, Option<pallas::Base>)>, // (name, width, x, y_lsb)
+}
+
+impl CommitInputBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_field(&mut self, name: &str, v: &pallas::Base) {
+        self.inputs
+            .push((name.to_string(), FIELD_SIZE, Some(v.clone()), None));
+    }
+
+    // x-coordinate and the lsb of y-coordinate, for full commit only
+    pub fn add_point(&mut self, name: &str, v: &pallas::Point) {
+        let coordinates = v.to_affine().coordinates().unwrap();
+        self.inputs.push((
+            name.to_string(),
+            FIELD_SIZE,
+            Some(*coordinates.x()),
+            Some(bitrange_subset(coordinates.y(), 0..1)),
+        ));
+    }
+
+    pub fn add_nipoint(&mut self, name: &str, v: &NonIdentityPallasPoint) {
+        self.add_point(name, &v.value());
+    }
+
+    pub fn add_commitment(&mut self, name: &str, cm: &Commitment) {
+        self.add_point(name, &cm.value());
+    }
+
+    // the value is committed with L_VALUE bits
+    pub fn add_value<S: Signed>(&mut self, name: &str, v: ValueType<S>) {
+        self.inputs
+            .push((name.to_string(), L_VALUE, v.to_base(), None));
+    }
+
+    // inputs for SinsemillaCircuit::new
+    pub fn inputs(&self) -> CommitInputVec {
+        self.inputs
+            .iter()
+            .map(|(name, _width, x, y_lsb)| (name.clone(), x.clone(), y_lsb.clone()))
+            .collect()
+    }
+
+    // inputs for compute_commit_value
+    pub fn commit_inputs(&self) -> CommitInputs {
+        self.inputs.clone()
+    }
+}
+
 pub trait ISinsemillaCircuit {
     fn get_commit_gate_config(domain: &String) -> Vec<GateInfo>;
 }
@@ -44,6 +108,8 @@ pub struct SinsemillaCircuit<T: ISinsemillaCircuit> {
     pub commit_domain_name: String,
     pub inputs: CommitInputVec,
     pub input_r: Option<pallas::Scalar>,
+    pub is_deterministic: bool,   // hash only, input_r is not committed
+    pub personalization: Vec<u8>, // absorbed before the inputs, see CommitSpec::personalization
     pub _nothing: PhantomData<T>,
 }
 
@@ -54,6 +120,8 @@ impl<T: ISinsemillaCircuit> Default for SinsemillaCircuit<T> {
             commit_domain_name: Default::default(),
             inputs: Default::default(),
             input_r: Default::default(),
+            is_deterministic: false,
+            personalization: Vec::new(),
             _nothing: Default::default(),
         }
     }
@@ -102,10 +170,32 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
             commit_domain_name: commit_domain_name.to_string(),
             inputs: inputs.clone(),
             input_r: input_r,
+            is_deterministic: false,
+            personalization: Vec::new(),
             _nothing: Default::default(),
         }
     }
 
+    // a commit without randomness, e.g. of a deterministic domain tag, the same as
+    // compute_deterministic_commit_value off-circuit. The circuit is the hash point only without
+    // the [r] R term, so no r can be committed, input_r is zero for the witness mode only
+    pub fn new_deterministic(
+        is_short_commit: bool,
+        commit_domain_name: &str,
+        inputs: &CommitInputVec,
+        gates: &Vec<GateInfo>,
+    ) -> Self {
+        let input_r = if inputs.len() > 0 && inputs[0].1.is_some() {
+            Some(pallas::Scalar::zero())
+        } else {
+            None // without witnesses
+        };
+        Self {
+            is_deterministic: true,
+            ..Self::new(is_short_commit, commit_domain_name, inputs, input_r, gates)
+        }
+    }
+
     pub(crate) fn do_configure(
         meta: &mut ConstraintSystem<pallas::Base>,
         commit_domain: HashDomainsType,
@@ -173,9 +263,11 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
         &self,
         config: &SinsemillaConfigType,
         ecc_chip: &EccChip<DomainFixedBases>,
+        sinsemilla_chip: Option<&SinsemillaChipType>, // shared by the embedded commits, constructed if None
         layouter: &mut impl Layouter<pallas::Base>,
         input_map: &mut InputConfigMap,
         is_with_witnesses: bool,
+        _parent_debug_info: Option<(&mut Option<BTreeMap<String, Vec<String>>>, &str)>, // (debug info, prefix)
     ) -> Result<PointResult, Error> {
         assert_synthesize_error!(
             self.input_r.is_none() || self.inputs.len() > 0,
@@ -183,7 +275,7 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
         );
         let is_with_witnesses =
             is_with_witnesses && self.input_r.is_some() && self.inputs[0].1.is_some();
-        let mut _debug_info = if is_with_witnesses && cfg!(feature = "debug") {
+        let mut _debug_info = if is_with_witnesses && cfg!(feature = "debug-info") {
             Some(BTreeMap::new())
         } else {
             None
@@ -191,7 +283,7 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
 
         let (commit_config, _ecc_config, gates) = config;
 
-        #[cfg(feature = "debug")]
+        #[cfg(feature = "debug-check")]
         let is_independent = input_map.len() == 0;
 
         let mut inputs = self
@@ -200,7 +292,9 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
             .map(|(name, x, y_lsb)| (name.clone(), ((x.clone(), 0), y_lsb.clone())))
             .collect::<CommitInputMap>();
 
-        let sinsemilla_chip = SinsemillaChip::construct(commit_config.sinsemilla_config.clone());
+        let sinsemilla_chip = sinsemilla_chip
+            .cloned()
+            .unwrap_or_else(|| SinsemillaChip::construct(commit_config.sinsemilla_config.clone()));
 
         let mut desc;
         for gate in gates {
@@ -234,7 +328,7 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
 
                             NonIdentityPoint::new(
                                 ecc_chip.clone(),
-                                layouter.namespace(|| base::string_to_static_str(&desc)),
+                                layouter.namespace(|| base::region_name(&desc)),
                                 p,
                             )?
                         };
@@ -272,11 +366,15 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
             &input_map,
             self.input_r,
             self.is_short_commit,
+            self.is_deterministic,
+            &self.personalization,
             is_with_witnesses,
             &mut _debug_info,
         )?;
 
-        #[cfg(feature = "debug")]
+        // the equality constraint below changes the circuit shape (and thus the VK),
+        // so it is only enabled with "debug-check", not with "debug-info"
+        #[cfg(feature = "debug-check")]
         if is_with_witnesses && is_independent {
             // for test
             let inputs: Vec<_> = self
@@ -284,12 +382,18 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
                 .iter()
                 .map(|(name, x, y)| (name.clone(), input_map[name].2, x.clone(), y.clone()))
                 .collect();
+            let r = if self.is_deterministic {
+                pallas::Scalar::zero()
+            } else {
+                self.input_r.unwrap()
+            };
 
             if self.is_short_commit {
                 let expected_result = compute_commit_value(
                     true,
                     &self.commit_domain_name,
-                    &self.input_r.unwrap(),
+                    &self.personalization,
+                    &r,
                     &inputs,
                 );
 
@@ -309,7 +413,8 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
                 let expected_result = compute_commit_value(
                     false,
                     &self.commit_domain_name,
-                    &self.input_r.unwrap(),
+                    &self.personalization,
+                    &r,
                     &inputs,
                 );
 
@@ -333,8 +438,14 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
             }
         }
 
-        #[cfg(feature = "debug")]
-        halo2api::output_debug_info("SinsemillaCircuit", &_debug_info);
+        // merged into the parent's debug info if any, output separately otherwise
+        #[cfg(feature = "debug-info")]
+        match _parent_debug_info {
+            Some((parent, prefix)) if parent.is_some() => {
+                halo2api::merge_debug_info(parent, prefix, &_debug_info)
+            }
+            _ => halo2api::output_debug_info("SinsemillaCircuit", &_debug_info),
+        }
 
         Ok(result)
     }
@@ -343,6 +454,11 @@ impl<T: ISinsemillaCircuit> SinsemillaCircuit<T> {
         let prover = MockProver::<pallas::Base>::run(k, self, vec![]);
         prover.unwrap().verify()
     }
+
+    #[cfg(feature = "dev-graph")]
+    pub fn render_layout(&self, k: u32, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        halo2api::render_layout("SinsemillaCircuit Layout", k, self, path)
+    }
 }
 
 impl<T: ISinsemillaCircuit> Circuit<pallas::Base> for SinsemillaCircuit<T> {
@@ -379,9 +495,11 @@ impl<T: ISinsemillaCircuit> Circuit<pallas::Base> for SinsemillaCircuit<T> {
         self.do_synthesize(
             &config,
             &ecc_chip,
+            None,
             &mut layouter,
             &mut BTreeMap::new(),
             is_with_witnesses,
+            None,
         )?;
         Ok(())
     }
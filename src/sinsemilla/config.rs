@@ -11,13 +11,14 @@ use halo2_gadgets::{
     primitives::sinsemilla::K,
     sinsemilla::{
         chip::{SinsemillaChip, SinsemillaConfig},
-        CommitDomain, Message, MessagePiece,
+        CommitDomain, CommitDomains, HashDomain, Message, MessagePiece,
     },
     utilities::{bitrange_subset, bool_check},
 };
 
 use lazy_static::lazy_static;
 use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
 use crate::base;
 use crate::consts::*;
@@ -61,6 +62,39 @@ pub(crate) enum PointResult {
     Point(Option<Point<pallas::Affine, EccChip<DomainFixedBases>>>),
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConfigError {
+    Width(String, usize, usize), // the gate, the declared width and the covered width
+    Canonicity(String, CellType), // the input gate and the missing check cell
+    TopSlice(String),            // the slice covering the top bit of the field
+    Empty(String),               // the gate without cells
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Width(gate, width, covered) => write!(
+                f,
+                "[Sinsemilla] [{}]: declared width [{}] but the pieces or slices cover [{}]",
+                gate, width, covered
+            ),
+            ConfigError::Canonicity(gate, celltype) => write!(
+                f,
+                "[Sinsemilla] [{}]: the full width input has no [{:?}] cell",
+                gate, celltype
+            ),
+            ConfigError::TopSlice(name) => write!(
+                f,
+                "[Sinsemilla] [{}]: the slice of the top bit should be configured TopSlice in piece gate",
+                name
+            ),
+            ConfigError::Empty(gate) => write!(f, "[Sinsemilla] [{}]: the gate has no cell", gate),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 lazy_static! {
     static ref VEC_TWO_POW: Vec<pallas::Base> = {
         let mut vec: Vec<pallas::Base> = Vec::new();
@@ -68,7 +102,7 @@ lazy_static! {
             vec.push(pallas::Base::from_u128(1 << i));
         }
         let two = pallas::Base::from(2);
-        for _ in 128..FILED_SIZE {
+        for _ in 128..FIELD_SIZE {
             vec.push(vec.last().unwrap() * two);
         }
         vec
@@ -114,6 +148,69 @@ impl CommitConfig {
         return true;
     }
 
+    // checks the pieces or slices of every gate cover the declared width and the full width inputs
+    // have the canonicity cells, without configuring any gate
+    pub fn validate_gates(gates: &Vec<GateInfo>) -> Result<(), ConfigError> {
+        if let Some(gate) = gates.iter().find(|gate| gate.cells.is_empty()) {
+            return Err(ConfigError::Empty(gate.name.to_string()));
+        }
+
+        let top_slices = gates
+            .iter()
+            .filter(|gate| !CellType::is_input_cell(gate.cells[0].celltype))
+            .flat_map(|gate| gate.cells[1..].iter())
+            .filter(|cell| cell.celltype == CellType::TopSlice)
+            .map(|cell| cell.name.clone())
+            .collect::<Vec<_>>();
+
+        for gate in gates {
+            let input = &gate.cells[0];
+            let mut offset: usize = 0;
+            for cell in &gate.cells[1..] {
+                if CellType::is_piece_or_slice_cell(cell.celltype)
+                    || cell.celltype == CellType::PadSlice
+                {
+                    if CellType::is_input_cell(input.celltype)
+                        && offset <= FIELD_SIZE - 1
+                        && offset + cell.width > FIELD_SIZE - 1
+                        && (offset != FIELD_SIZE - 1 || !top_slices.contains(&cell.name))
+                    {
+                        return Err(ConfigError::TopSlice(cell.name.clone()));
+                    }
+                    offset += cell.width;
+                }
+            }
+            if offset != input.width || input.width > FIELD_SIZE {
+                return Err(ConfigError::Width(input.name.clone(), input.width, offset));
+            }
+
+            if CellType::is_input_cell(input.celltype) && input.width == FIELD_SIZE {
+                let has = |celltypes: &[CellType]| {
+                    gate.cells[1..]
+                        .iter()
+                        .any(|cell| celltypes.contains(&cell.celltype))
+                };
+                if !has(&[CellType::PrimeCheck]) {
+                    return Err(ConfigError::Canonicity(
+                        input.name.clone(),
+                        CellType::PrimeCheck,
+                    ));
+                }
+                if !has(&[CellType::CanonicityCheck, CellType::CanonicityCheckSlice]) {
+                    return Err(ConfigError::Canonicity(
+                        input.name.clone(),
+                        CellType::CanonicityCheck,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_decomposition(&self) -> Result<(), ConfigError> {
+        Self::validate_gates(&self.gates)
+    }
+
     fn create_gate(
         meta: &mut ConstraintSystem<pallas::Base>,
         advices: &[Column<Advice>; 10],
@@ -187,8 +284,8 @@ impl CommitConfig {
         }
 
         // create gates
-        meta.create_gate(base::string_to_static_str(&gate.name), |meta| {
-            let t_p = Expression::Constant(pallas::Base::from_u128(T_P));
+        meta.create_gate(base::cow_to_static_str(&gate.name), |meta| {
+            let t_p = Expression::Constant(t_p_base());
 
             let q = meta.query_selector(*q);
             let mut desc;
@@ -267,7 +364,7 @@ impl CommitConfig {
                         whole = whole + _value.clone() * VEC_TWO_POW[offset];
 
                         if CellType::is_input_cell(gate.cells[0].celltype)
-                            && offset < FILED_SIZE - 1
+                            && offset < FIELD_SIZE - 1
                         {
                             if whole_items.len() < 2 && offset != MAX_PIECE_WIDTH {
                                 whole_items.push(_name.clone());
@@ -276,7 +373,7 @@ impl CommitConfig {
                         }
                     }
 
-                    if offset == FILED_SIZE - 1 {
+                    if offset == FIELD_SIZE - 1 {
                         assert!(
                             *_celltype == CellType::TopSlice,
                             "[Sinsemilla] cell type[{:?}] of [{}] should be configured TopSlice in piece gate",
@@ -330,7 +427,7 @@ impl CommitConfig {
             }
 
             // prime checks: < 2^254 + 2^126
-            if CellType::is_input_cell(gate.cells[0].celltype) && gate.cells[0].width >= FILED_SIZE
+            if CellType::is_input_cell(gate.cells[0].celltype) && gate.cells[0].width >= FIELD_SIZE
             {
                 assert!(prime.is_some(), "[Sinsemilla] [{}]: prime is none", gate.cells[0].name,);
 
@@ -397,8 +494,14 @@ impl CommitConfig {
                 );
 
                 let q = meta.selector();
-                let mut rule =
-                    Self::create_gate(meta, &advices, &q, &gate, &mut slices, &mut pad_slices);
+                let mut rule = Self::create_gate(
+                    meta,
+                    &advices,
+                    &q,
+                    &gate,
+                    &mut slices,
+                    &mut pad_slices,
+                );
                 if rule.slices.len() > 0 {
                     composition_rules.slices.append(&mut rule.slices);
                 }
@@ -464,7 +567,7 @@ impl CommitConfig {
             where z1_j = k_1.
         */
 
-        let t_p = Expression::Constant(pallas::Base::from_u128(T_P));
+        let t_p = Expression::Constant(t_p_base());
 
         let q_y_canon = meta.selector();
 
@@ -542,10 +645,7 @@ impl CommitConfig {
         // Decompose the low 130 bits of a_prime = a + 2^130 - t_P, and output
         // the running sum at the end of it. If a_prime < 2^130, the running sum
         // will be 0.
-        let a_prime = a.value().map(|a| {
-            let t_p = pallas::Base::from_u128(T_P);
-            a + VEC_TWO_POW[130] - t_p
-        });
+        let a_prime = a.value().map(|a| a + VEC_TWO_POW[130] - t_p_base());
         let zs = self.sinsemilla_config.lookup_config().witness_check(
             layouter.namespace(|| "[Sinsemilla] Decompose low 130 bits of (a + 2^130 - t_P)"),
             a_prime,
@@ -569,10 +669,10 @@ impl CommitConfig {
         // Decompose the low n bits of prime = a0 + 2^offset b + 2^n - t_P,
         // and output the running sum at the end of it.
         // If a0_b_prime < 2^n, the running sum will be 0.
-        let a0_b_prime = a0.value().zip(b.value()).map(|(a0, b)| {
-            let t_p = pallas::Base::from_u128(T_P);
-            a0 + (VEC_TWO_POW[a0_width] * b) + VEC_TWO_POW[n] - t_p
-        });
+        let a0_b_prime = a0
+            .value()
+            .zip(b.value())
+            .map(|(a0, b)| a0 + (VEC_TWO_POW[a0_width] * b) + VEC_TWO_POW[n] - t_p_base());
 
         let desc = format!(
             "[Sinsemilla] Decompose low [{}] bits of the canonicity inputs",
@@ -593,6 +693,53 @@ impl CommitConfig {
         Ok((zs[0].clone(), zs[n / K].clone()))
     }
 
+    // constrains x < 2^width by the lookup range check
+    fn range_check_width(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        name: &String,
+        x: AssignedCell<pallas::Base, pallas::Base>,
+        width: usize,
+    ) -> Result<(), Error> {
+        assert_synthesize_error!(
+            width > 0 && width < FIELD_SIZE,
+            &format!(
+                "[Sinsemilla] range check of [{}]: invalid width[{}]",
+                name, width
+            )
+        );
+
+        let lookup = self.sinsemilla_config.lookup_config();
+        let (num_words, num_bits) = (width / K, width % K);
+        if num_words == 0 {
+            return lookup.copy_short_check(
+                layouter.namespace(|| format!("[Sinsemilla] [{}] is [{}] bits", name, width)),
+                x,
+                num_bits,
+            );
+        }
+
+        // strict if width is times of K, otherwise the rest of the running sum is short checked
+        let zs = lookup.copy_check(
+            layouter.namespace(|| {
+                format!("[Sinsemilla] decompose [{}] to [{}] words", name, num_words)
+            }),
+            x,
+            num_words,
+            num_bits == 0,
+        )?;
+        if num_bits > 0 {
+            lookup.copy_short_check(
+                layouter.namespace(|| {
+                    format!("[Sinsemilla] the rest of [{}] is [{}] bits", name, num_bits)
+                }),
+                zs[num_words].clone(),
+                num_bits,
+            )?;
+        }
+        Ok(())
+    }
+
     // from zcash
     // Check canonicity of y-coordinate given its LSB as a value.
     // Also, witness the LSB and return the witnessed cell.
@@ -802,6 +949,8 @@ impl CommitConfig {
         inputs: &InputConfigMap,
         r: Option<pallas::Scalar>,
         is_short_commit: bool,
+        is_deterministic: bool, // the hash point only, r is not committed
+        personalization: &[u8], // the first message piece, see CommitSpec::personalization
         is_with_witnesses: bool,
         _debug_info: &mut Option<BTreeMap<String, Vec<String>>>,
     ) -> Result<PointResult, Error> {
@@ -811,6 +960,29 @@ impl CommitConfig {
             .filter(|gate| CellType::is_input_cell(gate.cells[0].celltype))
             .collect::<Vec<_>>();
 
+        // the inputs committed are also constrained to their widths if configured
+        for gate in &input_gates {
+            let cell = &gate.cells[0];
+            if cell.attr.as_deref() != Some(ATTRIBUTE_RANGE_CHECK) {
+                continue;
+            }
+
+            let v = inputs.get(&cell.name);
+            assert_synthesize_error!(
+                cell.celltype == CellType::Input && v.is_some() && v.unwrap().0.is_some(),
+                &format!(
+                    "[Sinsemilla] range checked input [{}] should be an assigned Input",
+                    cell.name
+                )
+            );
+            self.range_check_width(
+                layouter.namespace(|| "[Sinsemilla] range check"),
+                &cell.name,
+                v.unwrap().0.clone().unwrap(),
+                cell.width,
+            )?;
+        }
+
         let mut prime_cells = HashMap::new();
         let mut z13s: BTreeMap<String, (usize, Option<AssignedCell<pallas::Base, pallas::Base>>)> =
             BTreeMap::new();
@@ -920,8 +1092,8 @@ impl CommitConfig {
             let (_total_width, _slices) = &values;
 
             // y_lsb
-            //if y.is_some() && *_start >= FILED_SIZE && *_width == 1 {
-            if *_start >= FILED_SIZE && *_width == 1 {
+            //if y.is_some() && *_start >= FIELD_SIZE && *_width == 1 {
+            if *_start >= FIELD_SIZE && *_width == 1 {
                 ylsbs.insert(_hname.clone(), (name.clone(), (*_slices).len() - 1));
             }
         }
@@ -930,7 +1102,24 @@ impl CommitConfig {
         let mut primes = BTreeMap::new();
         let mut message_pieces = Vec::new();
 
+        // personalization as the first message piece, constrained to the constant of the commit
+        if let Some((value, num_words)) = personalization_piece(personalization) {
+            let piece = MessagePiece::from_field_elem(
+                chip.clone(),
+                layouter.namespace(|| "[Sinsemilla] personalization"),
+                Some(value),
+                num_words,
+            )?;
+            layouter.assign_region(
+                || "[Sinsemilla] personalization is constant",
+                |mut region| region.constrain_constant(piece.inner().cell_value().cell(), value),
+            )?;
+            message_pieces.push(piece);
+        }
+        let piece_offset = message_pieces.len();
+
         for (index, (name, values)) in piece_slices.iter_mut().enumerate() {
+            let index = index + piece_offset;
             let total_width = values.0;
 
             if values.1.len() > 1 {
@@ -1042,13 +1231,25 @@ impl CommitConfig {
         }
 
         let message = Message::from_pieces(chip.clone(), message_pieces);
-        let domain = CommitDomain::new(chip.clone(), ecc_chip, &self.commit_domain);
+        let domain = CommitDomain::new(chip.clone(), ecc_chip.clone(), &self.commit_domain);
+        // a witnessed r = 0 would not be constrained, a deterministic commit skips the [r] R term
+        // instead, M + [0] R = M
+        let hash_domain =
+            HashDomain::new(chip.clone(), ecc_chip, &self.commit_domain.hash_domain());
         let point = if is_short_commit {
-            let (short_cm, zs) = domain.short_commit(
-                layouter.namespace(|| "[Sinsemilla] Short Commit Hash"),
-                message,
-                r,
-            )?;
+            let (short_cm, zs) = if is_deterministic {
+                let (p, zs) = hash_domain.hash_to_point(
+                    layouter.namespace(|| "[Sinsemilla] Short Commit Hash (deterministic)"),
+                    message,
+                )?;
+                (p.extract_p(), zs)
+            } else {
+                domain.short_commit(
+                    layouter.namespace(|| "[Sinsemilla] Short Commit Hash"),
+                    message,
+                    r,
+                )?
+            };
 
             {
                 ///////// handle zs /////////
@@ -1071,11 +1272,18 @@ impl CommitConfig {
 
             PointResult::X(Some(short_cm))
         } else {
-            let (cm, zs) = domain.commit(
-                layouter.namespace(|| "[Sinsemilla] Commit Hash"),
-                message,
-                r,
-            )?;
+            let (cm, zs) = if is_deterministic {
+                hash_domain.hash_to_point(
+                    layouter.namespace(|| "[Sinsemilla] Commit Hash (deterministic)"),
+                    message,
+                )?
+            } else {
+                domain.commit(
+                    layouter.namespace(|| "[Sinsemilla] Commit Hash"),
+                    message,
+                    r,
+                )?
+            };
 
             {
                 ///////// handle zs /////////
@@ -1102,7 +1310,7 @@ impl CommitConfig {
         // canonicity checks (need whole pieces)
         for gate in &input_gates {
             if gate.cells.len() > 2
-                && gate.cells[0].width >= FILED_SIZE // >= 2^254 + 2^126
+                && gate.cells[0].width >= FIELD_SIZE // >= 2^254 + 2^126
                 && gate.cells[1].width < MAX_CANON_OFFSET
             {
                 let prime_data = gate.cells[1..3]
@@ -1187,11 +1395,19 @@ impl CommitConfig {
             let gate = &self.gates[i];
             let mut count: usize = 0;
 
+            // a region can not be referred before its first row, so the selector is moved
+            // down one row if any cell of the gate is placed at the previous row
+            let base_offset = if gate.cells.iter().any(|cell| cell.row == RowType::Prev) {
+                1
+            } else {
+                0
+            };
+
             desc = format!("[Sinsemilla] Commit MessagePiece [{}]", gate.cells[0].name);
             layouter.assign_region(
-                || base::string_to_static_str(&desc),
+                || base::region_name(&desc),
                 |mut region| {
-                    self.qs[i].enable(&mut region, 0)?;
+                    self.qs[i].enable(&mut region, base_offset)?;
                     count += 1;
 
                     for cell in &gate.cells {
@@ -1205,9 +1421,9 @@ impl CommitConfig {
                         }
 
                         let row = match cell.row {
-                            RowType::Cur => 0,
-                            RowType::Next => 1,
-                            _ => panic!("[Sinsemilla] wrong row configured: [{:?}]", cell.row),
+                            RowType::Prev => base_offset - 1,
+                            RowType::Cur => base_offset,
+                            RowType::Next => base_offset + 1,
                         };
 
                         let cell_value = if CellType::is_piece_or_slice_cell(cell.celltype) {
@@ -1238,7 +1454,7 @@ impl CommitConfig {
 
                             let value = halo2api::assign_advice(
                                 &mut region,
-                                || base::string_to_static_str(&cell.name),
+                                || base::region_name(&cell.name),
                                 &self.advices,
                                 cell.col,
                                 row,
@@ -1259,7 +1475,7 @@ impl CommitConfig {
                             halo2api::copy_advice(
                                 &assigned_values[&cell.name].as_ref().unwrap(),
                                 &mut region,
-                                || base::string_to_static_str(&cell.name),
+                                || base::region_name(&cell.name),
                                 &self.advices,
                                 cell.col,
                                 row,
@@ -1276,7 +1492,7 @@ impl CommitConfig {
                             halo2api::copy_advice(
                                 &cell_value,
                                 &mut region,
-                                || base::string_to_static_str(&cell.name),
+                                || base::region_name(&cell.name),
                                 &self.advices,
                                 cell.col,
                                 row,
@@ -1,12 +1,19 @@
-use ff::Field;
-use funty::Signed;
-use group::{Curve, GroupEncoding};
+use ff::{Field, PrimeField};
+use group::{Curve, Group, GroupEncoding};
+use halo2_gadgets::{
+    poseidon::{Pow5Chip as PoseidonChip, Pow5Config as PoseidonConfig},
+    primitives::poseidon,
+};
 use halo2_proofs::{
-    arithmetic::{CurveAffine, CurveExt},
-    dev::MockProver,
+    arithmetic::CurveAffine,
+    dev::{MockProver, VerifyFailure},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Fixed},
 };
 use pasta_curves::pallas;
-use rand::{rngs::OsRng, RngCore};
+use rand::{
+    rngs::{OsRng, StdRng},
+    RngCore, SeedableRng,
+};
 
 use super::constants;
 use crate::circuit::base::*;
@@ -18,6 +25,7 @@ use crate::global;
 use crate::primitives::{
     commitment::*, keys::*, nippoint::*, nullifier::*, tree::*, utils::*, value::*,
 };
+use crate::sinsemilla::circuit::CommitInputBuilder;
 use crate::types::*;
 
 pub const NUM_WINDOWS: usize = halo2_gadgets::ecc::chip::constants::NUM_WINDOWS; //85
@@ -37,647 +45,340 @@ const VALUE_COMMIT_V_DOMAIN_NAME: &'static str = "ValueCommitV_Test";
 
 const SHORT_COMMIT_DOMAIN_NAME: &'static str = "short_commit_domain_name_test";
 const COMMIT_DOMAIN_NAME: &'static str = "commit_domain_name_test";
+const AUTO_COMMIT_DOMAIN_NAME: &'static str = "auto_commit_domain_name_test";
+const CHAINED_COMMIT_DOMAIN_NAME: &'static str = "chained_commit_domain_name_test";
 
 type ValueNumType = i64;
 
 #[derive(Copy, Clone, Debug, Default)]
 struct ICTest {}
 
-// refer https://github.com/zcash/orchard/blob/main/src/circuit.rs
-impl ICConfig for ICTest {
-    type Value = ValueNumType;
-
-    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
-        let gate_configs = vec![
-            (
-                "old_v - new_v = magnitude * sign".to_string(),
-                vec![
-                    (
-                        format!("{}v", SIGN_OF_OLD_VALUE), //old_v
-                        ATTRIBUTE_VALUE.to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        0,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        format!("{}v", SIGN_OF_NEW_VALUE), //new_v
-                        ATTRIBUTE_VALUE.to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        1,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        format!("{}v", SIGN_OF_MAGNITUDE), //magnitude_v
-                        "".to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        2,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        format!("{}v", SIGN_OF_SIGN), //sign_v
-                        "".to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        3,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        format!("{}merklecrh_cm", SIGN_OF_ANCHOR), //anchor_merklecrh_cm
-                        format!(
-                            "{}merklecrh_cm#{}#cm_old",
-                            ATTRIBUTE_MERKLEPATH, MERKLE_DOMAIN_NAME
-                        ),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        4,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        "pub_input_anchor".to_string(),
-                        "".to_string(),
-                        "Instance".to_string(),
-                        "Advice".to_string(),
-                        5,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        "enable_spends".to_string(),
-                        "".to_string(),
-                        "Instance".to_string(),
-                        "Advice".to_string(),
-                        6,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        "enable_outputs".to_string(),
-                        "".to_string(),
-                        "Instance".to_string(),
-                        "Advice".to_string(),
-                        7,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                ],
-            ),
-            (
-                "poseidon_hash(nk, rho_old) + psi_old".to_string(),
-                vec![
-                    (
-                        "sum".to_string(),
-                        "".to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        6,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        "hash_old".to_string(),
-                        "".to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        7,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
-                    (
-                        "psi_old".to_string(),
-                        "".to_string(),
-                        "Input".to_string(),
-                        "Advice".to_string(),
-                        8,
-                        "Cur".to_string(),
-                        FILED_SIZE,
-                    ),
+impl ICTest {
+    // ivk = ShortCommit(ak, nk)
+    fn short_commit_scheme() -> CommitScheme {
+        CommitScheme {
+            spec: CommitSpec {
+                is_short: true,
+                output_name: "ivk".to_string(),
+                domain: SHORT_COMMIT_DOMAIN_NAME.to_string(),
+                num_windows: NUM_WINDOWS_SHORT,
+                inputs: vec![
+                    ("ak".to_string(), "".to_string()),
+                    ("nk".to_string(), "".to_string()),
                 ],
-            ),
-        ];
-
-        let algo_configs = vec![
-            vec![
+                randomness_name: "rivk".to_string(),
+                column_group: ColumnGroup::First,
+                personalization: Vec::new(),
+            },
+            piece_gates: vec![
                 (
-                    "".to_string(),
-                    "old_v - new_v - (magnitude_v * sign_v)".to_string(),
+                    "gate b".into(),
                     vec![
                         (
+                            "b".to_string(),
                             "".to_string(),
-                            "v".to_string(),
-                            "old_v - new_v".to_string(),
-                            ("old_v".to_string(), "".to_string()),
-                            "sub".to_string(),
-                            Some(("new_v".to_string(), "".to_string())),
+                            "Piece".to_string(),
+                            "Advice".to_string(),
+                            0,
+                            "Cur".to_string(),
+                            10,
                         ),
-                        //the above result "sub" the below because the operator next (the first item below) is sub!
                         (
-                            "sub".to_string(),
+                            "b_0".to_string(),
                             "".to_string(),
-                            "magnitude_v * sign_v".to_string(),
-                            ("magnitude_v".to_string(), "".to_string()),
-                            "mul".to_string(),
-                            Some(("sign_v".to_string(), "".to_string())),
+                            "Slice".to_string(),
+                            "Advice".to_string(),
+                            1,
+                            "Cur".to_string(),
+                            4,
+                        ),
+                        (
+                            "b_1".to_string(),
+                            "".to_string(),
+                            "TopSlice".to_string(),
+                            "Advice".to_string(),
+                            0,
+                            "Next".to_string(),
+                            1,
+                        ),
+                        (
+                            "b_2".to_string(),
+                            "".to_string(),
+                            "Slice".to_string(),
+                            "Advice".to_string(),
+                            1,
+                            "Next".to_string(),
+                            5,
                         ),
                     ],
                 ),
                 (
-                    "".to_string(),
-                    "Either old_v = 0, or anchor equals public input".to_string(),
+                    "gate d".into(),
                     vec![
                         (
+                            "d".to_string(),
                             "".to_string(),
+                            "Piece".to_string(),
+                            "Advice".to_string(),
+                            0,
+                            "Cur".to_string(),
+                            10,
+                        ),
+                        (
+                            "d_0".to_string(),
                             "".to_string(),
-                            "old_v".to_string(),
-                            ("old_v".to_string(), "".to_string()),
-                            "".to_string(),
-                            None,
+                            "Slice".to_string(),
+                            "Advice".to_string(),
+                            1,
+                            "Cur".to_string(),
+                            9,
                         ),
-                        // above "mul" below
                         (
-                            "mul".to_string(),
+                            "d_1".to_string(),
                             "".to_string(),
-                            "anchor - pub_input_anchor".to_string(),
-                            ("anchor_merklecrh_cm".to_string(), "".to_string()),
-                            "sub".to_string(),
-                            Some(("pub_input_anchor".to_string(), "".to_string())),
+                            "TopSlice".to_string(),
+                            "Advice".to_string(),
+                            1,
+                            "Next".to_string(),
+                            1,
                         ),
                     ],
                 ),
+            ],
+            input_gates: vec![
                 (
-                    "".to_string(),
-                    "old_v = 0 or enable_spends = 1".to_string(),
+                    "gate ak".into(),
                     vec![
                         (
+                            "ak".to_string(),
                             "".to_string(),
+                            "Input".to_string(),
+                            "Advice".to_string(),
+                            0,
+                            "Cur".to_string(),
+                            FIELD_SIZE,
+                        ),
+                        (
+                            "a".to_string(),
                             "".to_string(),
-                            "old_v".to_string(),
-                            ("old_v".to_string(), "".to_string()),
-                            "".to_string(),
-                            None,
+                            "Piece".to_string(),
+                            "Advice".to_string(),
+                            1,
+                            "Cur".to_string(),
+                            250,
                         ),
                         (
-                            "mul".to_string(),
+                            "b_0".to_string(),
                             "".to_string(),
-                            "1 - enable_spends".to_string(),
-                            ("enable_spends".to_string(), "".to_string()),
-                            "boolean_neg".to_string(),
-                            None,
+                            "CanonicityCheckSlice".to_string(),
+                            "Advice".to_string(),
+                            2,
+                            "Cur".to_string(),
+                            4,
                         ),
-                    ],
-                ),
-                (
-                    "".to_string(),
-                    "old_v = 0 or enable_spends = 1".to_string(),
-                    vec![
                         (
+                            "b_1".to_string(),
                             "".to_string(),
+                            "Slice".to_string(),
+                            "Advice".to_string(),
+                            3,
+                            "Cur".to_string(),
+                            1,
+                        ),
+                        (
+                            "z13_a".to_string(),
                             "".to_string(),
-                            "old_v".to_string(),
-                            ("old_v".to_string(), "".to_string()),
+                            "CanonicityCheckZ13".to_string(),
+                            "Advice".to_string(),
+                            1,
+                            "Next".to_string(),
+                            FIELD_SIZE,
+                        ),
+                        (
+                            "prime_a".to_string(),
                             "".to_string(),
-                            None,
+                            "PrimeCheck".to_string(),
+                            "Advice".to_string(),
+                            2,
+                            "Next".to_string(),
+                            FIELD_SIZE,
                         ),
                         (
-                            "mul".to_string(),
+                            "z13_prime_a".to_string(),
                             "".to_string(),
-                            "1 - enable_spends".to_string(),
-                            ("enable_spends".to_string(), "".to_string()),
-                            "boolean_neg".to_string(),
-                            None,
+                            "CanonicityCheck".to_string(),
+                            "Advice".to_string(),
+                            3,
+                            "Next".to_string(),
+                            FIELD_SIZE,
                         ),
                     ],
                 ),
-            ],
-            // the followings for constraints
-            vec![(
-                "".to_string(),
-                "poseidon_hash(nk, rho_old) + psi_old".to_string(),
-                vec![
-                    (
-                        "".to_string(),
-                        "".to_string(),
-                        "hash_old + psi_old".to_string(),
-                        ("hash_old".to_string(), "".to_string()),
-                        "add".to_string(),
-                        Some(("psi_old".to_string(), "".to_string())),
-                    ),
-                    (
-                        "sub".to_string(),
-                        "".to_string(),
-                        "sum".to_string(),
-                        ("sum".to_string(), "".to_string()),
-                        "".to_string(),
-                        None,
-                    ),
-                ],
-            )],
-            vec![(
-                "constraint".to_string(),
-                "net_cv =  [v] ValueCommitV + [rcv] ValueCommitR".to_string(),
-                vec![
-                    (
-                        "".to_string(),
-                        "commitment".to_string(),
-                        "[v] ValueCommitV".to_string(),
-                        ("v".to_string(), "MagnitudeSign".to_string()),
-                        "mul".to_string(),
-                        Some(("ValueCommitV".to_string(), "ShortField".to_string())),
-                    ),
-                    (
-                        "add".to_string(),
-                        "net_cv".to_string(),
-                        "[rcv] ValueCommitR".to_string(),
-                        ("rcv".to_string(), "Scalar".to_string()),
-                        "mul".to_string(),
-                        Some(("ValueCommitR".to_string(), "FullField".to_string())),
-                    ),
-                ],
-            )],
-            vec![(
-                "constraint".to_string(),
-                "nf_old = cm_old + [poseidon_hash(nk, rho_old) + psi_old]NullifierK".to_string(),
-                vec![
-                    (
-                        "".to_string(),
-                        "hash_old".to_string(),
-                        "poseidon_hash(nk, rho_old)".to_string(),
-                        ("nk".to_string(), "Cell".to_string()),
-                        "poseidon".to_string(),
-                        Some(("rho_old".to_string(), "Cell".to_string())),
-                    ),
-                    (
-                        "add".to_string(),
-                        "sum".to_string(),
-                        "hash_old + psi_old".to_string(),
-                        ("psi_old".to_string(), "Field".to_string()),
-                        "".to_string(),
-                        None,
-                    ),
-                    (
-                        "mul".to_string(),
-                        "product".to_string(),
-                        "[sum]NullifierK".to_string(),
-                        ("NullifierK".to_string(), "BaseField".to_string()),
-                        "".to_string(),
-                        None,
-                    ),
-                    (
-                        "add".to_string(),
-                        "nf_old".to_string(),
-                        "product + cm_old".to_string(),
-                        ("cm_old".to_string(), "Point".to_string()),
-                        "".to_string(),
-                        None,
-                    ),
-                ],
-            )],
-            vec![(
-                "constraint".to_string(),
-                "rk = [alpha] SpendAuthG + ak".to_string(),
-                vec![
-                    (
-                        "".to_string(),
-                        "alpha_commitment".to_string(),
-                        "[alpha] AuthG".to_string(),
-                        ("alpha".to_string(), "Scalar".to_string()),
-                        "mul".to_string(),
-                        Some(("AuthG".to_string(), "FullField".to_string())),
-                    ),
-                    (
-                        "add".to_string(),
-                        "rk".to_string(),
-                        "alpha_commitment + ak".to_string(),
-                        ("ak".to_string(), "NIPoint".to_string()),
-                        "".to_string(),
-                        None,
-                    ),
-                ],
-            )],
-            vec![(
-                "constraint-commit".to_string(),
-                "derived_pk_d_old = [ivk] g_d_old".to_string(),
-                vec![(
-                    "".to_string(),
-                    "constraint_derived_pk_d_old".to_string(),
-                    "[ivk] g_d_old".to_string(),
-                    ("ivk".to_string(), "CommitCell".to_string()),
-                    "mul".to_string(),
-                    Some(("g_d_old".to_string(), "NIPoint".to_string())),
-                )],
-            )],
-            vec![(
-                "constraint-commit".to_string(),
-                "check derived_cm".to_string(),
-                vec![(
-                    "".to_string(),
-                    "derived_cm".to_string(),
-                    "".to_string(),
-                    ("derived_cm".to_string(), "CommitCell".to_string()),
-                    "".to_string(),
-                    None,
-                )],
-            )],
-        ];
-
-        (gate_configs, algo_configs)
-    }
-
-    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
-        if domain == SHORT_COMMIT_DOMAIN_NAME {
-            Some(vec![
                 (
-                    "gate b".to_string(),
+                    "gate nk".into(),
                     vec![
                         (
-                            "b".to_string(),
+                            "nk".to_string(),
                             "".to_string(),
-                            "Piece".to_string(),
+                            "Input".to_string(),
                             "Advice".to_string(),
                             0,
                             "Cur".to_string(),
-                            10,
+                            FIELD_SIZE,
                         ),
                         (
-                            "b_0".to_string(),
+                            "b_2".to_string(),
                             "".to_string(),
                             "Slice".to_string(),
                             "Advice".to_string(),
                             1,
                             "Cur".to_string(),
-                            4,
+                            5,
                         ),
                         (
-                            "b_1".to_string(),
+                            "c".to_string(),
                             "".to_string(),
-                            "TopSlice".to_string(),
+                            "Slice".to_string(),
                             "Advice".to_string(),
-                            0,
-                            "Next".to_string(),
-                            1,
+                            2,
+                            "Cur".to_string(),
+                            240,
                         ),
                         (
-                            "b_2".to_string(),
+                            "d_0".to_string(),
                             "".to_string(),
-                            "Slice".to_string(),
+                            "CanonicityCheckSlice".to_string(),
                             "Advice".to_string(),
-                            1,
-                            "Next".to_string(),
-                            5,
+                            3,
+                            "Cur".to_string(),
+                            9,
                         ),
-                    ],
-                ),
-                (
-                    "gate d".to_string(),
-                    vec![
                         (
-                            "d".to_string(),
+                            "d_1".to_string(),
                             "".to_string(),
-                            "Piece".to_string(),
+                            "Slice".to_string(),
                             "Advice".to_string(),
                             0,
-                            "Cur".to_string(),
-                            10,
+                            "Next".to_string(),
+                            1,
                         ),
                         (
-                            "d_0".to_string(),
+                            "z13_c".to_string(),
                             "".to_string(),
-                            "Slice".to_string(),
+                            "CanonicityCheckZ13".to_string(),
                             "Advice".to_string(),
                             1,
-                            "Cur".to_string(),
-                            9,
+                            "Next".to_string(),
+                            FIELD_SIZE,
                         ),
                         (
-                            "d_1".to_string(),
+                            "prime_b2_c".to_string(),
                             "".to_string(),
-                            "TopSlice".to_string(),
+                            "PrimeCheck".to_string(),
                             "Advice".to_string(),
-                            1,
+                            2,
                             "Next".to_string(),
-                            1,
+                            FIELD_SIZE,
                         ),
-                    ],
-                ),
-                (
-                    "gate ak".to_string(),
-                    vec![
                         (
-                            "ak".to_string(),
+                            "z14_prime_b2_c".to_string(),
                             "".to_string(),
-                            "Input".to_string(),
+                            "CanonicityCheck".to_string(),
                             "Advice".to_string(),
-                            0,
-                            "Cur".to_string(),
-                            FILED_SIZE,
+                            3,
+                            "Next".to_string(),
+                            FIELD_SIZE,
                         ),
+                    ],
+                ),
+            ],
+        }
+    }
+
+    // derived_cm = Commit(g_d_old, pk_d_new, new_v, nf_old, psi_old)
+    fn commit_scheme() -> CommitScheme {
+        CommitScheme {
+            spec: CommitSpec {
+                is_short: false,
+                output_name: "derived_cm".to_string(),
+                domain: COMMIT_DOMAIN_NAME.to_string(),
+                num_windows: NUM_WINDOWS,
+                inputs: vec![
+                    ("g_d_old".to_string(), "NIPoint".to_string()),
+                    ("pk_d_new".to_string(), "NIPoint".to_string()), //must specify type here
+                    ("new_v".to_string(), "".to_string()),
+                    ("nf_old".to_string(), "".to_string()),
+                    ("psi_old".to_string(), "".to_string()),
+                ],
+                randomness_name: "rcm".to_string(),
+                column_group: ColumnGroup::First,
+                personalization: Vec::new(),
+            },
+            piece_gates: vec![
+                (
+                    "gate b".into(),
+                    vec![
                         (
-                            "a".to_string(),
+                            "b".to_string(),
                             "".to_string(),
                             "Piece".to_string(),
                             "Advice".to_string(),
-                            1,
+                            6,
                             "Cur".to_string(),
-                            250,
+                            10,
                         ),
                         (
                             "b_0".to_string(),
                             "".to_string(),
-                            "CanonicityCheckSlice".to_string(),
+                            "Slice".to_string(),
                             "Advice".to_string(),
-                            2,
+                            7,
                             "Cur".to_string(),
                             4,
                         ),
                         (
                             "b_1".to_string(),
                             "".to_string(),
-                            "Slice".to_string(),
+                            "TopSlice".to_string(),
                             "Advice".to_string(),
-                            3,
+                            8,
                             "Cur".to_string(),
                             1,
                         ),
                         (
-                            "z13_a".to_string(),
-                            "".to_string(),
-                            "CanonicityCheckZ13".to_string(),
-                            "Advice".to_string(),
-                            1,
-                            "Next".to_string(),
-                            FILED_SIZE,
-                        ),
-                        (
-                            "prime_a".to_string(),
+                            "b_2".to_string(),
                             "".to_string(),
-                            "PrimeCheck".to_string(),
+                            "Slice".to_string(),
                             "Advice".to_string(),
-                            2,
+                            7,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            1,
                         ),
                         (
-                            "z13_prime_a".to_string(),
+                            "b_3".to_string(),
                             "".to_string(),
-                            "CanonicityCheck".to_string(),
+                            "Slice".to_string(),
                             "Advice".to_string(),
-                            3,
+                            8,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            4,
                         ),
                     ],
                 ),
                 (
-                    "gate nk".to_string(),
+                    "gate d".into(),
                     vec![
                         (
-                            "nk".to_string(),
+                            "d".to_string(),
                             "".to_string(),
-                            "Input".to_string(),
+                            "Piece".to_string(),
                             "Advice".to_string(),
-                            0,
+                            6,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            60,
                         ),
                         (
-                            "b_2".to_string(),
-                            "".to_string(),
-                            "Slice".to_string(),
-                            "Advice".to_string(),
-                            1,
-                            "Cur".to_string(),
-                            5,
-                        ),
-                        (
-                            "c".to_string(),
-                            "".to_string(),
-                            "Slice".to_string(),
-                            "Advice".to_string(),
-                            2,
-                            "Cur".to_string(),
-                            240,
-                        ),
-                        (
-                            "d_0".to_string(),
-                            "".to_string(),
-                            "CanonicityCheckSlice".to_string(),
-                            "Advice".to_string(),
-                            3,
-                            "Cur".to_string(),
-                            9,
-                        ),
-                        (
-                            "d_1".to_string(),
-                            "".to_string(),
-                            "Slice".to_string(),
-                            "Advice".to_string(),
-                            0,
-                            "Next".to_string(),
-                            1,
-                        ),
-                        (
-                            "z13_c".to_string(),
-                            "".to_string(),
-                            "CanonicityCheckZ13".to_string(),
-                            "Advice".to_string(),
-                            1,
-                            "Next".to_string(),
-                            FILED_SIZE,
-                        ),
-                        (
-                            "prime_b2_c".to_string(),
-                            "".to_string(),
-                            "PrimeCheck".to_string(),
-                            "Advice".to_string(),
-                            2,
-                            "Next".to_string(),
-                            FILED_SIZE,
-                        ),
-                        (
-                            "z14_prime_b2_c".to_string(),
-                            "".to_string(),
-                            "CanonicityCheck".to_string(),
-                            "Advice".to_string(),
-                            3,
-                            "Next".to_string(),
-                            FILED_SIZE,
-                        ),
-                    ],
-                ),
-            ])
-        } else if domain == COMMIT_DOMAIN_NAME {
-            Some(vec![
-                (
-                    "gate b".to_string(),
-                    vec![
-                        (
-                            "b".to_string(),
-                            "".to_string(),
-                            "Piece".to_string(),
-                            "Advice".to_string(),
-                            6,
-                            "Cur".to_string(),
-                            10,
-                        ),
-                        (
-                            "b_0".to_string(),
-                            "".to_string(),
-                            "Slice".to_string(),
-                            "Advice".to_string(),
-                            7,
-                            "Cur".to_string(),
-                            4,
-                        ),
-                        (
-                            "b_1".to_string(),
-                            "".to_string(),
-                            "TopSlice".to_string(),
-                            "Advice".to_string(),
-                            8,
-                            "Cur".to_string(),
-                            1,
-                        ),
-                        (
-                            "b_2".to_string(),
-                            "".to_string(),
-                            "Slice".to_string(),
-                            "Advice".to_string(),
-                            7,
-                            "Next".to_string(),
-                            1,
-                        ),
-                        (
-                            "b_3".to_string(),
-                            "".to_string(),
-                            "Slice".to_string(),
-                            "Advice".to_string(),
-                            8,
-                            "Next".to_string(),
-                            4,
-                        ),
-                    ],
-                ),
-                (
-                    "gate d".to_string(),
-                    vec![
-                        (
-                            "d".to_string(),
-                            "".to_string(),
-                            "Piece".to_string(),
-                            "Advice".to_string(),
-                            6,
-                            "Cur".to_string(),
-                            60,
-                        ),
-                        (
-                            "d_0".to_string(),
+                            "d_0".to_string(),
                             "".to_string(),
                             "TopSlice".to_string(),
                             "Advice".to_string(),
@@ -715,7 +416,7 @@ impl ICConfig for ICTest {
                     ],
                 ),
                 (
-                    "gate e".to_string(),
+                    "gate e".into(),
                     vec![
                         (
                             "e".to_string(),
@@ -747,7 +448,7 @@ impl ICConfig for ICTest {
                     ],
                 ),
                 (
-                    "gate g".to_string(),
+                    "gate g".into(),
                     vec![
                         (
                             "g".to_string(),
@@ -788,7 +489,7 @@ impl ICConfig for ICTest {
                     ],
                 ),
                 (
-                    "gate h".to_string(),
+                    "gate h".into(),
                     vec![
                         (
                             "h".to_string(),
@@ -828,8 +529,10 @@ impl ICConfig for ICTest {
                         ),
                     ],
                 ),
+            ],
+            input_gates: vec![
                 (
-                    "gate g_d_old".to_string(),
+                    "gate g_d_old".into(),
                     vec![
                         (
                             "g_d_old".to_string(),
@@ -838,7 +541,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             6,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "a".to_string(),
@@ -883,7 +586,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "prime_a".to_string(),
@@ -892,7 +595,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             8,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "z13_prime_a".to_string(),
@@ -901,12 +604,12 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                     ],
                 ),
                 (
-                    "gate pk_d_new".to_string(),
+                    "gate pk_d_new".into(),
                     vec![
                         (
                             "pk_d_new".to_string(),
@@ -915,7 +618,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             6,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "b_3".to_string(),
@@ -960,7 +663,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "prime_b3_c".to_string(),
@@ -969,7 +672,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             8,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "z14_prime_b3_c".to_string(),
@@ -978,16 +681,16 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                     ],
                 ),
                 (
-                    "gate new_v".to_string(),
+                    "gate new_v".into(),
                     vec![
                         (
                             "new_v".to_string(),
-                            "".to_string(),
+                            ATTRIBUTE_RANGE_CHECK.to_string(), // committed and range checked to 64 bits
                             "Input".to_string(),
                             "Advice".to_string(),
                             6,
@@ -1024,7 +727,7 @@ impl ICConfig for ICTest {
                     ],
                 ),
                 (
-                    "gate nf_old".to_string(),
+                    "gate nf_old".into(),
                     vec![
                         (
                             "nf_old".to_string(),
@@ -1033,7 +736,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             6,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "e_1".to_string(),
@@ -1069,7 +772,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "prime_e1_f".to_string(),
@@ -1078,7 +781,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             8,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "z14_prime_e1_f".to_string(),
@@ -1087,12 +790,12 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                     ],
                 ),
                 (
-                    "gate psi_old".to_string(),
+                    "gate psi_old".into(),
                     vec![
                         (
                             "psi_old".to_string(),
@@ -1101,7 +804,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             6,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "g_1".to_string(),
@@ -1146,7 +849,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Cur".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "prime_g1_g2".to_string(),
@@ -1155,7 +858,7 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             8,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                         (
                             "z13_prime_g1_g2".to_string(),
@@ -1164,399 +867,2793 @@ impl ICConfig for ICTest {
                             "Advice".to_string(),
                             9,
                             "Next".to_string(),
-                            FILED_SIZE,
+                            FIELD_SIZE,
                         ),
                     ],
                 ),
-            ])
-        } else {
-            None
+            ],
         }
     }
+}
+
+// refer https://github.com/zcash/orchard/blob/main/src/circuit.rs
+impl ICConfig for ICTest {
+    type Value = ValueNumType;
 
-    fn get_commit_configs(
-    ) -> Option<Vec<(bool, String, (String, usize), Vec<(String, String)>, String)>> {
-        Some(vec![
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let gate_configs = vec![
             (
-                true,
-                "ivk".to_string(),
-                (SHORT_COMMIT_DOMAIN_NAME.to_string(), NUM_WINDOWS_SHORT),
+                "old_v - new_v = magnitude * sign".into(),
                 vec![
-                    ("ak".to_string(), "".to_string()),
-                    ("nk".to_string(), "".to_string()),
+                    (
+                        format!("{}v", SIGN_OF_OLD_VALUE), //old_v
+                        ATTRIBUTE_VALUE.to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        0,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        format!("{}v", SIGN_OF_NEW_VALUE), //new_v
+                        ATTRIBUTE_VALUE.to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        1,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        format!("{}v", SIGN_OF_MAGNITUDE), //magnitude_v
+                        "".to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        2,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        format!("{}v", SIGN_OF_SIGN), //sign_v
+                        "".to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        3,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        format!("{}merklecrh_cm", SIGN_OF_ANCHOR), //anchor_merklecrh_cm
+                        format!(
+                            "{}merklecrh_cm#{}#cm_old",
+                            ATTRIBUTE_MERKLEPATH, MERKLE_DOMAIN_NAME
+                        ),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        4,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        "pub_input_anchor".to_string(),
+                        "".to_string(),
+                        "Instance".to_string(),
+                        "Advice".to_string(),
+                        5,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        "enable_spends".to_string(),
+                        "".to_string(),
+                        "Instance".to_string(),
+                        "Advice".to_string(),
+                        6,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        "enable_outputs".to_string(),
+                        "".to_string(),
+                        "Instance".to_string(),
+                        "Advice".to_string(),
+                        7,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
                 ],
-                "rivk".to_string(),
             ),
             (
-                false,
-                "derived_cm".to_string(),
-                (COMMIT_DOMAIN_NAME.to_string(), NUM_WINDOWS),
+                "poseidon_hash(nk, rho_old) + psi_old".into(),
                 vec![
-                    ("g_d_old".to_string(), "NIPoint".to_string()),
-                    ("pk_d_new".to_string(), "NIPoint".to_string()), //must specify type here
-                    ("new_v".to_string(), "".to_string()),
-                    ("nf_old".to_string(), "".to_string()),
-                    ("psi_old".to_string(), "".to_string()),
+                    (
+                        "sum".to_string(),
+                        "".to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        6,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        "hash_old".to_string(),
+                        "".to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        7,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
+                    (
+                        "psi_old".to_string(),
+                        "".to_string(),
+                        "Input".to_string(),
+                        "Advice".to_string(),
+                        8,
+                        "Cur".to_string(),
+                        FIELD_SIZE,
+                    ),
                 ],
-                "rcm".to_string(),
             ),
-        ])
-    }
-}
-
-impl InstanceOrder for ICTest {
-    fn get_instance_order() -> Vec<String> {
-        vec![
-            "enable_spends".to_string(),
-            "enable_outputs".to_string(),
-            "pub_input_anchor".to_string(),
-            "nf_old".to_string(),
-            "net_cv_x".to_string(),
-            "net_cv_y".to_string(),
-            "rk_x".to_string(),
-            "rk_y".to_string(),
-            "derived_cm".to_string(),
-        ]
-    }
-}
+        ];
 
-#[derive(Debug, Clone)]
-pub struct ValidatingKeyRandomizer;
-impl Randomizer for ValidatingKeyRandomizer {
-    fn randomize(
-        base: &pallas::Point,
-        randomizer: &pallas::Scalar,
+        let mut algo_configs = vec![
+            vec![
+                (
+                    "".to_string(),
+                    "old_v - new_v - (magnitude_v * sign_v)".to_string(),
+                    vec![
+                        (
+                            "".to_string(),
+                            "v".to_string(),
+                            "old_v - new_v".to_string(),
+                            ("old_v".to_string(), "".to_string()),
+                            "sub".to_string(),
+                            Some(("new_v".to_string(), "".to_string())),
+                        ),
+                        //the above result "sub" the below because the operator next (the first item below) is sub!
+                        (
+                            "sub".to_string(),
+                            "".to_string(),
+                            "magnitude_v * sign_v".to_string(),
+                            ("magnitude_v".to_string(), "".to_string()),
+                            "mul".to_string(),
+                            Some(("sign_v".to_string(), "".to_string())),
+                        ),
+                    ],
+                ),
+                (
+                    "".to_string(),
+                    "Either old_v = 0, or anchor equals public input".to_string(),
+                    vec![
+                        (
+                            "".to_string(),
+                            "".to_string(),
+                            "old_v".to_string(),
+                            ("old_v".to_string(), "".to_string()),
+                            "".to_string(),
+                            None,
+                        ),
+                        // above "mul" below
+                        (
+                            "mul".to_string(),
+                            "".to_string(),
+                            "anchor - pub_input_anchor".to_string(),
+                            ("anchor_merklecrh_cm".to_string(), "".to_string()),
+                            "sub".to_string(),
+                            Some(("pub_input_anchor".to_string(), "".to_string())),
+                        ),
+                    ],
+                ),
+            ],
+            // the followings for constraints
+            vec![(
+                "".to_string(),
+                "poseidon_hash(nk, rho_old) + psi_old".to_string(),
+                vec![
+                    (
+                        "".to_string(),
+                        "".to_string(),
+                        "hash_old + psi_old".to_string(),
+                        ("hash_old".to_string(), "".to_string()),
+                        "add".to_string(),
+                        Some(("psi_old".to_string(), "".to_string())),
+                    ),
+                    (
+                        "sub".to_string(),
+                        "".to_string(),
+                        "sum".to_string(),
+                        ("sum".to_string(), "".to_string()),
+                        "".to_string(),
+                        None,
+                    ),
+                ],
+            )],
+            vec![(
+                "constraint".to_string(),
+                "net_cv =  [v] ValueCommitV + [rcv] ValueCommitR".to_string(),
+                vec![
+                    (
+                        "".to_string(),
+                        "commitment".to_string(),
+                        "[v] ValueCommitV".to_string(),
+                        ("v".to_string(), "MagnitudeSign".to_string()),
+                        "mul".to_string(),
+                        Some(("ValueCommitV".to_string(), "ShortField".to_string())),
+                    ),
+                    (
+                        "add".to_string(),
+                        "net_cv".to_string(),
+                        "[rcv] ValueCommitR".to_string(),
+                        ("rcv".to_string(), "Scalar".to_string()),
+                        "mul".to_string(),
+                        Some(("ValueCommitR".to_string(), "FullField".to_string())),
+                    ),
+                ],
+            )],
+            vec![(
+                "constraint".to_string(),
+                "nf_old = cm_old + [poseidon_hash(nk, rho_old) + psi_old]NullifierK".to_string(),
+                vec![
+                    (
+                        "".to_string(),
+                        "hash_old".to_string(),
+                        "poseidon_hash(nk, rho_old)".to_string(),
+                        ("nk".to_string(), "Cell".to_string()),
+                        "poseidon".to_string(),
+                        Some(("rho_old".to_string(), "Cell".to_string())),
+                    ),
+                    (
+                        "add".to_string(),
+                        "sum".to_string(),
+                        "hash_old + psi_old".to_string(),
+                        ("psi_old".to_string(), "Field".to_string()),
+                        "".to_string(),
+                        None,
+                    ),
+                    (
+                        "mul".to_string(),
+                        "product".to_string(),
+                        "[sum]NullifierK".to_string(),
+                        ("NullifierK".to_string(), "BaseField".to_string()),
+                        "".to_string(),
+                        None,
+                    ),
+                    (
+                        "add".to_string(),
+                        "nf_old".to_string(),
+                        "product + cm_old".to_string(),
+                        ("cm_old".to_string(), "Point".to_string()),
+                        "".to_string(),
+                        None,
+                    ),
+                ],
+            )],
+            vec![(
+                "constraint".to_string(),
+                "rk = [alpha] SpendAuthG + ak".to_string(),
+                vec![
+                    (
+                        "".to_string(),
+                        "alpha_commitment".to_string(),
+                        "[alpha] AuthG".to_string(),
+                        ("alpha".to_string(), "Scalar".to_string()),
+                        "mul".to_string(),
+                        Some(("AuthG".to_string(), "FullField".to_string())),
+                    ),
+                    (
+                        "add".to_string(),
+                        "rk".to_string(),
+                        "alpha_commitment + ak".to_string(),
+                        ("ak".to_string(), "NIPoint".to_string()),
+                        "".to_string(),
+                        None,
+                    ),
+                ],
+            )],
+            vec![(
+                "constraint-commit".to_string(),
+                "derived_pk_d_old = [ivk] g_d_old".to_string(),
+                vec![(
+                    "".to_string(),
+                    "constraint_derived_pk_d_old".to_string(),
+                    "[ivk] g_d_old".to_string(),
+                    ("ivk".to_string(), "CommitCell".to_string()),
+                    "mul".to_string(),
+                    Some(("g_d_old".to_string(), "NIPoint".to_string())),
+                )],
+            )],
+            vec![(
+                "constraint-commit".to_string(),
+                "check derived_cm".to_string(),
+                vec![(
+                    "".to_string(),
+                    "derived_cm".to_string(),
+                    "".to_string(),
+                    ("derived_cm".to_string(), "CommitCell".to_string()),
+                    "".to_string(),
+                    None,
+                )],
+            )],
+        ];
+
+        algo_configs[0].extend(enable_algo_configs(
+            ("old_v", "enable_spends"),
+            ("new_v", "enable_outputs"),
+        ));
+
+        (gate_configs, algo_configs)
+    }
+
+    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+        vec![Self::short_commit_scheme(), Self::commit_scheme()]
+            .into_iter()
+            .find(|scheme| &scheme.spec.domain == domain)
+            .map(|scheme| scheme.gate_configs())
+    }
+
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        vec![Self::short_commit_scheme().spec, Self::commit_scheme().spec]
+    }
+
+    fn get_instance_points() -> Vec<String> {
+        vec!["net_cv".to_string(), "rk".to_string()]
+    }
+
+    fn get_checked_points() -> Vec<String> {
+        vec!["cm_old".to_string()]
+    }
+}
+
+impl InstanceOrder for ICTest {
+    fn get_instance_order() -> Vec<String> {
+        vec![
+            "pub_input_anchor".to_string(),
+            "enable_spends".to_string(),
+            "enable_outputs".to_string(),
+            "net_cv_x".to_string(),
+            "net_cv_y".to_string(),
+            "nf_old".to_string(),
+            "rk_x".to_string(),
+            "rk_y".to_string(),
+            "derived_cm".to_string(),
+        ]
+    }
+}
+
+mod fixture {
+    use super::*;
+
+    // the ICConfig and InstanceOrder fns of a fixture of ICTest, those not overridden are ICTest's.
+    // Not imported, so Self::get_ic_configs() etc. of a fixture is the ICConfig one
+    pub(super) trait ICTestFixture {
+        fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+            ICTest::get_ic_configs()
+        }
+
+        fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+            ICTest::get_commit_gate_configs(domain)
+        }
+
+        fn get_commit_specs() -> Vec<CommitSpec> {
+            ICTest::get_commit_specs()
+        }
+
+        fn get_reserved_gates() -> Vec<String> {
+            ICTest::get_reserved_gates()
+        }
+
+        fn get_shared_randomness() -> Vec<String> {
+            ICTest::get_shared_randomness()
+        }
+
+        fn get_selector_groups() -> Vec<Vec<String>> {
+            ICTest::get_selector_groups()
+        }
+
+        fn get_instance_points() -> Vec<String> {
+            ICTest::get_instance_points()
+        }
+
+        fn get_checked_points() -> Vec<String> {
+            ICTest::get_checked_points()
+        }
+
+        fn get_anchor_instances() -> Vec<(String, String)> {
+            ICTest::get_anchor_instances()
+        }
+
+        fn get_equal_anchors() -> Vec<(String, String)> {
+            ICTest::get_equal_anchors()
+        }
+
+        fn configure_poseidon_specs(
+            meta: &mut ConstraintSystem<pallas::Base>,
+            state: [Column<Advice>; 3],
+            partial_sbox: Column<Advice>,
+            rc_a: [Column<Fixed>; 3],
+            rc_b: [Column<Fixed>; 3],
+        ) -> Vec<(String, PoseidonConfig<pallas::Base, 3, 2>)> {
+            ICTest::configure_poseidon_specs(meta, state, partial_sbox, rc_a, rc_b)
+        }
+
+        fn get_instance_order() -> Vec<String> {
+            ICTest::get_instance_order()
+        }
+    }
+}
+
+// a fixture of ICTest overriding the fns given only, e.g.
+// ic_test_config!(MyTest<const N: usize> { fn get_checked_points() -> Vec<String> { .. } });
+macro_rules! ic_test_config {
+    ($name:ident$(<const $param:ident: $ty:ty>)? { $($fns:tt)* }) => {
+        #[derive(Copy, Clone, Debug, Default)]
+        struct $name$(<const $param: $ty>)? {}
+
+        impl$(<const $param: $ty>)? fixture::ICTestFixture for $name$(<$param>)? {
+            $($fns)*
+        }
+
+        impl$(<const $param: $ty>)? ICConfig for $name$(<$param>)? {
+            type Value = ValueNumType;
+
+            fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+                <Self as fixture::ICTestFixture>::get_ic_configs()
+            }
+
+            fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+                <Self as fixture::ICTestFixture>::get_commit_gate_configs(domain)
+            }
+
+            fn get_commit_specs() -> Vec<CommitSpec> {
+                <Self as fixture::ICTestFixture>::get_commit_specs()
+            }
+
+            fn get_reserved_gates() -> Vec<String> {
+                <Self as fixture::ICTestFixture>::get_reserved_gates()
+            }
+
+            fn get_shared_randomness() -> Vec<String> {
+                <Self as fixture::ICTestFixture>::get_shared_randomness()
+            }
+
+            fn get_selector_groups() -> Vec<Vec<String>> {
+                <Self as fixture::ICTestFixture>::get_selector_groups()
+            }
+
+            fn get_instance_points() -> Vec<String> {
+                <Self as fixture::ICTestFixture>::get_instance_points()
+            }
+
+            fn get_checked_points() -> Vec<String> {
+                <Self as fixture::ICTestFixture>::get_checked_points()
+            }
+
+            fn get_anchor_instances() -> Vec<(String, String)> {
+                <Self as fixture::ICTestFixture>::get_anchor_instances()
+            }
+
+            fn get_equal_anchors() -> Vec<(String, String)> {
+                <Self as fixture::ICTestFixture>::get_equal_anchors()
+            }
+
+            fn configure_poseidon_specs(
+                meta: &mut ConstraintSystem<pallas::Base>,
+                state: [Column<Advice>; 3],
+                partial_sbox: Column<Advice>,
+                rc_a: [Column<Fixed>; 3],
+                rc_b: [Column<Fixed>; 3],
+            ) -> Vec<(String, PoseidonConfig<pallas::Base, 3, 2>)> {
+                <Self as fixture::ICTestFixture>::configure_poseidon_specs(
+                    meta,
+                    state,
+                    partial_sbox,
+                    rc_a,
+                    rc_b,
+                )
+            }
+        }
+
+        impl$(<const $param: $ty>)? InstanceOrder for $name$(<$param>)? {
+            fn get_instance_order() -> Vec<String> {
+                <Self as fixture::ICTestFixture>::get_instance_order()
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidatingKeyRandomizer;
+impl Randomizer for ValidatingKeyRandomizer {
+    fn randomize(
+        base: &pallas::Point,
+        randomizer: &pallas::Scalar,
         domain_name: &str,
         h: &[u8; 1],
     ) -> pallas::Point {
         ValidatingKey::<ValidatingKeyRandomizer>::basepoint(domain_name, h) * randomizer + base
     }
-}
+}
+
+fn generate_circuit_instance<R: RngCore>(rng: R) -> (ICCircuit<ICTest>, Instance<ICTest>) {
+    let old_v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap(); //OverflowError if overflowed
+    let new_v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    generate_circuit_instance_with_values(rng, old_v, new_v)
+}
+
+struct SpentNote {
+    v: (ValueType<ValueNumType>, ValueType<ValueNumType>),
+    nk: pallas::Base,
+    rho_old: pallas::Base,
+    psi_old: pallas::Base,
+}
+
+impl NoteFields<ValueNumType> for SpentNote {
+    fn get_note_fields(&self) -> Vec<(String, NoteField<ValueNumType>)> {
+        vec![
+            ("v".to_string(), NoteField::Values(self.v)),
+            ("nk".to_string(), NoteField::Field(self.nk)),
+            ("rho_old".to_string(), NoteField::Field(self.rho_old)),
+            ("psi_old".to_string(), NoteField::Field(self.psi_old)),
+        ]
+    }
+}
+
+fn generate_circuit_instance_with_values<R: RngCore>(
+    mut rng: R,
+    old_v: ValueType<ValueNumType>,
+    new_v: ValueType<ValueNumType>,
+) -> (ICCircuit<ICTest>, Instance<ICTest>) {
+    let rcm = pallas::Scalar::random(&mut rng);
+    generate_circuit_instance_in(rng, old_v, new_v, &rcm)
+}
+
+// the circuit and instance of a config of the ICTest layout, derived_cm committed with rcm in the
+// domain of its commit spec in T
+fn generate_circuit_instance_in<T: Default + Clone + ICConfig<Value = ValueNumType>, R: RngCore>(
+    mut rng: R,
+    old_v: ValueType<ValueNumType>,
+    new_v: ValueType<ValueNumType>,
+    rcm: &pallas::Scalar,
+) -> (ICCircuit<T>, Instance<T>) {
+    let commit_spec = T::get_commit_specs()
+        .into_iter()
+        .find(|spec| spec.output_name == "derived_cm")
+        .unwrap();
+    let commit_domain = &commit_spec.domain;
+
+    let mut circuit = ICCircuit::<T>::default();
+    let mut instance = Instance::<T>::default();
+
+    // test merklecrh
+    let path = MerklePath::dummy(&mut rng);
+    let cm_old = Commitment::from_value_and_randomness(
+        COMMIT_DOMAIN_NAME,
+        &pallas::Point::random(&mut rng),
+        &CommitTrapdoor::random(&mut rng),
+    );
+    circuit.add_point("cm_old", cm_old.as_ref());
+    let anchor = path.root(cm_old.clone().into());
+    let cmx_old: ExtractedCommitment = cm_old.clone().into();
+    assert!(path.verify(cmx_old.value(), anchor.value()));
+    assert!(!path.verify(cmx_old.value() + pallas::Base::one(), anchor.value()));
+
+    circuit.add_merkle_data(
+        "merklecrh_cm",
+        &(MERKLE_DOMAIN_NAME, "cm_old", path.auth_path()),
+        path.position(),
+    );
+
+    let nk: Nullifier = Nullifier::dummy(&mut rng);
+    let rho_old: Nullifier = Nullifier::dummy(&mut rng);
+    let psi_old = pallas::Base::random(&mut rng);
+    let nf_old = Nullifier::derive(
+        FIXED_DOMAIN_NAME_1,
+        b"K",
+        &[nk.value().clone(), rho_old.value()],
+        psi_old,
+        cm_old.0,
+    );
+
+    circuit.load_note(&SpentNote {
+        v: (old_v, new_v),
+        nk: nk.value().clone(),
+        rho_old: rho_old.value(),
+        psi_old,
+    });
+
+    let rcv: ValueCommitTrapdoor = ValueCommitTrapdoor::random(&mut rng);
+    circuit.add_scalar("rcv", &rcv.value());
+    let net_cv: ValueCommitment =
+        ValueCommitment::derive(FIXED_DOMAIN_NAME_2, (old_v - new_v).unwrap(), rcv.clone());
+
+    let alpha = pallas::Scalar::random(&mut rng);
+    let ak = ValidatingKey::<ValidatingKeyRandomizer>::dummy(&mut rng);
+    let rk = ak.randomize(&alpha, FIXED_DOMAIN_NAME_1, b"G");
+    let rk = pallas::Point::from_bytes(&rk.clone().into())
+        .unwrap()
+        .to_affine()
+        .coordinates()
+        .unwrap();
+    circuit.add_scalar("alpha", &alpha);
+    circuit.add_nipoint("ak", &(&ak).into());
+
+    let g_d_old = NonIdentityPallasPoint::dummy(&mut rng);
+    circuit.add_nipoint("g_d_old", &g_d_old.value());
+
+    {
+        // test short commit
+        let rivk = pallas::Scalar::random(&mut rng);
+        circuit.add_scalar("rivk", &rivk);
+
+        // the widths are the ones of the input gates of the scheme
+        let mut inputs = CommitInputBuilder::new();
+        inputs.add_field("ak", ak.point.to_affine().coordinates().unwrap().x());
+        inputs.add_field("nk", &nk.value());
+        let inputs = ICTest::short_commit_scheme().commit_inputs(&inputs.inputs());
+
+        let result = compute_commit_value(true, SHORT_COMMIT_DOMAIN_NAME, b"", &rivk, &inputs);
+        match result {
+            CommitResult::X(x) => {
+                let ivk = mod_r_p(x.clone().unwrap());
+                circuit.add_constraint_point(
+                    "constraint_derived_pk_d_old",
+                    &g_d_old.mul(&ivk).value(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    // test commit
+    let derived_cm = {
+        let pk_d_new = NonIdentityPallasPoint::dummy(&mut rng);
+        circuit.add_nipoint("pk_d_new", &(&pk_d_new).value());
+
+        circuit.add_scalar("rcm", rcm);
+
+        // the inputs are in the order and widths configured in the gates above
+        let mut inputs = CommitInputBuilder::new();
+        inputs.add_nipoint("g_d_old", &g_d_old);
+        inputs.add_nipoint("pk_d_new", &pk_d_new);
+        inputs.add_value("new_v", new_v);
+        inputs.add_field("nf_old", &nf_old.value());
+        inputs.add_field("psi_old", &psi_old);
+        let inputs = ICTest::commit_scheme().commit_inputs(&inputs.inputs());
+        let derived_cm = match compute_commit_value(
+            false,
+            commit_domain,
+            &commit_spec.personalization,
+            rcm,
+            &inputs,
+        ) {
+            CommitResult::Point(Some(p)) => extract_p(&p),
+            _ => panic!("the commit result should be a point"),
+        };
+
+        // the note commitment has no personalization
+        if commit_spec.personalization.is_empty() {
+            let cm = Commitment::derive_note(
+                commit_domain,
+                &g_d_old,
+                &pk_d_new,
+                new_v,
+                nf_old.value(),
+                psi_old,
+                rcm,
+            );
+            assert_eq!(cm.value(), derived_cm);
+        }
+        derived_cm
+    };
+
+    // fill instances
+    instance.enables.insert("enable_spends".to_string(), true);
+    instance.enables.insert("enable_outputs".to_string(), true);
+    instance
+        .fields
+        .insert("pub_input_anchor".to_string(), anchor.value());
+    instance.fields.insert("nf_old".to_string(), nf_old.value());
+    instance.fields.insert("net_cv_x".to_string(), net_cv.x());
+    instance.fields.insert("net_cv_y".to_string(), net_cv.y());
+    instance.fields.insert("rk_x".to_string(), *rk.x());
+    instance.fields.insert("rk_y".to_string(), *rk.y());
+    instance.fields.insert("derived_cm".to_string(), derived_cm);
+
+    (circuit, instance)
+}
+
+fn configure_domains() {
+    let fixed_base = |alias: &str, domain: &str, num_windows, generator, zs: &[u64], us: &[_]| {
+        global::FixedBaseSetup {
+            alias: alias.to_string(),
+            domain: domain.to_string(),
+            num_windows,
+            generator,
+            zs: zs.to_vec(),
+            us: us.to_vec(),
+            base_point: None,
+        }
+    };
+
+    global::ICDomainConfig {
+        domain_names: vec![(DOMAIN_MERKLECRH.to_string(), MERKLE_DOMAIN_NAME.to_string())],
+        hash_generators: vec![
+            (MERKLE_DOMAIN_NAME.to_string(), constants::merklecrh::Q),
+            (NETCV_DOMAIN_NAME.to_string(), constants::netcv::Q),
+        ],
+        full_fixed_bases: vec![
+            // AuthG -> FIXED_DOMAIN_NAME_1 -> AUTH_G_DOMAIN_NAME, the base of the validating keys
+            global::FixedBaseSetup {
+                base_point: Some(FIXED_DOMAIN_NAME_1.to_string()),
+                ..fixed_base(
+                    "AuthG",
+                    AUTH_G_DOMAIN_NAME,
+                    NUM_WINDOWS,
+                    constants::auth_g::GENERATOR,
+                    &constants::auth_g::Z,
+                    &constants::auth_g::U,
+                )
+            },
+            // ValueCommitR -> FIXED_DOMAIN_NAME_2 -> VALUE_COMMIT_R_DOMAIN_NAME
+            fixed_base(
+                "ValueCommitR",
+                VALUE_COMMIT_R_DOMAIN_NAME,
+                NUM_WINDOWS,
+                constants::valuecommit_r::GENERATOR,
+                &constants::valuecommit_r::Z,
+                &constants::valuecommit_r::U,
+            ),
+        ],
+        // NullifierK -> FIXED_DOMAIN_NAME_1 -> NULLIFIER_K_DOMAIN_NAME
+        base_field_fixed_bases: vec![fixed_base(
+            "NullifierK",
+            NULLIFIER_K_DOMAIN_NAME,
+            NUM_WINDOWS,
+            constants::nullifier_k::GENERATOR,
+            &constants::nullifier_k::Z,
+            &constants::nullifier_k::U,
+        )],
+        // ValueCommitV -> FIXED_DOMAIN_NAME_2 -> VALUE_COMMIT_V_DOMAIN_NAME
+        short_fixed_bases: vec![fixed_base(
+            "ValueCommitV",
+            VALUE_COMMIT_V_DOMAIN_NAME,
+            NUM_WINDOWS_SHORT,
+            constants::valuecommit_v::GENERATOR,
+            &constants::valuecommit_v::Z,
+            &constants::valuecommit_v::U,
+        )],
+        commit_domains: vec![
+            global::CommitDomainSetup {
+                domain: SHORT_COMMIT_DOMAIN_NAME.to_string(),
+                generator_q: constants::short_commit::GENERATOR_Q,
+                generator_r: constants::short_commit::GENERATOR_R,
+                zs_and_us: (
+                    constants::short_commit::Z.to_vec(),
+                    constants::short_commit::U.to_vec(),
+                ),
+                zs_and_us_short: Some((
+                    constants::short_commit::Z_SHORT.to_vec(),
+                    constants::short_commit::U_SHORT.to_vec(),
+                )),
+            },
+            global::CommitDomainSetup {
+                domain: COMMIT_DOMAIN_NAME.to_string(),
+                generator_q: constants::commit::GENERATOR_Q,
+                generator_r: constants::commit::GENERATOR_R,
+                zs_and_us: (constants::commit::Z.to_vec(), constants::commit::U.to_vec()),
+                zs_and_us_short: None,
+            },
+        ],
+    }
+    .apply();
+
+    // Q, R and the zs and us of R derived from the name
+    global::config_commit_domain_auto(AUTO_COMMIT_DOMAIN_NAME, NUM_WINDOWS);
+    global::config_commit_domain_auto(CHAINED_COMMIT_DOMAIN_NAME, NUM_WINDOWS);
+}
+
+#[test]
+pub fn test_ic() {
+    let mut rng = OsRng;
+
+    configure_domains();
+
+    let (circuits, instances): (Vec<_>, Vec<_>) =
+        (0..5).map(|_i| generate_circuit_instance(&mut rng)).unzip();
+
+    for (circuit, instance) in circuits.iter().zip(instances.iter()) {
+        assert_eq!(
+            MockProver::run(K, circuit, instance.to_halo2_instance())
+                .unwrap()
+                .verify(),
+            Ok(())
+        );
+    }
+    println!("[test]==> MockProver passed");
+
+    let vk = VerifyingKey::build::<ICTest>(K);
+    println!("[test]==> build vk completed");
+    let pk = ProvingKey::build::<ICTest>(K);
+    println!("[test]==> build pk completed");
+    // a key built independently (lazily) is the same, and can be shared across threads
+    fn assert_send_sync<S: Send + Sync>(_: &S) {}
+    let lazy_pk = LazyProvingKey::<ICTest>::new(K);
+    assert_send_sync(&lazy_pk);
+    assert!(!lazy_pk.is_built());
+    assert!(pk == *lazy_pk.get());
+    assert_eq!(pk.key_fingerprint(), lazy_pk.get().key_fingerprint());
+    assert!(lazy_pk.is_built());
+    assert_eq!(pk.fingerprint(), vk.fingerprint());
+    assert_eq!(pk.k(), K);
+
+    let first_batch = 2;
+    {
+        let expected_proof_size =
+            Proof::get_expected_proof_size(&circuits[..first_batch], &instances[..first_batch], K);
+        println!("[test]==> expected_proof_size: {}", expected_proof_size);
+        let proof = Proof::create(
+            &pk,
+            &circuits[..first_batch],
+            &instances[..first_batch],
+            &mut rng,
+        )
+        .unwrap();
+        println!("[test]==> create proof completed");
+        assert!(proof.verify(&vk, &instances[..first_batch]).is_ok());
+        println!("[test]==> verify proof completed");
+        assert_eq!(proof.as_ref().len(), expected_proof_size);
+
+        let mut bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), PROOF_HEADER_SIZE + expected_proof_size);
+        let loaded = Proof::from_bytes(&bytes).unwrap();
+        assert!(loaded.is_for_circuit(&vk));
+        assert!(loaded.verify(&vk, &instances[..first_batch]).is_ok());
+        bytes[1] ^= 1;
+        assert!(!Proof::from_bytes(&bytes).unwrap().is_for_circuit(&vk));
+        let mut zeroed = bytes.clone();
+        zeroed[1..PROOF_HEADER_SIZE].fill(0);
+        assert!(matches!(
+            Proof::from_bytes(&zeroed),
+            Err(VerifyError::NoCircuitHash)
+        ));
+        bytes[0] = PROOF_FORMAT_VERSION + 1;
+        assert!(Proof::from_bytes(&bytes).is_err());
+    }
+
+    {
+        let expected_proof_size =
+            Proof::get_expected_proof_size(&circuits[first_batch..], &instances[first_batch..], K);
+        println!("[test]==> expected_proof_size: {}", expected_proof_size);
+        let proof = Proof::create(
+            &pk,
+            &circuits[first_batch..],
+            &instances[first_batch..],
+            &mut rng,
+        )
+        .unwrap();
+        println!("[test]==> create proof completed");
+        assert!(proof.verify(&vk, &instances[first_batch..]).is_ok());
+        println!("[test]==> verify proof completed");
+        assert_eq!(proof.as_ref().len(), expected_proof_size);
+    }
+
+    {
+        let proofs = Proof::create_separate(
+            &pk,
+            &circuits[..first_batch],
+            &instances[..first_batch],
+            &mut rng,
+        )
+        .unwrap();
+        println!("[test]==> create separate proofs completed");
+        for (i, proof) in proofs.iter().enumerate() {
+            let instance = &instances[i..i + 1];
+            assert!(proof.verify(&vk, instance).is_ok());
+            assert_eq!(
+                proof.as_ref().len(),
+                Proof::get_expected_proof_size(&circuits[i..i + 1], instance, K)
+            );
+        }
+        println!("[test]==> verify separate proofs completed");
+
+        let all_instances = &instances;
+        let stream = |swapped: bool| {
+            proofs.iter().enumerate().map(move |(i, proof)| {
+                let i = if swapped { first_batch - 1 - i } else { i };
+                (proof.clone(), all_instances[i..i + 1].to_vec())
+            })
+        };
+        assert!(matches!(
+            Proof::verify_stream(&vk, stream(false)),
+            Ok(n) if n == first_batch
+        ));
+        assert!(matches!(
+            Proof::verify_stream(&vk, stream(true)),
+            Err((0, VerifyError::InvalidProof(_)))
+        ));
+
+        let digest = instance_digest(&instances[..first_batch]);
+        assert_eq!(digest, instance_digest(&instances[..first_batch]));
+        assert_ne!(digest, instance_digest(&instances[..first_batch - 1]));
+    }
+
+    {
+        let seed = [7u8; 32];
+        let proof = Proof::create_deterministic(
+            &pk,
+            &circuits[..first_batch],
+            &instances[..first_batch],
+            seed,
+        )
+        .unwrap();
+        let same = Proof::create_deterministic(
+            &pk,
+            &circuits[..first_batch],
+            &instances[..first_batch],
+            seed,
+        )
+        .unwrap();
+        assert_eq!(proof.as_ref(), same.as_ref());
+        assert!(proof.verify(&vk, &instances[..first_batch]).is_ok());
+        println!("[test]==> deterministic proofs completed");
+    }
+
+    {
+        let pk = pk.with_transcript_personalization(Some(b"deployment a"));
+        let proof = Proof::create(
+            &pk,
+            &circuits[..first_batch],
+            &instances[..first_batch],
+            &mut rng,
+        )
+        .unwrap();
+        let vk = vk.with_transcript_personalization(Some(b"deployment a"));
+        assert!(proof.verify(&vk, &instances[..first_batch]).is_ok());
+
+        let vk = vk.with_transcript_personalization(Some(b"deployment b"));
+        assert!(matches!(
+            verify(&vk, &instances[..first_batch], &proof),
+            Err(VerifyError::InvalidProof(_))
+        ));
+        let vk = vk.with_transcript_personalization(None);
+        assert!(proof.verify(&vk, &instances[..first_batch]).is_err());
+        println!("[test]==> transcript personalization completed");
+    }
+}
+
+#[test]
+pub fn test_zero_value() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    #[allow(non_snake_case)]
+    let R = domain_generator(FIXED_DOMAIN_NAME_2, b"r");
+    let rcv: ValueCommitTrapdoor = ValueCommitTrapdoor::random(&mut rng);
+    let cv: ValueCommitment = ValueCommitment::derive(
+        FIXED_DOMAIN_NAME_2,
+        ValueType::<ValueNumType>::zero(),
+        rcv.clone(),
+    );
+    assert_eq!(cv.value(), R * rcv.value());
+
+    // magnitude 0 in [v] ValueCommitV
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    for (old_v, new_v) in [(ValueType::zero(), ValueType::zero()), (v, v)] {
+        let (circuit, instance) = generate_circuit_instance_with_values(&mut rng, old_v, new_v);
+        assert_eq!(
+            MockProver::run(K, &circuit, instance.to_halo2_instance())
+                .unwrap()
+                .verify(),
+            Ok(())
+        );
+    }
+    println!("[test]==> zero value passed");
+}
+
+#[test]
+pub fn test_derived_instance_order() {
+    // the order is the layout of the instance column, not only the set of names
+    assert_eq!(
+        ICTest::derived_instance_order(),
+        ICTest::get_instance_order()
+    );
+}
+
+#[test]
+pub fn test_compute_outputs() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    // the instance rows and the blinding rows fit in a k far below the rows of the regions
+    let min_k = CircuitStats::build::<ICTest>().min_k(ICTest::get_instance_order().len());
+    assert!(min_k > 1 && min_k < K);
+
+    let (circuit, instance) = generate_circuit_instance(&mut rng);
+    let outputs = circuit.compute_outputs().unwrap();
+
+    assert!(!outputs.instances.is_empty());
+    for (name, v) in &outputs.instances {
+        assert_eq!(instance.fields.get(name), Some(v), "instance [{}]", name);
+    }
+
+    let derived = circuit.derive_instance().unwrap();
+    assert!(instance.diff_against(&derived).is_empty());
+    let name = derived.fields.keys().next().unwrap().clone();
+    let mut wrong = instance.clone();
+    *wrong.fields.get_mut(&name).unwrap() += pallas::Base::one();
+    assert_eq!(wrong.diff_against(&derived), vec![name]);
+    println!("[test]==> compute outputs passed");
+}
+
+#[test]
+pub fn test_set_point() {
+    let p = pallas::Point::random(OsRng);
+    let coordinates = p.to_affine().coordinates().unwrap();
+
+    let mut instance = Instance::<ICTest>::default();
+    instance.set_point("net_cv", &p);
+    assert_eq!(instance.fields.get("net_cv_x"), Some(coordinates.x()));
+    assert_eq!(instance.fields.get("net_cv_y"), Some(coordinates.y()));
+    assert!(!instance.fields.contains_key("net_cv"));
+
+    let mut instance = Instance::<XOnlyTest>::default();
+    instance.set_point("net_cv", &p);
+    assert_eq!(instance.fields.get("net_cv"), Some(coordinates.x()));
+    assert!(!instance.fields.contains_key("net_cv_x"));
+    assert!(!instance.fields.contains_key("net_cv_y"));
+}
+
+// ICTest publishing net_cv by its x-coordinate only, rk by both
+ic_test_config!(XOnlyTest {
+    fn get_instance_order() -> Vec<String> {
+        ICTest::get_instance_order()
+            .into_iter()
+            .filter(|name| name != "net_cv_y")
+            .map(|name| {
+                if name == "net_cv_x" {
+                    "net_cv".to_string()
+                } else {
+                    name
+                }
+            })
+            .collect()
+    }
+});
+
+#[test]
+pub fn test_set_point_x_only() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, mut instance) =
+        generate_circuit_instance_in::<XOnlyTest, _>(&mut rng, v, v, &rcm);
+    let net_cv: pallas::Point = pallas::Affine::from_xy(
+        instance.fields.remove("net_cv_x").unwrap(),
+        instance.fields.remove("net_cv_y").unwrap(),
+    )
+    .unwrap()
+    .into();
+    instance.set_point("net_cv", &net_cv);
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    assert_eq!(
+        circuit.compute_outputs().unwrap().instances.get("net_cv"),
+        Some(&extract_p(&net_cv))
+    );
+
+    // -net_cv has the same x-coordinate, x-only publication does not tell them apart
+    let mut negated = instance.clone();
+    negated.set_point("net_cv", &-net_cv);
+    assert_eq!(
+        MockProver::run(K, &circuit, negated.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    instance.set_point("net_cv", &(net_cv + pallas::Point::generator()));
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+#[test]
+pub fn test_instance_field_bytes() {
+    let mut instance = Instance::<ICTest>::default();
+    let v = pallas::Base::random(OsRng);
+    assert!(instance
+        .set_field_bytes("pub_input_anchor", &v.to_repr())
+        .is_ok());
+    assert_eq!(instance.fields.get("pub_input_anchor"), Some(&v));
+
+    // the modulus itself
+    let mut p = (-pallas::Base::one()).to_repr();
+    p[0] += 1;
+    assert_eq!(
+        instance.set_field_bytes("pub_input_anchor", &p),
+        Err(InstanceError::NonCanonical("pub_input_anchor".to_string()))
+    );
+    assert_eq!(instance.fields.get("pub_input_anchor"), Some(&v));
+}
+
+#[test]
+pub fn test_constraint_expressions() {
+    configure_domains();
+
+    let mut meta = ConstraintSystem::default();
+    let config = ICCircuit::<ICTest>::configure(&mut meta);
+    let expressions = config.constraint_expressions();
+    assert!(
+        expressions.contains(
+            &"[old_v - new_v = magnitude * sign] old_v - new_v - (magnitude_v * sign_v): q * ((old_v - new_v) - (magnitude_v * sign_v))"
+                .to_string()
+        ),
+        "{:?}",
+        expressions
+    );
+}
+
+#[test]
+pub fn test_tampered_witness() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let (circuit, instance) = generate_circuit_instance(&mut rng);
+    assert!(circuit.mock_verify(K, &instance).is_ok());
+
+    // psi_old is bound to nf_old and derived_cm, the gates are satisfied by the tampered value
+    // but the computed instances are not copied from the instance column
+    let tampered = circuit.with_tampered("psi_old", pallas::Base::random(&mut rng));
+    match tampered.mock_verify(K, &instance) {
+        Err(MockVerifyError::Unsatisfied(failures)) => {
+            assert!(!failures.is_empty());
+            assert!(
+                failures
+                    .iter()
+                    .all(|failure| matches!(failure, VerifyFailure::Permutation { .. })),
+                "{:?}",
+                failures
+            );
+        }
+        result => panic!("psi_old tampered: {:?}", result),
+    }
+
+    // old_v != 0, the anchor gate requires the anchor to be the public input
+    let mut wrong = instance.clone();
+    *wrong.fields.get_mut("pub_input_anchor").unwrap() += pallas::Base::one();
+    match circuit.mock_verify(K, &wrong) {
+        Err(MockVerifyError::Unsatisfied(failures)) => {
+            let gate = "create_gate: [old_v - new_v = magnitude * sign]";
+            assert!(
+                failures.iter().any(|failure| matches!(
+                    failure,
+                    VerifyFailure::ConstraintNotSatisfied { constraint, .. }
+                        if constraint.to_string().contains(gate)
+                )),
+                "{:?}",
+                failures
+            );
+        }
+        result => panic!("pub_input_anchor tampered: {:?}", result),
+    }
+
+    // a synthesis error is returned, not a panic
+    let mut identity = circuit.clone();
+    identity.add_nipoint("g_d_old", &pallas::Point::identity());
+    assert!(matches!(
+        identity.mock_verify(K, &instance),
+        Err(MockVerifyError::Synthesis(_))
+    ));
+}
+
+#[test]
+pub fn test_identity_points() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let (circuit, instance) = generate_circuit_instance(&mut rng);
+    assert!(circuit.mock_verify(K, &instance).is_ok());
+
+    // the nipoints and the checked points are rejected as the identity
+    for (name, is_nipoint) in [("g_d_old", true), ("cm_old", false)] {
+        let mut tampered = circuit.clone();
+        if is_nipoint {
+            tampered.add_nipoint(name, &pallas::Point::identity());
+        } else {
+            tampered.add_point(name, &pallas::Point::identity());
+        }
+        assert!(
+            MockProver::run(K, &tampered, instance.to_halo2_instance()).is_err(),
+            "[{}]",
+            name
+        );
+    }
+}
+
+#[test]
+pub fn test_missing_merkle_leaf() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let (mut circuit, instance) = generate_circuit_instance(&mut rng);
+    assert!(circuit.missing_merkle_leaves().is_empty());
+
+    let path = MerklePath::dummy(&mut rng);
+    circuit.add_merkle_data(
+        "merklecrh_missing",
+        &(MERKLE_DOMAIN_NAME, "cm_missing", path.auth_path()),
+        path.position(),
+    );
+    assert_eq!(
+        circuit.missing_merkle_leaves(),
+        vec![("merklecrh_missing".to_string(), "cm_missing".to_string())]
+    );
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance()).is_err());
+}
+
+// ICTest publishing the root of merklecrh_cm and of a second path merklecrh_nf (of cm_old in
+// another tree) as anchor instances, only the first one is in the order unless IS_ORDERED
+ic_test_config!(AnchorTest<const IS_ORDERED: bool> {
+    fn get_anchor_instances() -> Vec<(String, String)> {
+        vec![
+            ("merklecrh_cm".to_string(), "cm_anchor".to_string()),
+            ("merklecrh_nf".to_string(), "nf_anchor".to_string()),
+        ]
+    }
+
+    fn get_instance_order() -> Vec<String> {
+        let mut order = ICTest::get_instance_order();
+        order.push("cm_anchor".to_string());
+        if IS_ORDERED {
+            order.push("nf_anchor".to_string());
+        }
+        order
+    }
+});
+
+#[test]
+pub fn test_anchor_instances() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<AnchorTest<true>, _>(&mut rng, v, v, &rcm);
+
+    let cm_old: ExtractedCommitment =
+        Commitment(circuit.points.as_ref().unwrap()["cm_old"].unwrap()).into();
+    let path = MerklePath::dummy(&mut rng);
+    circuit.add_merkle_data(
+        "merklecrh_nf",
+        &(MERKLE_DOMAIN_NAME, "cm_old", path.auth_path()),
+        path.position(),
+    );
+    let cm_anchor = instance.fields["pub_input_anchor"];
+    let nf_anchor = path.root(cm_old).value();
+    assert_ne!(cm_anchor, nf_anchor);
+    instance.fields.insert("cm_anchor".to_string(), cm_anchor);
+    instance.fields.insert("nf_anchor".to_string(), nf_anchor);
+
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    let outputs = circuit.compute_outputs().unwrap();
+    assert_eq!(outputs.instances.get("cm_anchor"), Some(&cm_anchor));
+    assert_eq!(outputs.instances.get("nf_anchor"), Some(&nf_anchor));
+
+    // each root is bound to its own instance
+    for (name, root) in [
+        ("cm_anchor", nf_anchor),
+        ("nf_anchor", cm_anchor + pallas::Base::one()),
+    ] {
+        let mut wrong = instance.clone();
+        wrong.fields.insert(name.to_string(), root);
+        assert!(
+            MockProver::run(K, &circuit, wrong.to_halo2_instance())
+                .unwrap()
+                .verify()
+                .is_err(),
+            "[{}]",
+            name
+        );
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "the anchor instance [nf_anchor] of merkle path [merklecrh_nf] is not in the instance order"
+)]
+pub fn test_anchor_instance_not_ordered() {
+    configure_domains();
+    ICCircuit::<AnchorTest<false>>::configure(&mut ConstraintSystem::default());
+}
+
+// ICTest with a second note cm_sibling in the tree of merklecrh_cm, its path merklecrh_sibling
+// is constrained to the same root
+ic_test_config!(EqualAnchorTest {
+    fn get_equal_anchors() -> Vec<(String, String)> {
+        vec![("merklecrh_sibling".to_string(), "merklecrh_cm".to_string())]
+    }
+});
+
+#[test]
+pub fn test_equal_anchors() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<EqualAnchorTest, _>(&mut rng, v, v, &rcm);
+
+    // cm_old and cm_sibling are the two leaves of a node, the path of cm_old is re-rooted
+    let cm_old: ExtractedCommitment =
+        Commitment(circuit.points.as_ref().unwrap()["cm_old"].unwrap()).into();
+    let cm_sibling = Commitment::from_value_and_randomness(
+        COMMIT_DOMAIN_NAME,
+        &pallas::Point::random(&mut rng),
+        &CommitTrapdoor::random(&mut rng),
+    );
+    circuit.add_point("cm_sibling", cm_sibling.as_ref());
+    let cm_sibling: ExtractedCommitment = cm_sibling.into();
+
+    let position = circuit.positions.as_ref().unwrap()["merklecrh_cm"].unwrap();
+    let mut auth_path = circuit.paths.as_ref().unwrap()["merklecrh_cm"].2.unwrap();
+    auth_path[0] = DomainMerkleHash::from_cmx(&cm_sibling);
+    let path = MerklePath::from_parts(position, auth_path);
+    auth_path[0] = DomainMerkleHash::from_cmx(&cm_old);
+    let sibling_path = MerklePath::from_parts(position ^ 1, auth_path);
+
+    let anchor = path.root(cm_old).value();
+    assert_eq!(sibling_path.root(cm_sibling).value(), anchor);
+    circuit.add_merkle_data(
+        "merklecrh_cm",
+        &(MERKLE_DOMAIN_NAME, "cm_old", path.auth_path()),
+        position,
+    );
+    instance
+        .fields
+        .insert("pub_input_anchor".to_string(), anchor);
+
+    let mut equal = circuit.clone();
+    equal.add_merkle_data(
+        "merklecrh_sibling",
+        &(MERKLE_DOMAIN_NAME, "cm_sibling", sibling_path.auth_path()),
+        position ^ 1,
+    );
+    assert_eq!(
+        MockProver::run(K, &equal, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+
+    // a path of cm_sibling in another tree
+    let other_path = MerklePath::dummy(&mut rng);
+    let mut other = circuit.clone();
+    other.add_merkle_data(
+        "merklecrh_sibling",
+        &(MERKLE_DOMAIN_NAME, "cm_sibling", other_path.auth_path()),
+        other_path.position(),
+    );
+    assert!(MockProver::run(K, &other, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+// ICTest with a gate of two cells reserved for a constraint added later, IS_UPGRADED adds it
+// (reserved_a = reserved_b) in the same layout
+ic_test_config!(ReservedGateTest<const IS_UPGRADED: bool> {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (mut gates, mut algos) = ICTest::get_ic_configs();
+        let cell = |name: &str, column| {
+            (
+                name.to_string(),
+                "".to_string(),
+                "Input".to_string(),
+                "Advice".to_string(),
+                column,
+                "Cur".to_string(),
+                FIELD_SIZE,
+            )
+        };
+
+        let algo = if IS_UPGRADED {
+            vec![(
+                "".to_string(),
+                "reserved_a - reserved_b".to_string(),
+                vec![(
+                    "".to_string(),
+                    "".to_string(),
+                    "reserved_a - reserved_b".to_string(),
+                    ("reserved_a".to_string(), "".to_string()),
+                    "sub".to_string(),
+                    Some(("reserved_b".to_string(), "".to_string())),
+                )],
+            )]
+        } else {
+            Vec::new()
+        };
+        algos.insert(gates.len(), algo);
+        gates.push((
+            "reserved".into(),
+            vec![cell("reserved_a", 0), cell("reserved_b", 1)],
+        ));
+        (gates, algos)
+    }
+
+    fn get_reserved_gates() -> Vec<String> {
+        if IS_UPGRADED {
+            Vec::new()
+        } else {
+            vec!["reserved".to_string()]
+        }
+    }
+});
+
+#[test]
+pub fn test_reserved_gate() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    // the cells not added are zero
+    let (mut reserved, instance) =
+        generate_circuit_instance_in::<ReservedGateTest<false>, _>(&mut rng, v, v, &rcm);
+    assert_eq!(
+        MockProver::run(K, &reserved, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    // any value is satisfied until the constraint is added
+    reserved.add_field("reserved_a", &pallas::Base::one());
+    assert_eq!(
+        MockProver::run(K, &reserved, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+
+    let (mut upgraded, instance) =
+        generate_circuit_instance_in::<ReservedGateTest<true>, _>(&mut rng, v, v, &rcm);
+    upgraded.add_field("reserved_a", &pallas::Base::one());
+    upgraded.add_field("reserved_b", &pallas::Base::one());
+    assert_eq!(
+        MockProver::run(K, &upgraded, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    upgraded.add_field("reserved_b", &pallas::Base::zero());
+    assert!(MockProver::run(K, &upgraded, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+#[test]
+pub fn test_reserved_gate_keys() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let vk = VerifyingKey::build::<ReservedGateTest<false>>(K);
+    let pk = ProvingKey::build::<ReservedGateTest<false>>(K);
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, instance) =
+        generate_circuit_instance_in::<ReservedGateTest<false>, _>(&mut rng, v, v, &rcm);
+    // a domain configured meanwhile, e.g. for another circuit, is not in the fingerprint
+    global::config_base_point(
+        "unreferenced_domain_name_test",
+        &pallas::Affine::generator().to_bytes(),
+    );
+    let proof = Proof::create(&pk, &[circuit], &[instance.clone()], &mut rng).unwrap();
+    assert!(proof.verify(&vk, &[instance]).is_ok());
+
+    // the upgrade keeps the columns, but not the VK of the reserved gate
+    let upgraded = VerifyingKey::build::<ReservedGateTest<true>>(K);
+    let (stats, upgraded_stats) = (vk.stats(), upgraded.stats());
+    assert_eq!(stats.advice_columns, upgraded_stats.advice_columns);
+    assert_eq!(stats.fixed_columns, upgraded_stats.fixed_columns);
+    assert_eq!(stats.instance_columns, upgraded_stats.instance_columns);
+    assert_eq!(stats.selectors, upgraded_stats.selectors);
+    assert_ne!(vk.circuit_hash(), upgraded.circuit_hash());
+    assert!(!proof.is_for_circuit(&upgraded));
+}
+
+#[test]
+pub fn test_key_params() {
+    configure_domains();
+
+    // k is read back with the params, not recomputed from them
+    let mut bytes = Vec::new();
+    KeyParams::new(K).write(&mut bytes).unwrap();
+    let params = KeyParams::read(&mut &bytes[..]).unwrap();
+    assert_eq!(params.k(), K);
+    assert_eq!(
+        VerifyingKey::build_with_params::<ICTest>(&params).circuit_hash(),
+        VerifyingKey::build::<ICTest>(K).circuit_hash()
+    );
+    assert!(KeyParams::read(&mut &bytes[..2]).is_err());
+}
+
+#[test]
+pub fn test_witness_columns() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let (circuit, _) = generate_circuit_instance(&mut rng);
+    let columns = circuit.witness_columns(K).unwrap();
+
+    assert_eq!(columns.len(), 10);
+    assert!(columns.iter().all(|column| column.len() == 1 << K));
+    assert!(columns.iter().flatten().any(|v| v.is_some()));
+}
+
+// asserts the instances derived off-circuit by the primitives (nf_old, net_cv, rk, derived_cm)
+// and the constraint points (ivk) are the ones computed in-circuit, the value balance and the
+// anchor are checked by MockProver
+fn assert_round_trip(circuit: &ICCircuit<ICTest>, instance: &Instance<ICTest>) {
+    assert_eq!(
+        MockProver::run(K, circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+
+    let outputs = circuit.compute_outputs().unwrap();
+    for name in ICTest::get_instance_order() {
+        if instance.enables.contains_key(&name) || name == "pub_input_anchor" {
+            continue;
+        }
+        assert_eq!(
+            outputs.instances.get(&name),
+            instance.fields.get(&name),
+            "instance [{}] derived off-circuit differs from the circuit",
+            name
+        );
+    }
+
+    for (name, p) in &circuit.constraint_points {
+        let p = p.unwrap().to_affine().coordinates().unwrap();
+        assert_eq!(
+            outputs.points.get(name),
+            Some(&(*p.x(), *p.y())),
+            "constraint point [{}] derived off-circuit differs from the circuit",
+            name
+        );
+    }
+}
+
+#[test]
+pub fn test_round_trip() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    for _ in 0..2 {
+        let (circuit, instance) = generate_circuit_instance(&mut rng);
+        assert_round_trip(&circuit, &instance);
+    }
+
+    let (circuit, instance) =
+        generate_circuit_instance_with_values(&mut rng, ValueType::zero(), ValueType::zero());
+    assert_round_trip(&circuit, &instance);
+    println!("[test]==> round trip passed");
+}
+
+// the saved config of load_config_in_new_process and its fingerprint in hex
+const LOADED_CONFIG_ENV: &'static str = "HALO2EX_TEST_LOADED_CONFIG";
+const LOADED_FINGERPRINT_ENV: &'static str = "HALO2EX_TEST_LOADED_FINGERPRINT";
+
+#[test]
+pub fn test_save_and_load_config() {
+    configure_domains();
+
+    let mut config = Vec::new();
+    global::save_config(&mut config).unwrap();
+    let fingerprint = global::config_fingerprint();
+
+    global::load_config(&mut &config[..]).unwrap();
+    assert_eq!(global::config_fingerprint(), fingerprint);
+    assert!(global::load_config(&mut &config[1..]).is_err());
+
+    // loaded by a process of its own, whose registry has nothing configured
+    let path = std::env::temp_dir().join(format!("halo2ex_test_config_{}", std::process::id()));
+    std::fs::write(&path, &config).unwrap();
+    let test_name = format!(
+        "{}::load_config_in_new_process",
+        module_path!().split_once("::").unwrap().1
+    );
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            test_name.as_str(),
+            "--exact",
+            "--ignored",
+            "--test-threads=1",
+        ])
+        .env(LOADED_CONFIG_ENV, &path)
+        .env(LOADED_FINGERPRINT_ENV, hex::encode(fingerprint))
+        .status()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(status.success());
+}
+
+// run by test_save_and_load_config only
+#[test]
+#[ignore]
+pub fn load_config_in_new_process() {
+    let path = match std::env::var(LOADED_CONFIG_ENV) {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+    assert!(!global::is_configured(COMMIT_DOMAIN_NAME).is_configured());
+
+    let config = std::fs::read(path).unwrap();
+    global::load_config(&mut &config[..]).unwrap();
+    assert_eq!(
+        hex::encode(global::config_fingerprint()),
+        std::env::var(LOADED_FINGERPRINT_ENV).unwrap()
+    );
+    assert!(global::is_configured(COMMIT_DOMAIN_NAME).is_configured());
+
+    // the domains are the ones of configure_domains
+    let (circuit, instance) = generate_circuit_instance(OsRng);
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+}
+
+#[test]
+pub fn test_load_tampered_config() {
+    // the records as saved by save_config, the number of records first
+    let config = |num_records: u64, records: &[(&str, Vec<u8>)]| {
+        let mut config = b"halo2ex_config".to_vec();
+        config.extend(num_records.to_le_bytes());
+        for (key, value) in records {
+            config.extend((key.len() as u64).to_le_bytes());
+            config.extend(key.as_bytes());
+            config.extend((value.len() as u64).to_le_bytes());
+            config.extend(value);
+        }
+        config
+    };
+    let domain = "tampered_domain_name_test";
+    let generator = |(x, y): TGenerator| [&x[..], &y[..]].concat();
+    let valid = (
+        format!("generator_q:{}", domain),
+        generator(crate::base::generate_r(domain)),
+    );
+
+    let mut not_on_curve = [[0u8; 32]; 2];
+    not_on_curve[0][0] = 1;
+    not_on_curve[1][0] = 1;
+    let window = [vec![0u8; 8], vec![1u8; 32 * halo2_gadgets::ecc::chip::H]].concat();
+    let tampered = [
+        (
+            format!("generator_r:{}", domain),
+            generator((not_on_curve[0], not_on_curve[1])),
+        ),
+        (format!("zs_and_us:{}", domain), window),
+        (format!("base_point:{}", domain), vec![0xff; 32]),
+        (
+            format!("fixedbasefull:{}", domain),
+            [domain.as_bytes(), &usize::MAX.to_le_bytes()].concat(),
+        ),
+    ];
+    for record in tampered {
+        let records = [
+            (valid.0.as_str(), valid.1.clone()),
+            (record.0.as_str(), record.1),
+        ];
+        assert!(global::load_config(&mut &config(2, &records)[..]).is_err());
+        // nothing is configured, not even the valid record
+        assert!(!global::is_configured(domain).is_configured());
+    }
+
+    // a length is not allocated before it is validated
+    let mut too_long = config(1, &[]);
+    too_long.extend(u64::MAX.to_le_bytes());
+    assert!(global::load_config(&mut &too_long[..]).is_err());
+    assert!(global::load_config(&mut &config(u64::MAX, &[])[..]).is_err());
+
+    let records = [(valid.0.as_str(), valid.1.clone())];
+    global::load_config(&mut &config(1, &records)[..]).unwrap();
+    assert!(global::is_configured(domain).generator_q);
+}
+
+#[test]
+pub fn test_unused_domains() {
+    configure_domains();
+
+    let config = ICCircuit::<ICTest>::configure(&mut ConstraintSystem::default());
+    let referenced = config.referenced_domains();
+    assert!(referenced.contains(COMMIT_DOMAIN_NAME));
+    assert!(referenced.contains(MERKLE_DOMAIN_NAME));
+    assert!(!global::unused_domains(&referenced).contains(&COMMIT_DOMAIN_NAME.to_string()));
+
+    // the commit domain is configured by configure_domains, it is unused by a circuit without
+    // the commit
+    let mut without_commit = referenced.clone();
+    without_commit.remove(COMMIT_DOMAIN_NAME);
+    assert!(global::unused_domains(&without_commit).contains(&COMMIT_DOMAIN_NAME.to_string()));
+}
+
+// ICTest committing the value new_v FIELD_SIZE wide instead of L_VALUE bits
+ic_test_config!(WideValueTest {
+    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+        ICTest::get_commit_gate_configs(domain).map(|mut gates| {
+            for (_, cells) in gates.iter_mut() {
+                if cells[0].0 == "new_v" {
+                    cells[0].6 = FIELD_SIZE;
+                }
+            }
+            gates
+        })
+    }
+});
+
+#[test]
+#[should_panic(expected = "it should be L_VALUE")]
+pub fn test_wide_value_input() {
+    configure_domains();
+    ICCircuit::<WideValueTest>::configure(&mut ConstraintSystem::default());
+}
+
+// ICTest committing derived_cm in a commit domain configured by config_commit_domain_auto
+ic_test_config!(AutoCommitTest {
+    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+        vec![ICTest::short_commit_scheme(), Self::commit_scheme()]
+            .into_iter()
+            .find(|scheme| &scheme.spec.domain == domain)
+            .map(|scheme| scheme.gate_configs())
+    }
+
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        vec![
+            ICTest::short_commit_scheme().spec,
+            Self::commit_scheme().spec,
+        ]
+    }
+});
+
+impl AutoCommitTest {
+    fn commit_scheme() -> CommitScheme {
+        let mut scheme = ICTest::commit_scheme();
+        scheme.spec.domain = AUTO_COMMIT_DOMAIN_NAME.to_string();
+        scheme
+    }
+}
+
+#[test]
+pub fn test_auto_commit_domain() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    // derived_cm is the commit of compute_commit_value in the auto domain
+    let (circuit, instance) =
+        generate_circuit_instance_in::<AutoCommitTest, _>(&mut rng, v, v, &rcm);
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    assert_eq!(
+        circuit
+            .compute_outputs()
+            .unwrap()
+            .instances
+            .get("derived_cm"),
+        instance.fields.get("derived_cm")
+    );
+}
+
+// ICTest committing derived_cm personalized as a "note" or a "memo" in one commit domain
+ic_test_config!(PersonalizedCommitTest<const IS_MEMO: bool> {
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        vec![
+            ICTest::short_commit_scheme().spec,
+            Self::commit_scheme().spec,
+        ]
+    }
+});
+
+impl<const IS_MEMO: bool> PersonalizedCommitTest<IS_MEMO> {
+    fn commit_scheme() -> CommitScheme {
+        let mut scheme = ICTest::commit_scheme();
+        scheme.spec.personalization = if IS_MEMO { b"memo" } else { b"note" }.to_vec();
+        scheme
+    }
+}
+
+#[test]
+pub fn test_commit_personalization() {
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut OsRng);
+    let seed = rand::random::<[u8; 32]>();
+    // the same inputs committed as a note and as a memo
+    let (note, note_instance) = generate_circuit_instance_in::<PersonalizedCommitTest<false>, _>(
+        StdRng::from_seed(seed),
+        v,
+        v,
+        &rcm,
+    );
+    let (memo, memo_instance) = generate_circuit_instance_in::<PersonalizedCommitTest<true>, _>(
+        StdRng::from_seed(seed),
+        v,
+        v,
+        &rcm,
+    );
+    assert_ne!(
+        note_instance.fields.get("derived_cm"),
+        memo_instance.fields.get("derived_cm")
+    );
+
+    assert_eq!(
+        MockProver::run(K, &note, note_instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    assert_eq!(
+        MockProver::run(K, &memo, memo_instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    // a memo is not a note
+    assert!(MockProver::run(K, &note, memo_instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+// ICTest committing derived_cm deterministically, without randomness
+ic_test_config!(DeterministicCommitTest {
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        vec![
+            ICTest::short_commit_scheme().spec,
+            Self::commit_scheme().spec,
+        ]
+    }
+});
+
+impl DeterministicCommitTest {
+    fn commit_scheme() -> CommitScheme {
+        let mut scheme = ICTest::commit_scheme();
+        scheme.spec.randomness_name = "".to_string();
+        scheme
+    }
+}
+
+#[test]
+pub fn test_deterministic_commit() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let (circuit, instance) = generate_circuit_instance_in::<DeterministicCommitTest, _>(
+        &mut rng,
+        v,
+        v,
+        &pallas::Scalar::zero(),
+    );
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+
+    // a derived_cm blinded by a nonzero r, the rcm witnessed is not committed
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, instance) =
+        generate_circuit_instance_in::<DeterministicCommitTest, _>(&mut rng, v, v, &rcm);
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+// ICTest with the commits declared by tuples, derived_cm is placed in the second column group.
+// Not of ic_test_config!, its commit specs are converted from get_commit_configs
+#[derive(Copy, Clone, Debug, Default)]
+struct ColumnGroupTest {}
+
+impl ICConfig for ColumnGroupTest {
+    type Value = ValueNumType;
+
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        ICTest::get_ic_configs()
+    }
+
+    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+        ICTest::get_commit_gate_configs(domain)
+    }
+
+    fn get_commit_configs() -> Option<Vec<CommitConfigTuple>> {
+        Some(
+            ICTest::get_commit_specs()
+                .into_iter()
+                .map(|spec| {
+                    (
+                        spec.is_short,
+                        spec.output_name,
+                        (spec.domain, spec.num_windows),
+                        spec.inputs,
+                        spec.randomness_name,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn get_commit_column_groups() -> Vec<(String, ColumnGroup)> {
+        vec![("derived_cm".to_string(), ColumnGroup::Second)]
+    }
+
+    fn get_instance_points() -> Vec<String> {
+        ICTest::get_instance_points()
+    }
+
+    fn get_checked_points() -> Vec<String> {
+        ICTest::get_checked_points()
+    }
+}
+
+impl InstanceOrder for ColumnGroupTest {
+    fn get_instance_order() -> Vec<String> {
+        ICTest::get_instance_order()
+    }
+}
+
+#[test]
+pub fn test_commit_column_groups() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    // ivk is not listed, it is in the first group
+    let column_groups = ColumnGroupTest::get_commit_specs()
+        .into_iter()
+        .map(|spec| (spec.output_name, spec.column_group))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        column_groups,
+        vec![
+            ("ivk".to_string(), ColumnGroup::First),
+            ("derived_cm".to_string(), ColumnGroup::Second),
+        ]
+    );
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, instance) =
+        generate_circuit_instance_in::<ColumnGroupTest, _>(&mut rng, v, v, &rcm);
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+}
+
+// ICTest with ivk chained into a second link, chained_ivk = ShortCommit(ivk, nk) in another
+// domain, which is compute_chained_commit_value of (ak, nk) and (nk)
+ic_test_config!(ChainedCommitTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        algos.push(vec![(
+            "constraint-commit".to_string(),
+            "check chained_ivk".to_string(),
+            vec![(
+                "".to_string(),
+                "chained_ivk".to_string(),
+                "".to_string(),
+                ("chained_ivk".to_string(), "CommitCell".to_string()),
+                "".to_string(),
+                None,
+            )],
+        )]);
+        (gates, algos)
+    }
+
+    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+        vec![
+            ICTest::short_commit_scheme(),
+            Self::chained_commit_scheme(),
+            ICTest::commit_scheme(),
+        ]
+        .into_iter()
+        .find(|scheme| &scheme.spec.domain == domain)
+        .map(|scheme| scheme.gate_configs())
+    }
+
+    // the links in the order of the chain
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        vec![
+            ICTest::short_commit_scheme().spec,
+            Self::chained_commit_scheme().spec,
+            ICTest::commit_scheme().spec,
+        ]
+    }
+
+    fn get_instance_order() -> Vec<String> {
+        let mut order = ICTest::get_instance_order();
+        order.push("chained_ivk".to_string());
+        order
+    }
+});
+
+impl ChainedCommitTest {
+    // the gates of ivk, the previous result, are the ones of ak
+    fn chained_commit_scheme() -> CommitScheme {
+        let mut scheme = ICTest::short_commit_scheme();
+        scheme.spec.output_name = "chained_ivk".to_string();
+        scheme.spec.domain = CHAINED_COMMIT_DOMAIN_NAME.to_string();
+        scheme.spec.inputs[0].0 = "ivk".to_string();
+        scheme.spec.randomness_name = "rchain".to_string();
+        scheme.input_gates[0].0 = "gate ivk".into();
+        scheme.input_gates[0].1[0].0 = "ivk".to_string();
+        scheme
+    }
+}
+
+#[test]
+pub fn test_chained_commit() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<ChainedCommitTest, _>(&mut rng, v, v, &rcm);
+    let rchain = pallas::Scalar::random(&mut rng);
+    circuit.add_scalar("rchain", &rchain);
+
+    let ak = extract_p(&circuit.nipoint("ak").unwrap());
+    let nk = circuit.field("nk").unwrap();
+    let links = [
+        (
+            SHORT_COMMIT_DOMAIN_NAME,
+            circuit.scalar("rivk").unwrap(),
+            vec![
+                ("ak".to_string(), FIELD_SIZE, Some(ak), None),
+                ("nk".to_string(), FIELD_SIZE, Some(nk), None),
+            ],
+        ),
+        (
+            CHAINED_COMMIT_DOMAIN_NAME,
+            rchain,
+            vec![("nk".to_string(), FIELD_SIZE, Some(nk), None)],
+        ),
+    ];
+    let chained_ivk = match compute_chained_commit_value(true, b"", &links) {
+        CommitResult::X(x) => x.unwrap(),
+        _ => panic!("short commit should be X"),
+    };
+    instance
+        .fields
+        .insert("chained_ivk".to_string(), chained_ivk);
+
+    // room for the second link
+    assert_eq!(
+        MockProver::run(K + 1, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+    assert_eq!(
+        circuit
+            .compute_outputs()
+            .unwrap()
+            .instances
+            .get("chained_ivk"),
+        Some(&chained_ivk)
+    );
+
+    // the second link alone, without the previous result, is not the chained commit
+    let unchained =
+        match compute_commit_value(true, CHAINED_COMMIT_DOMAIN_NAME, b"", &rchain, &links[1].2) {
+            CommitResult::X(x) => x.unwrap(),
+            _ => panic!("short commit should be X"),
+        };
+    instance.fields.insert("chained_ivk".to_string(), unchained);
+    assert!(
+        MockProver::run(K + 1, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify()
+            .is_err()
+    );
+}
+
+// ICTest with the gates of old_v - new_v and of poseidon_hash(nk, rho_old) + psi_old sharing one
+// selector, hash_old and sum of the latter are computed
+ic_test_config!(SelectorGroupTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (mut gates, algos) = ICTest::get_ic_configs();
+        for cell in gates[1].1.iter_mut() {
+            cell.5 = "Next".to_string();
+        }
+        (gates, algos)
+    }
+
+    fn get_selector_groups() -> Vec<Vec<String>> {
+        let (gates, _) = Self::get_ic_configs();
+        vec![gates
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect()]
+    }
+});
+
+#[test]
+pub fn test_selector_group() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let old_v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let new_v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, instance) =
+        generate_circuit_instance_in::<SelectorGroupTest, _>(&mut rng, old_v, new_v, &rcm);
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+}
+
+// ICTest with [ivk] g_d_old computed by two constraints, and alpha_commitment of rk rebound to
+// [rcv] ValueCommitR before alpha_commitment + ak is computed again
+ic_test_config!(MemoTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        algos.push(vec![(
+            "constraint".to_string(),
+            "rebound = [rcv] ValueCommitR + ak".to_string(),
+            vec![
+                (
+                    "".to_string(),
+                    "alpha_commitment".to_string(),
+                    "[rcv] ValueCommitR".to_string(),
+                    ("rcv".to_string(), "Scalar".to_string()),
+                    "mul".to_string(),
+                    Some(("ValueCommitR".to_string(), "FullField".to_string())),
+                ),
+                (
+                    "add".to_string(),
+                    "constraint_rebound".to_string(),
+                    "alpha_commitment + ak".to_string(),
+                    ("ak".to_string(), "NIPoint".to_string()),
+                    "".to_string(),
+                    None,
+                ),
+            ],
+        )]);
+        algos.push(vec![(
+            "constraint-commit".to_string(),
+            "derived_pk_d_old = [ivk] g_d_old, again".to_string(),
+            vec![(
+                "".to_string(),
+                "constraint_derived_pk_d_old_again".to_string(),
+                "[ivk] g_d_old".to_string(),
+                ("ivk".to_string(), "CommitCell".to_string()),
+                "mul".to_string(),
+                Some(("g_d_old".to_string(), "NIPoint".to_string())),
+            )],
+        )]);
+        (gates, algos)
+    }
+});
+
+#[test]
+pub fn test_memo() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, instance) = generate_circuit_instance_in::<MemoTest, _>(&mut rng, v, v, &rcm);
+
+    // the point of the first constraint is reused
+    let derived_pk_d_old = circuit.constraint_points["constraint_derived_pk_d_old"].unwrap();
+    circuit.add_constraint_point("constraint_derived_pk_d_old_again", &derived_pk_d_old);
+
+    #[allow(non_snake_case)]
+    let R = domain_generator(FIXED_DOMAIN_NAME_2, b"r");
+    let ak = circuit.nipoint("ak").unwrap();
+    let rebound = R * circuit.scalar("rcv").unwrap() + ak;
+    let mut with_rebound = circuit.clone();
+    with_rebound.add_constraint_point("constraint_rebound", &rebound);
+    assert_eq!(
+        MockProver::run(K, &with_rebound, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+
+    // alpha_commitment + ak of the rk constraint
+    let rk = ValidatingKey::<ValidatingKeyRandomizer>::basepoint(FIXED_DOMAIN_NAME_1, b"G")
+        * circuit.scalar("alpha").unwrap()
+        + ak;
+    circuit.add_constraint_point("constraint_rebound", &rk);
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
+// ICTest reusing alpha_commitment of the rk constraint after the commits, or (unless IS_ORDERED)
+// consuming alpha_again of a constraint after the commits in a constraint before them
+ic_test_config!(ComputedTest<const IS_ORDERED: bool> {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        if IS_ORDERED {
+            // the name is trimmed when parsed, it is after the commits as well
+            algos.push(vec![(
+                " constraint-commit ".to_string(),
+                "rk = alpha_commitment + ak, again".to_string(),
+                vec![(
+                    "".to_string(),
+                    "constraint_rk_again".to_string(),
+                    "alpha_commitment + ak".to_string(),
+                    (
+                        "alpha_commitment".to_string(),
+                        ATTRIBUTE_COMPUTED.to_string(),
+                    ),
+                    "add".to_string(),
+                    Some(("ak".to_string(), "NIPoint".to_string())),
+                )],
+            )]);
+        } else {
+            algos.push(vec![(
+                "constraint-commit".to_string(),
+                "alpha_again = [alpha] AuthG".to_string(),
+                vec![(
+                    "".to_string(),
+                    "alpha_again".to_string(),
+                    "[alpha] AuthG".to_string(),
+                    ("alpha".to_string(), "Scalar".to_string()),
+                    "mul".to_string(),
+                    Some(("AuthG".to_string(), "FullField".to_string())),
+                )],
+            )]);
+            algos.push(vec![(
+                "constraint".to_string(),
+                "rk = alpha_again + ak, before alpha_again".to_string(),
+                vec![(
+                    "".to_string(),
+                    "constraint_rk_early".to_string(),
+                    "alpha_again + ak".to_string(),
+                    ("alpha_again".to_string(), ATTRIBUTE_COMPUTED.to_string()),
+                    "add".to_string(),
+                    Some(("ak".to_string(), "NIPoint".to_string())),
+                )],
+            )]);
+        }
+        (gates, algos)
+    }
+});
+
+#[test]
+pub fn test_computed_operand() {
+    let mut rng = OsRng;
+    configure_domains();
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, instance) =
+        generate_circuit_instance_in::<ComputedTest<true>, _>(&mut rng, v, v, &rcm);
+
+    let ak = circuit.nipoint("ak").unwrap();
+    let alpha_commitment =
+        ValidatingKey::<ValidatingKeyRandomizer>::basepoint(FIXED_DOMAIN_NAME_1, b"G")
+            * circuit.scalar("alpha").unwrap();
+    let mut reused = circuit.clone();
+    reused.add_constraint_point("constraint_rk_again", &(alpha_commitment + ak));
+    assert_eq!(
+        MockProver::run(K, &reused, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
+
+    circuit.add_constraint_point("constraint_rk_again", &ak);
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+
+    // alpha_again is computed after the commits, too late for the constraint before them
+    let (mut circuit, instance) =
+        generate_circuit_instance_in::<ComputedTest<false>, _>(&mut rng, v, v, &rcm);
+    let rk = ValidatingKey::<ValidatingKeyRandomizer>::basepoint(FIXED_DOMAIN_NAME_1, b"G")
+        * circuit.scalar("alpha").unwrap()
+        + circuit.nipoint("ak").unwrap();
+    circuit.add_constraint_point("constraint_rk_early", &rk);
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance()).is_err());
+}
+
+// ICTest with the bundle commitment of the N fields cm_<i> published as bundle
+ic_test_config!(BundleTest<const N: usize> {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        let names = (0..N).map(|i| format!("cm_{}", i)).collect::<Vec<_>>();
+        let commitments = names
+            .iter()
+            .map(|name| (name.as_str(), "Cell"))
+            .collect::<Vec<_>>();
+        algos.push(vec![bundle_algo_config("bundle", &commitments)]);
+        (gates, algos)
+    }
+
+    fn get_instance_order() -> Vec<String> {
+        let mut order = ICTest::get_instance_order();
+        order.push("bundle".to_string());
+        order
+    }
+});
+
+fn assert_bundle<const N: usize>() {
+    let mut rng = OsRng;
+
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<BundleTest<N>, _>(&mut rng, v, v, &rcm);
+
+    let commitments = (0..N)
+        .map(|i| {
+            let cmx: ExtractedCommitment = Commitment::from_value_and_randomness(
+                COMMIT_DOMAIN_NAME,
+                &pallas::Point::random(&mut rng),
+                &CommitTrapdoor::random(&mut rng),
+            )
+            .into();
+            circuit.add_field(&format!("cm_{}", i), &cmx.value());
+            cmx
+        })
+        .collect::<Vec<_>>();
+    instance
+        .fields
+        .insert("bundle".to_string(), bundle_commitment(&commitments));
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(()),
+        "bundle of [{}] commitments",
+        N
+    );
+
+    // the digest of the first commitments only
+    if N > 2 {
+        instance.fields.insert(
+            "bundle".to_string(),
+            bundle_commitment(&commitments[..N - 1]),
+        );
+        assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
+
+#[test]
+pub fn test_bundle_commitment() {
+    configure_domains();
+
+    assert_bundle::<1>();
+    assert_bundle::<2>();
+    assert_bundle::<3>();
+}
+
+// ICTest with hash_sum = poseidon_hash(arith_a + arith_b, arith_c), the sum is unnamed
+ic_test_config!(ArithTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        algos.push(vec![(
+            "constraint".to_string(),
+            "hash_sum = poseidon_hash(arith_a + arith_b, arith_c)".to_string(),
+            vec![
+                (
+                    "".to_string(),
+                    "".to_string(),
+                    "arith_a + arith_b".to_string(),
+                    ("arith_a".to_string(), "Cell".to_string()),
+                    "add".to_string(),
+                    Some(("arith_b".to_string(), "Cell".to_string())),
+                ),
+                (
+                    "poseidon".to_string(),
+                    "hash_sum".to_string(),
+                    "poseidon_hash(arith_a + arith_b, arith_c)".to_string(),
+                    ("arith_c".to_string(), "Cell".to_string()),
+                    "".to_string(),
+                    None,
+                ),
+            ],
+        )]);
+        (gates, algos)
+    }
 
-impl Nullifier {
-    pub fn derive(
-        domain_name: &str,
-        inputs: &[pallas::Base; 2],
-        addend: pallas::Base,
-        cm: Commitment,
-    ) -> Self {
-        let k = pallas::Point::hash_to_curve(domain_name)(b"K");
-
-        Nullifier(extract_p(
-            &(k * mod_r_p(poseidon_hash::<2>(inputs) + addend) + cm.0),
-        ))
+    fn get_instance_order() -> Vec<String> {
+        let mut order = ICTest::get_instance_order();
+        order.push("hash_sum".to_string());
+        order
     }
-}
+});
 
-impl ValueCommitment {
-    #[allow(non_snake_case)]
-    pub fn derive<S: Signed>(domain: &str, value: ValueType<S>, rcv: ValueCommitTrapdoor) -> Self {
-        debug_assert!(ValueType::<S>::is_valid_range(value.value(), true));
+#[test]
+pub fn test_unnamed_arith() {
+    let mut rng = OsRng;
+    configure_domains();
 
-        let hasher = pallas::Point::hash_to_curve(domain);
-        let V = hasher(&*b"v");
-        let R = hasher(&*b"r");
-        let abs_value = S::try_from(value.value().abs()).expect("value must be in valid range");
+    // the arith gate is configured only if used, the VK of ICTest is unchanged
+    let config = ICCircuit::<ICTest>::configure(&mut ConstraintSystem::default());
+    assert!(config.arith_qs.is_none());
+    let config = ICCircuit::<ArithTest>::configure(&mut ConstraintSystem::default());
+    assert!(config.arith_qs.is_some());
 
-        let abs_value = abs_value.as_i128(); // never fail
-        let value = if value.value().is_negative() {
-            -pallas::Scalar::from(abs_value as u64)
-        } else {
-            pallas::Scalar::from(abs_value as u64)
-        };
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<ArithTest, _>(&mut rng, v, v, &rcm);
+    let [a, b, c] = [(); 3].map(|_| pallas::Base::random(&mut rng));
+    circuit.add_field("arith_a", &a);
+    circuit.add_field("arith_b", &b);
+    circuit.add_field("arith_c", &c);
 
-        ValueCommitment::from(V * value + R * rcv.value())
-    }
-}
+    instance
+        .fields
+        .insert("hash_sum".to_string(), poseidon_hash(&[a + b, c]));
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
 
-fn generate_circuit_instance<R: RngCore>(mut rng: R) -> (ICCircuit<ICTest>, Instance<ICTest>) {
-    let mut circuit = ICCircuit::<ICTest>::default();
-    let mut instance = Instance::<ICTest>::default();
+    instance
+        .fields
+        .insert("hash_sum".to_string(), poseidon_hash(&[a * b, c]));
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
 
-    // test value and merklecrh
-    let old_v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap(); //OverflowError if overflowed
-    let new_v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
-    circuit.add_values("v", &(old_v, new_v));
+// ICTest with arith_is_zero = is_zero(arith_a), published as an instance
+ic_test_config!(IsZeroTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        algos.push(vec![(
+            "constraint".to_string(),
+            "arith_is_zero = is_zero(arith_a)".to_string(),
+            vec![(
+                "".to_string(),
+                "arith_is_zero".to_string(),
+                "is_zero(arith_a)".to_string(),
+                ("arith_a".to_string(), "Cell".to_string()),
+                "is_zero".to_string(),
+                None,
+            )],
+        )]);
+        (gates, algos)
+    }
 
-    let path = MerklePath::dummy(&mut rng);
-    let cm_old = Commitment::dummy(&mut rng);
-    circuit.add_point("cm_old", cm_old.as_ref());
-    let anchor = path.root(cm_old.clone().into());
+    fn get_instance_order() -> Vec<String> {
+        let mut order = ICTest::get_instance_order();
+        order.push("arith_is_zero".to_string());
+        order
+    }
+});
 
-    circuit.add_merkle_data(
-        "merklecrh_cm",
-        &(MERKLE_DOMAIN_NAME, "cm_old", path.auth_path()),
-        path.position(),
-    );
+#[test]
+pub fn test_is_zero() {
+    let mut rng = OsRng;
+    configure_domains();
 
-    let nk = Nullifier::dummy(&mut rng);
-    let rho_old = Nullifier::dummy(&mut rng);
-    let psi_old = pallas::Base::random(&mut rng);
-    let nf_old = Nullifier::derive(
-        FIXED_DOMAIN_NAME_1,
-        &[nk.value().clone(), rho_old.value()],
-        psi_old,
-        cm_old,
-    );
+    // the is_zero gate is configured only if used, the VK of ICTest is unchanged
+    let config = ICCircuit::<ICTest>::configure(&mut ConstraintSystem::default());
+    assert!(config.is_zero_q.is_none());
+    let config = ICCircuit::<IsZeroTest>::configure(&mut ConstraintSystem::default());
+    assert!(config.is_zero_q.is_some());
 
-    circuit.add_field("nk", &nk.value());
-    circuit.add_field("rho_old", &rho_old.value());
-    circuit.add_field("psi_old", &psi_old);
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<IsZeroTest, _>(&mut rng, v, v, &rcm);
+    for (a, is_zero) in [
+        (pallas::Base::zero(), pallas::Base::one()),
+        (pallas::Base::random(&mut rng), pallas::Base::zero()),
+    ] {
+        circuit.add_field("arith_a", &a);
+        instance.fields.insert("arith_is_zero".to_string(), is_zero);
+        assert_eq!(
+            MockProver::run(K, &circuit, instance.to_halo2_instance())
+                .unwrap()
+                .verify(),
+            Ok(())
+        );
 
-    let rcv = ValueCommitTrapdoor::random(&mut rng);
-    circuit.add_scalar("rcv", &rcv.value());
-    let net_cv =
-        ValueCommitment::derive(FIXED_DOMAIN_NAME_2, (old_v - new_v).unwrap(), rcv.clone());
+        // the negated result
+        instance
+            .fields
+            .insert("arith_is_zero".to_string(), pallas::Base::one() - is_zero);
+        assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
 
-    let alpha = pallas::Scalar::random(&mut rng);
-    let ak = ValidatingKey::<ValidatingKeyRandomizer>::dummy(&mut rng);
-    let rk = ak.randomize(&alpha, FIXED_DOMAIN_NAME_1, b"G");
-    let rk = pallas::Point::from_bytes(&rk.clone().into())
-        .unwrap()
-        .to_affine()
-        .coordinates()
-        .unwrap();
-    circuit.add_scalar("alpha", &alpha);
-    circuit.add_nipoint("ak", &(&ak).into());
+// ICTest with a gate constraining one_of_a to one of 1, 2 and 5
+ic_test_config!(OneOfTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (mut gates, mut algos) = ICTest::get_ic_configs();
+        algos.insert(
+            gates.len(),
+            vec![(
+                "".to_string(),
+                "one_of_a in {1, 2, 5}".to_string(),
+                vec![(
+                    "".to_string(),
+                    "".to_string(),
+                    "(one_of_a - 1) * (one_of_a - 2) * (one_of_a - 5)".to_string(),
+                    ("one_of_a".to_string(), "".to_string()),
+                    "one_of".to_string(),
+                    Some(("1, 2, 5".to_string(), "Constants".to_string())),
+                )],
+            )],
+        );
+        gates.push((
+            "one_of".into(),
+            vec![(
+                "one_of_a".to_string(),
+                "".to_string(),
+                "Input".to_string(),
+                "Advice".to_string(),
+                0,
+                "Cur".to_string(),
+                FIELD_SIZE,
+            )],
+        ));
+        (gates, algos)
+    }
+});
 
-    let g_d_old = NonIdentityPallasPoint::dummy(&mut rng);
-    circuit.add_nipoint("g_d_old", &g_d_old.value());
+#[test]
+pub fn test_one_of() {
+    let mut rng = OsRng;
+    configure_domains();
 
-    {
-        // test short commit
-        let rivk = pallas::Scalar::random(&mut rng);
-        circuit.add_scalar("rivk", &rivk);
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, instance) = generate_circuit_instance_in::<OneOfTest, _>(&mut rng, v, v, &rcm);
+    for (a, is_allowed) in [(1, true), (2, true), (5, true), (0, false), (3, false)] {
+        let mut circuit = circuit.clone();
+        circuit.add_field("one_of_a", &pallas::Base::from(a));
+        assert_eq!(
+            MockProver::run(K, &circuit, instance.to_halo2_instance())
+                .unwrap()
+                .verify()
+                .is_ok(),
+            is_allowed,
+            "one_of_a = [{}]",
+            a
+        );
+    }
+}
 
-        let inputs = vec![
-            (
-                "ak".to_string(),
-                FILED_SIZE, // same as configured in the gate above for "ak"
-                Some(ak.point.to_affine().coordinates().unwrap().x().clone()),
-                None,
-            ),
-            ("nk".to_string(), FILED_SIZE, Some(nk.value().clone()), None),
-        ];
+// P128Pow5T3 with the second secure MDS, so its round constants differ from P128Pow5T3
+#[derive(Debug)]
+struct AltPoseidonSpec;
 
-        let result = compute_commit_value(true, SHORT_COMMIT_DOMAIN_NAME, &rivk, &inputs);
-        match result {
-            CommitResult::X(x) => {
-                let ivk = mod_r_p(x.clone().unwrap());
-                circuit.add_constraint_point(
-                    "constraint_derived_pk_d_old",
-                    &g_d_old.mul(&ivk).value(),
-                );
-            }
-            _ => {}
-        }
+impl poseidon::Spec<pallas::Base, 3, 2> for AltPoseidonSpec {
+    fn full_rounds() -> usize {
+        8
     }
 
-    // test commit
-    let derived_cm: ExtractedCommitment = {
-        let pk_d_new = NonIdentityPallasPoint::dummy(&mut rng);
-        circuit.add_nipoint("pk_d_new", &(&pk_d_new).value());
-
-        let g_d_old = g_d_old.to_affine().coordinates();
-        let pk_d_new = pk_d_new.to_affine().coordinates();
+    fn partial_rounds() -> usize {
+        56
+    }
 
-        let inputs = vec![
-            (
-                "g_d_old".to_string(),
-                FILED_SIZE, // same as configured in the gate above for "g_d_old"
-                Some(*g_d_old.clone().unwrap().x()),
-                Some(*g_d_old.clone().unwrap().y()),
-            ),
-            (
-                "pk_d_new".to_string(),
-                FILED_SIZE,
-                Some(*pk_d_new.clone().unwrap().x()),
-                Some(*pk_d_new.clone().unwrap().y()),
-            ),
-            ("new_v".to_string(), 64, new_v.to_base(), None),
-            ("nf_old".to_string(), FILED_SIZE, Some(nf_old.value()), None),
-            (
-                "psi_old".to_string(),
-                FILED_SIZE,
-                Some(psi_old.clone()),
-                None,
-            ),
-        ];
+    fn sbox(val: pallas::Base) -> pallas::Base {
+        val.pow_vartime(&[5])
+    }
 
-        let rcm = pallas::Scalar::random(&mut rng);
-        circuit.add_scalar("rcm", &rcm);
+    fn secure_mds() -> usize {
+        1
+    }
+}
 
-        let result = compute_commit_value(false, COMMIT_DOMAIN_NAME, &rcm, &inputs);
-        match result {
-            CommitResult::Point(Some(p)) => Commitment(p).into(),
-            _ => panic!("wrong type of commit result"),
-        }
-    };
+// ICTest with alt_hash = poseidon_hash(arith_a, arith_b) by the "alt" spec
+ic_test_config!(PoseidonSpecTest {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        let (gates, mut algos) = ICTest::get_ic_configs();
+        algos.push(vec![(
+            "constraint".to_string(),
+            "alt_hash = poseidon_hash(arith_a, arith_b) by alt".to_string(),
+            vec![(
+                "".to_string(),
+                "alt_hash".to_string(),
+                "poseidon_hash(arith_a, arith_b)".to_string(),
+                ("arith_a".to_string(), "Cell".to_string()),
+                "poseidon:alt".to_string(),
+                Some(("arith_b".to_string(), "Cell".to_string())),
+            )],
+        )]);
+        (gates, algos)
+    }
 
-    // fill instances
-    instance.enables.insert("enable_spends".to_string(), true);
-    instance.enables.insert("enable_outputs".to_string(), true);
-    instance
-        .fields
-        .insert("pub_input_anchor".to_string(), anchor.value());
-    instance.fields.insert("nf_old".to_string(), nf_old.value());
-    instance.fields.insert("net_cv_x".to_string(), net_cv.x());
-    instance.fields.insert("net_cv_y".to_string(), net_cv.y());
-    instance.fields.insert("rk_x".to_string(), *rk.x());
-    instance.fields.insert("rk_y".to_string(), *rk.y());
-    instance
-        .fields
-        .insert("derived_cm".to_string(), derived_cm.value());
+    fn configure_poseidon_specs(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        state: [Column<Advice>; 3],
+        partial_sbox: Column<Advice>,
+        rc_a: [Column<Fixed>; 3],
+        rc_b: [Column<Fixed>; 3],
+    ) -> Vec<(String, PoseidonConfig<pallas::Base, 3, 2>)> {
+        vec![(
+            "alt".to_string(),
+            PoseidonChip::configure::<AltPoseidonSpec>(meta, state, partial_sbox, rc_a, rc_b),
+        )]
+    }
 
-    (circuit, instance)
-}
+    fn get_instance_order() -> Vec<String> {
+        let mut order = ICTest::get_instance_order();
+        order.push("alt_hash".to_string());
+        order
+    }
+});
 
 #[test]
-pub fn test_ic() {
+pub fn test_poseidon_spec() {
     let mut rng = OsRng;
+    configure_domains();
 
-    // configure domains
-    {
-        global::config_domain_name(DOMAIN_MERKLECRH, MERKLE_DOMAIN_NAME);
-        global::config_generator_q(MERKLE_DOMAIN_NAME, &Some(constants::merklecrh::Q));
-        global::config_generator_q(NETCV_DOMAIN_NAME, &Some(constants::netcv::Q));
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (mut circuit, mut instance) =
+        generate_circuit_instance_in::<PoseidonSpecTest, _>(&mut rng, v, v, &rcm);
+    let [a, b] = [(); 2].map(|_| pallas::Base::random(&mut rng));
+    circuit.add_field("arith_a", &a);
+    circuit.add_field("arith_b", &b);
 
-        // NullifierK -> FIXED_DOMAIN_NAME_1 -> NULLIFIER_K_DOMAIN_NAME
-        global::config_fixedpointbasefield("NullifierK", NULLIFIER_K_DOMAIN_NAME, NUM_WINDOWS);
-        global::config_generator(
-            NULLIFIER_K_DOMAIN_NAME,
-            &Some(constants::nullifier_k::GENERATOR),
-        );
-        global::config_zs_and_us(
-            NULLIFIER_K_DOMAIN_NAME,
-            &Some((
-                constants::nullifier_k::Z.to_vec(),
-                constants::nullifier_k::U.to_vec(),
-            )),
-        );
+    let alt_hash = poseidon::Hash::<_, AltPoseidonSpec, poseidon::ConstantLength<2>, 3, 2>::init()
+        .hash([a, b]);
+    instance.fields.insert("alt_hash".to_string(), alt_hash);
+    assert_eq!(
+        MockProver::run(K, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
 
-        // AuthG -> FIXED_DOMAIN_NAME_1 -> AUTH_G_DOMAIN_NAME
-        global::config_fixedbasefull("AuthG", AUTH_G_DOMAIN_NAME, NUM_WINDOWS);
-        global::config_generator(AUTH_G_DOMAIN_NAME, &Some(constants::auth_g::GENERATOR));
-        global::config_base_point(FIXED_DOMAIN_NAME_1, &constants::auth_g::GENERATOR.0);
-        global::config_zs_and_us(
-            AUTH_G_DOMAIN_NAME,
-            &Some((constants::auth_g::Z.to_vec(), constants::auth_g::U.to_vec())),
-        );
+    // the hash of the default spec
+    instance
+        .fields
+        .insert("alt_hash".to_string(), poseidon_hash(&[a, b]));
+    assert!(MockProver::run(K, &circuit, instance.to_halo2_instance())
+        .unwrap()
+        .verify()
+        .is_err());
+}
 
-        // ValueCommitR -> FIXED_DOMAIN_NAME_2 -> VALUE_COMMIT_R_DOMAIN_NAME
-        global::config_fixedbasefull("ValueCommitR", VALUE_COMMIT_R_DOMAIN_NAME, NUM_WINDOWS);
-        global::config_generator(
-            VALUE_COMMIT_R_DOMAIN_NAME,
-            &Some(constants::valuecommit_r::GENERATOR),
-        );
-        global::config_zs_and_us(
-            VALUE_COMMIT_R_DOMAIN_NAME,
-            &Some((
-                constants::valuecommit_r::Z.to_vec(),
-                constants::valuecommit_r::U.to_vec(),
-            )),
-        );
+// ChainedCommitTest with the second link blinded by rivk as well, shared if IS_SHARED
+ic_test_config!(SharedRandomnessTest<const IS_SHARED: bool> {
+    fn get_ic_configs() -> (Vec<GateConfig>, Vec<Vec<AlgoConfig>>) {
+        ChainedCommitTest::get_ic_configs()
+    }
 
-        // ValueCommitV -> FIXED_DOMAIN_NAME_2 -> VALUE_COMMIT_V_DOMAIN_NAME
-        global::config_fixedpointshort(
-            "ValueCommitV",
-            VALUE_COMMIT_V_DOMAIN_NAME,
-            NUM_WINDOWS_SHORT,
-        );
-        global::config_generator(
-            VALUE_COMMIT_V_DOMAIN_NAME,
-            &Some(constants::valuecommit_v::GENERATOR),
-        );
-        global::config_zs_and_us_short(
-            VALUE_COMMIT_V_DOMAIN_NAME,
-            &Some((
-                constants::valuecommit_v::Z.to_vec(),
-                constants::valuecommit_v::U.to_vec(),
-            )),
-        );
+    fn get_commit_gate_configs(domain: &String) -> Option<Vec<GateConfig>> {
+        vec![
+            ICTest::short_commit_scheme(),
+            Self::chained_commit_scheme(),
+            ICTest::commit_scheme(),
+        ]
+        .into_iter()
+        .find(|scheme| &scheme.spec.domain == domain)
+        .map(|scheme| scheme.gate_configs())
+    }
 
-        // sinsemilla short commit
-        global::config_generator_q(
-            SHORT_COMMIT_DOMAIN_NAME,
-            &Some(constants::short_commit::GENERATOR_Q),
-        );
-        global::config_generator_r(
-            SHORT_COMMIT_DOMAIN_NAME,
-            &Some(constants::short_commit::GENERATOR_R),
-        );
-        global::config_zs_and_us(
-            SHORT_COMMIT_DOMAIN_NAME,
-            &Some((
-                constants::short_commit::Z.to_vec(),
-                constants::short_commit::U.to_vec(),
-            )),
-        );
-        global::config_zs_and_us_short(
-            SHORT_COMMIT_DOMAIN_NAME,
-            &Some((
-                constants::short_commit::Z_SHORT.to_vec(),
-                constants::short_commit::U_SHORT.to_vec(),
-            )),
-        );
+    fn get_commit_specs() -> Vec<CommitSpec> {
+        vec![
+            ICTest::short_commit_scheme().spec,
+            Self::chained_commit_scheme().spec,
+            ICTest::commit_scheme().spec,
+        ]
+    }
 
-        // sinsemilla commit
-        global::config_generator_q(COMMIT_DOMAIN_NAME, &Some(constants::commit::GENERATOR_Q));
-        global::config_generator_r(COMMIT_DOMAIN_NAME, &Some(constants::commit::GENERATOR_R));
-        global::config_zs_and_us(
-            COMMIT_DOMAIN_NAME,
-            &Some((constants::commit::Z.to_vec(), constants::commit::U.to_vec())),
-        );
+    fn get_shared_randomness() -> Vec<String> {
+        if IS_SHARED {
+            vec!["rivk".to_string()]
+        } else {
+            Vec::new()
+        }
     }
 
-    let (circuits, instances): (Vec<_>, Vec<_>) =
-        (0..5).map(|_i| generate_circuit_instance(&mut rng)).unzip();
+    fn get_instance_order() -> Vec<String> {
+        ChainedCommitTest::get_instance_order()
+    }
+});
 
-    for (circuit, instance) in circuits.iter().zip(instances.iter()) {
-        assert_eq!(
-            MockProver::run(K, circuit, instance.to_halo2_instance())
-                .unwrap()
-                .verify(),
-            Ok(())
-        );
+impl<const IS_SHARED: bool> SharedRandomnessTest<IS_SHARED> {
+    fn chained_commit_scheme() -> CommitScheme {
+        let mut scheme = ChainedCommitTest::chained_commit_scheme();
+        scheme.spec.randomness_name = "rivk".to_string();
+        scheme
     }
-    println!("[test]==> MockProver passed");
+}
 
-    let vk = VerifyingKey::build::<ICTest>(K);
-    println!("[test]==> build vk completed");
-    let pk = ProvingKey::build::<ICTest>(K);
-    println!("[test]==> build pk completed");
+#[test]
+#[should_panic(expected = "commits [ivk] and [chained_ivk] share the randomness [rivk]")]
+pub fn test_shared_randomness_not_declared() {
+    configure_domains();
+    ICCircuit::<SharedRandomnessTest<false>>::configure(&mut ConstraintSystem::default());
+}
 
-    let first_batch = 2;
-    {
-        let expected_proof_size =
-            Proof::get_expected_proof_size(&circuits[..first_batch], &instances[..first_batch], K);
-        println!("[test]==> expected_proof_size: {}", expected_proof_size);
-        let proof = Proof::create(
-            &pk,
-            &circuits[..first_batch],
-            &instances[..first_batch],
-            &mut rng,
-        )
-        .unwrap();
-        println!("[test]==> create proof completed");
-        assert!(proof.verify(&vk, &instances[..first_batch]).is_ok());
-        println!("[test]==> verify proof completed");
-        assert_eq!(proof.as_ref().len(), expected_proof_size);
-    }
+#[test]
+pub fn test_shared_randomness() {
+    let mut rng = OsRng;
+    configure_domains();
 
-    {
-        let expected_proof_size =
-            Proof::get_expected_proof_size(&circuits[first_batch..], &instances[first_batch..], K);
-        println!("[test]==> expected_proof_size: {}", expected_proof_size);
-        let proof = Proof::create(
-            &pk,
-            &circuits[first_batch..],
-            &instances[first_batch..],
-            &mut rng,
-        )
-        .unwrap();
-        println!("[test]==> create proof completed");
-        assert!(proof.verify(&vk, &instances[first_batch..]).is_ok());
-        println!("[test]==> verify proof completed");
-        assert_eq!(proof.as_ref().len(), expected_proof_size);
-    }
+    let v = ValueType::<ValueNumType>::from(rand::random::<u32>() as ValueNumType).unwrap();
+    let rcm = pallas::Scalar::random(&mut rng);
+    let (circuit, mut instance) =
+        generate_circuit_instance_in::<SharedRandomnessTest<true>, _>(&mut rng, v, v, &rcm);
+
+    let rivk = circuit.scalar("rivk").unwrap();
+    let ak = extract_p(&circuit.nipoint("ak").unwrap());
+    let nk = circuit.field("nk").unwrap();
+    let links = [
+        (
+            SHORT_COMMIT_DOMAIN_NAME,
+            rivk,
+            vec![
+                ("ak".to_string(), FIELD_SIZE, Some(ak), None),
+                ("nk".to_string(), FIELD_SIZE, Some(nk), None),
+            ],
+        ),
+        (
+            CHAINED_COMMIT_DOMAIN_NAME,
+            rivk,
+            vec![("nk".to_string(), FIELD_SIZE, Some(nk), None)],
+        ),
+    ];
+    let chained_ivk = match compute_chained_commit_value(true, b"", &links) {
+        CommitResult::X(x) => x.unwrap(),
+        _ => panic!("short commit should be X"),
+    };
+    instance
+        .fields
+        .insert("chained_ivk".to_string(), chained_ivk);
+    assert_eq!(
+        MockProver::run(K + 1, &circuit, instance.to_halo2_instance())
+            .unwrap()
+            .verify(),
+        Ok(())
+    );
 }
@@ -0,0 +1,38 @@
+use ff::Field;
+use pasta_curves::pallas;
+
+use crate::primitives::utils::{base_from_str, ParseBaseError};
+
+#[test]
+pub fn base_from_decimal_and_hex() {
+    let v = pallas::Base::from(12345u64);
+    assert_eq!(base_from_str("12345").unwrap(), v);
+    assert_eq!(base_from_str("0x3039").unwrap(), v);
+    assert_eq!(base_from_str("0X3039").unwrap(), v);
+    assert_eq!(base_from_str("0").unwrap(), pallas::Base::zero());
+
+    // p - 1 and p
+    let p_minus_1 = "0x40000000000000000000000000000000224698fc094cf91b992d30ed00000000";
+    assert_eq!(base_from_str(p_minus_1).unwrap(), -pallas::Base::one());
+    assert_eq!(
+        base_from_str(
+            "28948022309329048855892746252171976963363056481941560715954676764349967630336"
+        )
+        .unwrap(),
+        -pallas::Base::one()
+    );
+    let p = "0x40000000000000000000000000000000224698fc094cf91b992d30ed00000001";
+    assert!(matches!(base_from_str(p), Err(ParseBaseError::Range(_))));
+    let overflow = format!("0x1{}", "0".repeat(64));
+    assert!(matches!(
+        base_from_str(&overflow),
+        Err(ParseBaseError::Range(_))
+    ));
+
+    assert!(matches!(base_from_str(""), Err(ParseBaseError::Empty)));
+    assert!(matches!(base_from_str("0x"), Err(ParseBaseError::Empty)));
+    assert!(matches!(
+        base_from_str("12a"),
+        Err(ParseBaseError::Digit('a'))
+    ));
+}
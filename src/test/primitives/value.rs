@@ -0,0 +1,59 @@
+use ff::Field;
+use pasta_curves::pallas;
+
+use crate::primitives::value::ValueType;
+
+#[test]
+pub fn value_bytes_and_base() {
+    for v in [0i64, 1, 1 << 20, (1i64 << 32) - 1] {
+        let value = ValueType::<i64>::from(v).unwrap();
+
+        let bytes = value.to_le_bytes().unwrap();
+        assert_eq!(ValueType::<i64>::from_le_bytes(&bytes).unwrap(), value);
+
+        let base = value.to_base().unwrap();
+        assert_eq!(ValueType::<i64>::from_base(base).unwrap(), value);
+    }
+
+    // out of the valid range of i64 values
+    let bytes = (1u64 << 32).to_le_bytes();
+    assert!(ValueType::<i64>::from_le_bytes(&bytes).is_err());
+    assert!(ValueType::<i64>::from_le_bytes(&bytes[..4]).is_err());
+    assert!(ValueType::<i64>::from_base(-pallas::Base::one()).is_err());
+
+    // negative values are not encoded
+    let neg = (ValueType::<i64>::zero() - ValueType::<i64>::from(1).unwrap()).unwrap();
+    assert!(neg.to_le_bytes().is_err());
+}
+
+#[test]
+pub fn value_signed_scalar() {
+    let v = ValueType::<i64>::from(1 << 20).unwrap();
+    assert_eq!(v.to_signed_scalar(), pallas::Scalar::from(1u64 << 20));
+    assert_eq!(
+        (-v).unwrap().to_signed_scalar(),
+        -pallas::Scalar::from(1u64 << 20)
+    );
+    assert_eq!(
+        ValueType::<i64>::zero().to_signed_scalar(),
+        pallas::Scalar::zero()
+    );
+}
+
+#[test]
+pub fn value_signed_scalar_min() {
+    // the sums may reach S::MIN, whose magnitude is not an S
+    let two_pow_63 = pallas::Scalar::from(1u64 << 63);
+    assert_eq!(
+        ValueType::<i64>::from_bytes(i64::MIN.to_le_bytes()).to_signed_scalar(),
+        -two_pow_63
+    );
+    assert_eq!(
+        ValueType::<i128>::from_bytes(i128::MIN.to_le_bytes()).to_signed_scalar(),
+        -(two_pow_63.square() + two_pow_63.square())
+    );
+    assert_eq!(
+        ValueType::<i128>::from_bytes((-(1i128 << 100)).to_le_bytes()).to_signed_scalar(),
+        -pallas::Scalar::from(1u64 << 50).square()
+    );
+}
@@ -0,0 +1,81 @@
+use ff::Field;
+use group::{Curve, Group};
+use pasta_curves::{arithmetic::CurveAffine, vesta};
+use rand::rngs::OsRng;
+
+use crate::primitives::utils::{base_from_str, extract_p, mod_r_p};
+use crate::primitives::value::ValueType;
+use crate::primitives::vesta::*;
+
+#[test]
+pub fn vesta_nullifier_bytes_and_derive() {
+    let mut rng = OsRng;
+
+    let nf = Nullifier::dummy(&mut rng);
+    assert_eq!(Nullifier::from_bytes(&nf.to_bytes()).unwrap(), nf);
+
+    let inputs = [vesta::Base::random(&mut rng), vesta::Base::random(&mut rng)];
+    let addend = vesta::Base::random(&mut rng);
+    let cm = vesta::Point::random(&mut rng);
+    let nf = Nullifier::derive("z.cash:Orchard", b"K", &inputs, addend, cm);
+    assert_eq!(Nullifier::from_bytes(&nf.to_bytes()).unwrap(), nf);
+    assert_eq!(
+        Nullifier::derive("z.cash:Orchard", b"K", &inputs, addend, cm),
+        nf
+    );
+    assert_ne!(
+        Nullifier::derive("z.cash:Orchard", b"k", &inputs, addend, cm),
+        nf
+    );
+    assert_ne!(
+        Nullifier::derive("z.cash:Orchard", b"K", &[inputs[1], inputs[0]], addend, cm),
+        nf
+    );
+}
+
+#[test]
+pub fn vesta_value_commitment_bytes_and_sum() {
+    let mut rng = OsRng;
+
+    let (v1, v2) = (
+        ValueType::<i64>::from(1 << 20).unwrap(),
+        ValueType::<i64>::from(3).unwrap(),
+    );
+    let (rcv1, rcv2) = (
+        ValueCommitTrapdoor::random(&mut rng),
+        ValueCommitTrapdoor::random(&mut rng),
+    );
+    let cv1 = ValueCommitment::derive("z.cash:Orchard-cv", v1, rcv1.clone());
+    let cv2 = ValueCommitment::derive("z.cash:Orchard-cv", v2, rcv2.clone());
+
+    let loaded = ValueCommitment::from_bytes(&cv1.to_bytes()).unwrap();
+    assert_eq!(loaded.value(), cv1.value());
+    let coordinates = cv1.value().to_affine().coordinates().unwrap();
+    assert_eq!((cv1.x(), cv1.y()), (*coordinates.x(), *coordinates.y()));
+    assert_eq!(cv1.x(), extract_p(&cv1.value()));
+
+    // the commitment is homomorphic, over the vesta scalar field
+    let sum = ValueCommitment::derive(
+        "z.cash:Orchard-cv",
+        (v1 + v2).unwrap(),
+        [rcv1, rcv2].iter().sum(),
+    );
+    assert_eq!(
+        [cv1, cv2].iter().sum::<ValueCommitment>().value(),
+        sum.value()
+    );
+}
+
+#[test]
+pub fn vesta_mod_r_p() {
+    assert_eq!(
+        mod_r_p(vesta::Base::from(12345u64)),
+        vesta::Scalar::from(12345u64)
+    );
+
+    // the vesta base field is larger, q - 1 is reduced to q - 1 - p
+    assert_eq!(
+        mod_r_p(-vesta::Base::one()) + vesta::Scalar::one(),
+        base_from_str("0x47afc1f319ba3400000000").unwrap()
+    );
+}
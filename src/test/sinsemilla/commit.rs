@@ -1,6 +1,11 @@
 #![allow(dead_code)]
 
-use halo2_proofs::arithmetic::{Field, FieldExt};
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
 use pasta_curves::pallas;
 use rand::{rngs::OsRng, RngCore};
 
@@ -9,6 +14,7 @@ use crate::consts::*;
 use crate::domains::*;
 use crate::global;
 use crate::sinsemilla::circuit::*;
+use crate::sinsemilla::config::{CommitConfig, ConfigError};
 use crate::types::*;
 
 const COMMIT_DOMAIN_NAME: &str = "Sinsemilla Circuit Test";
@@ -28,8 +34,7 @@ fn add_circuit<T: ISinsemillaCircuit>(
     ));
 }
 
-#[test]
-pub fn short_commit() {
+fn config_commit_domain() {
     global::config_generator_q(COMMIT_DOMAIN_NAME, &Some(constants::GENERATOR_Q));
     global::config_generator_r(COMMIT_DOMAIN_NAME, &Some(constants::GENERATOR_R));
     global::config_zs_and_us(
@@ -40,264 +45,269 @@ pub fn short_commit() {
         COMMIT_DOMAIN_NAME,
         &Some((constants::Z_SHORT.to_vec(), constants::U_SHORT.to_vec())),
     );
+}
 
-    struct MyCircuitConfig {}
+struct ShortCommitConfig {}
 
-    impl ISinsemillaCircuit for MyCircuitConfig {
-        fn get_commit_gate_config(_domain: &String) -> Vec<GateInfo> {
-            /*
-            refer https://zcash.github.io/orchard/design/circuit/gadgets/sinsemilla/commit-ivk.html
+impl ISinsemillaCircuit for ShortCommitConfig {
+    fn get_commit_gate_config(_domain: &String) -> Vec<GateInfo> {
+        /*
+        refer https://zcash.github.io/orchard/design/circuit/gadgets/sinsemilla/commit-ivk.html
 
-            gate b:
-            | A_0 | A_1 | q |
-            -----------------
-            |  b  | b_0 | 1 |
-            | b_1 | b_2 | 0 |
+        gate b:
+        | A_0 | A_1 | q |
+        -----------------
+        |  b  | b_0 | 1 |
+        | b_1 | b_2 | 0 |
 
-            gate d:
-            | A_0 | A_1 | q |
-            -----------------
-            |  d  | d_0 | 1 |
-            |     | d_1 | 0 |
+        gate d:
+        | A_0 | A_1 | q |
+        -----------------
+        |  d  | d_0 | 1 |
+        |     | d_1 | 0 |
 
-            gate input1:
-            |    A_0    |  A_1  |    A_2  |     A_3     |  q  |
-            ---------------------------------------------------
-            |   input1  |   a   |   b_0   |     b_1     |  1  |
-            |           | z13_a | prime_a | z13_prime_a |  0  |
+        gate input1:
+        |    A_0    |  A_1  |    A_2  |     A_3     |  q  |
+        ---------------------------------------------------
+        |   input1  |   a   |   b_0   |     b_1     |  1  |
+        |           | z13_a | prime_a | z13_prime_a |  0  |
 
-            gate input2:
-            |    A_0   |  A_1  |     A_2    |       A_3      |  q  |
-            --------------------------------------------------------
-            |  input2  |  b_2  |      c     |       d_0      |  1  |
-            |    d_1   | z13_c | prime_b2_c | z14_prime_b2_c |  0  |
-            */
-            vec![
-                GateInfo {
-                    name: "gate b".to_string(),
-                    cells: vec![
-                        CellInfo {
-                            name: "b".to_string(),
-                            celltype: CellType::Piece,
-                            coltype: ColType::Advice,
-                            col: 0,
-                            row: RowType::Cur,
-                            width: 10,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "b_0".to_string(),
-                            celltype: CellType::Slice,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Cur,
-                            width: 4,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "b_1".to_string(),
-                            celltype: CellType::TopSlice,
-                            coltype: ColType::Advice,
-                            col: 0,
-                            row: RowType::Next,
-                            width: 1,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "b_2".to_string(),
-                            celltype: CellType::Slice,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Next,
-                            width: 5,
-                            attr: None,
-                        },
-                    ],
-                },
-                GateInfo {
-                    name: "gate d".to_string(),
-                    cells: vec![
-                        CellInfo {
-                            name: "d".to_string(),
-                            celltype: CellType::Piece,
-                            coltype: ColType::Advice,
-                            col: 0,
-                            row: RowType::Cur,
-                            width: 10,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "d_0".to_string(),
-                            celltype: CellType::Slice,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Cur,
-                            width: 9,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "d_1".to_string(),
-                            celltype: CellType::TopSlice,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Next,
-                            width: 1,
-                            attr: None,
-                        },
-                    ],
-                },
-                GateInfo {
-                    name: "gate input1".to_string(),
-                    cells: vec![
-                        CellInfo {
-                            name: "input1".to_string(),
-                            celltype: CellType::Input,
-                            coltype: ColType::Advice,
-                            col: 0,
-                            row: RowType::Cur,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "a".to_string(),
-                            celltype: CellType::Piece,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Cur,
-                            width: 250,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "b_0".to_string(),
-                            celltype: CellType::CanonicityCheckSlice,
-                            coltype: ColType::Advice,
-                            col: 2,
-                            row: RowType::Cur,
-                            width: 4,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "b_1".to_string(),
-                            celltype: CellType::Slice, //no TopSlice here
-                            coltype: ColType::Advice,
-                            col: 3,
-                            row: RowType::Cur,
-                            width: 1,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "z13_a".to_string(),
-                            celltype: CellType::CanonicityCheckZ13,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Next,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "prime_a".to_string(),
-                            celltype: CellType::PrimeCheck,
-                            coltype: ColType::Advice,
-                            col: 2,
-                            row: RowType::Next,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "z13_prime_a".to_string(),
-                            celltype: CellType::CanonicityCheck,
-                            coltype: ColType::Advice,
-                            col: 3,
-                            row: RowType::Next,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                    ],
-                },
-                GateInfo {
-                    name: "gate input2".to_string(),
-                    cells: vec![
-                        CellInfo {
-                            name: "input2".to_string(),
-                            celltype: CellType::Input,
-                            coltype: ColType::Advice,
-                            col: 0,
-                            row: RowType::Cur,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "b_2".to_string(),
-                            celltype: CellType::Slice,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Cur,
-                            width: 5,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "c".to_string(),
-                            celltype: CellType::Slice,
-                            coltype: ColType::Advice,
-                            col: 2,
-                            row: RowType::Cur,
-                            width: 240,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "d_0".to_string(),
-                            celltype: CellType::CanonicityCheckSlice,
-                            coltype: ColType::Advice,
-                            col: 3,
-                            row: RowType::Cur,
-                            width: 9,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "d_1".to_string(),
-                            celltype: CellType::Slice, //no TopSlice here
-                            coltype: ColType::Advice,
-                            col: 0,
-                            row: RowType::Next,
-                            width: 1,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "z13_c".to_string(),
-                            celltype: CellType::CanonicityCheckZ13,
-                            coltype: ColType::Advice,
-                            col: 1,
-                            row: RowType::Next,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "prime_b2_c".to_string(),
-                            celltype: CellType::PrimeCheck,
-                            coltype: ColType::Advice,
-                            col: 2,
-                            row: RowType::Next,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                        CellInfo {
-                            name: "z14_prime_b2_c".to_string(),
-                            celltype: CellType::CanonicityCheck,
-                            coltype: ColType::Advice,
-                            col: 3,
-                            row: RowType::Next,
-                            width: FILED_SIZE,
-                            attr: None,
-                        },
-                    ],
-                },
-            ]
-        }
+        gate input2:
+        |    A_0   |  A_1  |     A_2    |       A_3      |  q  |
+        --------------------------------------------------------
+        |  input2  |  b_2  |      c     |       d_0      |  1  |
+        |    d_1   | z13_c | prime_b2_c | z14_prime_b2_c |  0  |
+        */
+        vec![
+            GateInfo {
+                name: "gate b".into(),
+                cells: vec![
+                    CellInfo {
+                        name: "b".to_string(),
+                        celltype: CellType::Piece,
+                        coltype: ColType::Advice,
+                        col: 0,
+                        row: RowType::Cur,
+                        width: 10,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "b_0".to_string(),
+                        celltype: CellType::Slice,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Cur,
+                        width: 4,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "b_1".to_string(),
+                        celltype: CellType::TopSlice,
+                        coltype: ColType::Advice,
+                        col: 0,
+                        row: RowType::Next,
+                        width: 1,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "b_2".to_string(),
+                        celltype: CellType::Slice,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Next,
+                        width: 5,
+                        attr: None,
+                    },
+                ],
+            },
+            GateInfo {
+                name: "gate d".into(),
+                cells: vec![
+                    CellInfo {
+                        name: "d".to_string(),
+                        celltype: CellType::Piece,
+                        coltype: ColType::Advice,
+                        col: 0,
+                        row: RowType::Cur,
+                        width: 10,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "d_0".to_string(),
+                        celltype: CellType::Slice,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Cur,
+                        width: 9,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "d_1".to_string(),
+                        celltype: CellType::TopSlice,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Next,
+                        width: 1,
+                        attr: None,
+                    },
+                ],
+            },
+            GateInfo {
+                name: "gate input1".into(),
+                cells: vec![
+                    CellInfo {
+                        name: "input1".to_string(),
+                        celltype: CellType::Input,
+                        coltype: ColType::Advice,
+                        col: 0,
+                        row: RowType::Cur,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "a".to_string(),
+                        celltype: CellType::Piece,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Cur,
+                        width: 250,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "b_0".to_string(),
+                        celltype: CellType::CanonicityCheckSlice,
+                        coltype: ColType::Advice,
+                        col: 2,
+                        row: RowType::Cur,
+                        width: 4,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "b_1".to_string(),
+                        celltype: CellType::Slice, //no TopSlice here
+                        coltype: ColType::Advice,
+                        col: 3,
+                        row: RowType::Cur,
+                        width: 1,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "z13_a".to_string(),
+                        celltype: CellType::CanonicityCheckZ13,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Next,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "prime_a".to_string(),
+                        celltype: CellType::PrimeCheck,
+                        coltype: ColType::Advice,
+                        col: 2,
+                        row: RowType::Next,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "z13_prime_a".to_string(),
+                        celltype: CellType::CanonicityCheck,
+                        coltype: ColType::Advice,
+                        col: 3,
+                        row: RowType::Next,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                ],
+            },
+            GateInfo {
+                name: "gate input2".into(),
+                cells: vec![
+                    CellInfo {
+                        name: "input2".to_string(),
+                        celltype: CellType::Input,
+                        coltype: ColType::Advice,
+                        col: 0,
+                        row: RowType::Cur,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "b_2".to_string(),
+                        celltype: CellType::Slice,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Cur,
+                        width: 5,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "c".to_string(),
+                        celltype: CellType::Slice,
+                        coltype: ColType::Advice,
+                        col: 2,
+                        row: RowType::Cur,
+                        width: 240,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "d_0".to_string(),
+                        celltype: CellType::CanonicityCheckSlice,
+                        coltype: ColType::Advice,
+                        col: 3,
+                        row: RowType::Cur,
+                        width: 9,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "d_1".to_string(),
+                        celltype: CellType::Slice, //no TopSlice here
+                        coltype: ColType::Advice,
+                        col: 0,
+                        row: RowType::Next,
+                        width: 1,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "z13_c".to_string(),
+                        celltype: CellType::CanonicityCheckZ13,
+                        coltype: ColType::Advice,
+                        col: 1,
+                        row: RowType::Next,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "prime_b2_c".to_string(),
+                        celltype: CellType::PrimeCheck,
+                        coltype: ColType::Advice,
+                        col: 2,
+                        row: RowType::Next,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                    CellInfo {
+                        name: "z14_prime_b2_c".to_string(),
+                        celltype: CellType::CanonicityCheck,
+                        coltype: ColType::Advice,
+                        col: 3,
+                        row: RowType::Next,
+                        width: FIELD_SIZE,
+                        attr: None,
+                    },
+                ],
+            },
+        ]
     }
+}
 
-    let mut circuits: Vec<SinsemillaCircuit<MyCircuitConfig>> = Vec::new();
+#[test]
+pub fn short_commit() {
+    config_commit_domain();
 
-    add_circuit::<MyCircuitConfig>(
+    let mut circuits: Vec<SinsemillaCircuit<ShortCommitConfig>> = Vec::new();
+
+    add_circuit::<ShortCommitConfig>(
         true,
         &mut circuits,
         &vec![
@@ -403,6 +413,15 @@ pub fn short_commit() {
         ],
     );
 
+    // without randomness
+    let inputs = circuits[0].inputs.clone();
+    circuits.push(SinsemillaCircuit::new_deterministic(
+        true,
+        COMMIT_DOMAIN_NAME,
+        &inputs,
+        &Vec::default(),
+    ));
+
     for i in 0..circuits.len() {
         assert_eq!(circuits[i].mock_verify(11), Ok(()));
         println!(
@@ -412,6 +431,89 @@ pub fn short_commit() {
     }
 }
 
+// gate b of the short commit with b_1 queried at the previous row:
+// | A_0 | A_1 | q |
+// -----------------
+// |     | b_1 | 0 |
+// |  b  | b_0 | 1 |
+// |     | b_2 | 0 |
+struct PrevRowConfig {}
+
+impl ISinsemillaCircuit for PrevRowConfig {
+    fn get_commit_gate_config(domain: &String) -> Vec<GateInfo> {
+        let mut gates = ShortCommitConfig::get_commit_gate_config(domain);
+        for cell in gates[0].cells.iter_mut() {
+            match cell.name.as_str() {
+                "b_1" => {
+                    cell.col = 1;
+                    cell.row = RowType::Prev;
+                }
+                "b_2" => cell.row = RowType::Next,
+                _ => {}
+            }
+        }
+        gates
+    }
+}
+
+// assigns b_1 and b_2 of gate b at each other's row, the gate still queries b_1 at the previous
+// row, so the decomposition of b does not hold
+struct SwappedRowCircuit(SinsemillaCircuit<PrevRowConfig>);
+
+impl Circuit<pallas::Base> for SwappedRowCircuit {
+    type Config = <SinsemillaCircuit<PrevRowConfig> as Circuit<pallas::Base>>::Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        SinsemillaCircuit::<PrevRowConfig>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        mut config: Self::Config,
+        layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        for cell in config.0.gates[0].cells.iter_mut() {
+            match cell.name.as_str() {
+                "b_1" => cell.row = RowType::Next,
+                "b_2" => cell.row = RowType::Prev,
+                _ => {}
+            }
+        }
+        self.0.synthesize(config, layouter)
+    }
+}
+
+#[test]
+pub fn prev_row_commit() {
+    config_commit_domain();
+
+    let gates = PrevRowConfig::get_commit_gate_config(&COMMIT_DOMAIN_NAME.to_string());
+    assert_eq!(gates[0].name, "gate b");
+    assert_eq!(CommitConfig::validate_gates(&gates), Ok(()));
+
+    // input1 = 1 and input2 = 1 give b_1 = 0 and b_2 = 1
+    let inputs = vec![
+        ("input1".to_string(), Some(pallas::Base::one()), None),
+        ("input2".to_string(), Some(pallas::Base::one()), None),
+    ];
+
+    let mut circuits: Vec<SinsemillaCircuit<PrevRowConfig>> = Vec::new();
+    add_circuit(true, &mut circuits, &inputs);
+    assert_eq!(circuits[0].mock_verify(11), Ok(()));
+
+    add_circuit(true, &mut circuits, &inputs);
+    let circuit = SwappedRowCircuit(circuits.pop().unwrap());
+    assert!(MockProver::run(11, &circuit, vec![])
+        .unwrap()
+        .verify()
+        .is_err());
+}
+
 //#[test]
 pub fn commit() {
     global::config_generator_q(COMMIT_DOMAIN_NAME, &Some(constants::GENERATOR_Q));
@@ -494,7 +596,7 @@ pub fn commit() {
             */
             vec![
                 GateInfo {
-                    name: "gate b".to_string(),
+                    name: "gate b".into(),
                     cells: vec![
                         CellInfo {
                             name: "b".to_string(),
@@ -544,7 +646,7 @@ pub fn commit() {
                     ],
                 },
                 GateInfo {
-                    name: "gate d".to_string(),
+                    name: "gate d".into(),
                     cells: vec![
                         CellInfo {
                             name: "d".to_string(),
@@ -594,7 +696,7 @@ pub fn commit() {
                     ],
                 },
                 GateInfo {
-                    name: "gate e".to_string(),
+                    name: "gate e".into(),
                     cells: vec![
                         CellInfo {
                             name: "e".to_string(),
@@ -626,7 +728,7 @@ pub fn commit() {
                     ],
                 },
                 GateInfo {
-                    name: "gate g".to_string(),
+                    name: "gate g".into(),
                     cells: vec![
                         CellInfo {
                             name: "g".to_string(),
@@ -667,7 +769,7 @@ pub fn commit() {
                     ],
                 },
                 GateInfo {
-                    name: "gate h".to_string(),
+                    name: "gate h".into(),
                     cells: vec![
                         CellInfo {
                             name: "h".to_string(),
@@ -708,7 +810,7 @@ pub fn commit() {
                     ],
                 },
                 GateInfo {
-                    name: "gate input1".to_string(),
+                    name: "gate input1".into(),
                     cells: vec![
                         CellInfo {
                             name: "input1".to_string(),
@@ -716,7 +818,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 6,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -761,7 +863,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -770,7 +872,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 8,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -779,13 +881,13 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                     ],
                 },
                 GateInfo {
-                    name: "gate input2".to_string(),
+                    name: "gate input2".into(),
                     cells: vec![
                         CellInfo {
                             name: "input2".to_string(),
@@ -793,7 +895,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 6,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -838,7 +940,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -847,7 +949,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 8,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -856,13 +958,13 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                     ],
                 },
                 GateInfo {
-                    name: "gate value".to_string(),
+                    name: "gate value".into(),
                     cells: vec![
                         CellInfo {
                             name: "value".to_string(),
@@ -903,7 +1005,7 @@ pub fn commit() {
                     ],
                 },
                 GateInfo {
-                    name: "gate input3".to_string(),
+                    name: "gate input3".into(),
                     cells: vec![
                         CellInfo {
                             name: "input3".to_string(),
@@ -911,7 +1013,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 6,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -947,7 +1049,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -956,7 +1058,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 8,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -965,13 +1067,13 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                     ],
                 },
                 GateInfo {
-                    name: "gate input4".to_string(),
+                    name: "gate input4".into(),
                     cells: vec![
                         CellInfo {
                             name: "input4".to_string(),
@@ -979,7 +1081,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 6,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -1024,7 +1126,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Cur,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -1033,7 +1135,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 8,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                         CellInfo {
@@ -1042,7 +1144,7 @@ pub fn commit() {
                             coltype: ColType::Advice,
                             col: 9,
                             row: RowType::Next,
-                            width: FILED_SIZE,
+                            width: FIELD_SIZE,
                             attr: None,
                         },
                     ],
@@ -1292,4 +1394,118 @@ pub fn commit() {
         assert_eq!(circuits[i].mock_verify(11), Ok(()));
         println!("[test]==> commit[{}]({}) verify ok!", i, COMMIT_DOMAIN_NAME);
     }
+
+    let mut gates = MyCircuitConfig::get_commit_gate_config(&COMMIT_DOMAIN_NAME.to_string());
+    assert_eq!(CommitConfig::validate_gates(&gates), Ok(()));
+    let last = gates.len() - 1;
+    gates[last].cells[1].width -= 1;
+    assert_eq!(
+        CommitConfig::validate_gates(&gates),
+        Err(ConfigError::Width(
+            "input4".to_string(),
+            FIELD_SIZE,
+            FIELD_SIZE - 1
+        ))
+    );
+
+    // the full width input4 without its prime check or its canonicity check
+    let gates = MyCircuitConfig::get_commit_gate_config(&COMMIT_DOMAIN_NAME.to_string());
+    let input4 = gates
+        .iter()
+        .position(|gate| gate.name == "gate input4")
+        .unwrap();
+    for celltype in [CellType::PrimeCheck, CellType::CanonicityCheck] {
+        let mut gates = gates.clone();
+        gates[input4].cells.retain(|cell| cell.celltype != celltype);
+        assert_eq!(
+            CommitConfig::validate_gates(&gates),
+            Err(ConfigError::Canonicity("input4".to_string(), celltype))
+        );
+    }
+
+    // h_1 covers the top bit of input4, it must be a TopSlice in gate h
+    let mut top_slice = gates.clone();
+    let h = top_slice
+        .iter()
+        .position(|gate| gate.name == "gate h")
+        .unwrap();
+    top_slice[h]
+        .cells
+        .iter_mut()
+        .find(|cell| cell.name == "h_1")
+        .unwrap()
+        .celltype = CellType::Slice;
+    assert_eq!(
+        CommitConfig::validate_gates(&top_slice),
+        Err(ConfigError::TopSlice("h_1".to_string()))
+    );
+
+    // an empty gate is an error, not a panic
+    let mut empty = gates.clone();
+    empty[0].cells.clear();
+    assert_eq!(
+        CommitConfig::validate_gates(&empty),
+        Err(ConfigError::Empty(empty[0].name.to_string()))
+    );
+}
+
+#[test]
+pub fn chained_commit() {
+    let domain_name = "Sinsemilla Chained Commit Test";
+    let mut rng = OsRng;
+
+    let inputs: CommitInputs = (0..12)
+        .map(|i| {
+            (
+                format!("input{}", i),
+                FIELD_SIZE,
+                Some(pallas::Base::from(rng.next_u64())),
+                None,
+            )
+        })
+        .collect();
+    let links = split_commit_inputs(true, b"", &inputs);
+    assert_eq!(links.len(), 2);
+    assert_eq!(
+        links.iter().map(|link| link.len()).sum::<usize>(),
+        inputs.len()
+    );
+
+    let rs = [pallas::Scalar::random(rng), pallas::Scalar::random(rng)];
+    let chained = compute_chained_commit_value(
+        true,
+        b"",
+        &[
+            (domain_name, rs[0], links[0].clone()),
+            (domain_name, rs[1], links[1].clone()),
+        ],
+    );
+
+    let batch = compute_commit_values_batch(&[
+        (true, domain_name, b"", rs[0], links[0].clone()),
+        (true, domain_name, b"", rs[1], links[1].clone()),
+    ]);
+    for (i, result) in batch.into_iter().enumerate() {
+        match (
+            result,
+            compute_commit_value(true, domain_name, b"", &rs[i], &links[i]),
+        ) {
+            (CommitResult::X(x), CommitResult::X(expected)) => assert_eq!(x, expected),
+            _ => panic!("short commit should be X"),
+        }
+    }
+
+    let first = match compute_commit_value(true, domain_name, b"", &rs[0], &links[0]) {
+        CommitResult::X(x) => x,
+        _ => panic!("short commit should be X"),
+    };
+    let mut second = vec![(domain_name.to_string(), FIELD_SIZE, first, None)];
+    second.extend(links[1].iter().cloned());
+    match (
+        chained,
+        compute_commit_value(true, domain_name, b"", &rs[1], &second),
+    ) {
+        (CommitResult::X(x), CommitResult::X(expected)) => assert_eq!(x, expected),
+        _ => panic!("short commit should be X"),
+    }
 }
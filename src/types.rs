@@ -4,6 +4,9 @@
 use halo2_gadgets::ecc::chip::H;
 use halo2_proofs::dev::VerifyFailure;
 use pasta_curves::{arithmetic::CurveAffine, pallas};
+use std::borrow::Cow;
+
+use crate::consts::{ATTRIBUTE_COMMIT_CELL, SIGN_OF_CONSTRAINT, SIGN_OF_CONSTRAINT_COMMIT};
 
 pub type TGenerator = ([u8; 32], [u8; 32]);
 pub type TVecZsUs<C> = Vec<(u64, [<C as CurveAffine>::Base; H])>;
@@ -56,12 +59,12 @@ pub struct CellInfo {
 
 #[derive(Clone, Debug, Default)]
 pub struct GateInfo {
-    pub name: String,
+    pub name: Cow<'static, str>, // a literal name is used by create_gate without leaking
     pub cells: Vec<CellInfo>,
 }
 
 pub type GateConfig = (
-    String, // gate name
+    Cow<'static, str>, // gate name
     //cell_name, attr_name, cell_type, col_type, col, row, width
     Vec<(String, String, String, String, usize, String, usize)>,
 );
@@ -147,9 +150,9 @@ impl From<String> for RowType {
 pub struct AlgoItem {
     pub name: String, // result name
     pub desc: String,
-    pub operator: String,           // add, sub, mul, poseidon
+    pub operator: String, // add, sub, mul, poseidon[:<spec>], is_zero, one_of, alias
     pub operand1: (String, String), // (name, Operand type)
-    pub operand2: Option<(String, String)>,
+    pub operand2: Option<(String, String)>, // ("c_0,c_1,...", "Constants") for one_of
 }
 
 #[derive(Clone, Debug, Default)]
@@ -167,7 +170,7 @@ pub type AlgoConfig = (
         String,                   //result name
         String,                   //desc
         (String, String),         //operand1
-        String,                   //operator, should be "" if operand2 is None
+        String,                   //operator, should be "", "is_zero" or "alias" if operand2 is None
         Option<(String, String)>, //operand2
     )>,
 );
@@ -191,14 +194,113 @@ impl From<&AlgoConfig> for Algo {
             })
             .collect::<Vec<_>>();
 
+        // the type of the algo ("", constraint or constraint-commit), trimmed once for every check
         Self {
-            name: name.clone(),
+            name: name.trim().to_string(),
             desc: desc.clone(),
             items,
         }
     }
 }
 
+// the constraint algo of the bundle commitment (see commitment::bundle_commitment) named name over
+// the commitments (name, operand type), a constraint-commit one if any of them is a CommitCell.
+// The bundle is a public instance by name unless the name starts with constraint
+pub fn bundle_algo_config(name: &str, commitments: &[(&str, &str)]) -> AlgoConfig {
+    assert!(
+        commitments.len() > 0,
+        "[bundle_algo_config] [{}]: no commitment",
+        name
+    );
+
+    let operand = |(cm, celltype): &(&str, &str)| (cm.to_string(), celltype.to_string());
+    let items = if commitments.len() == 1 {
+        vec![(
+            "".to_string(),
+            name.to_string(),
+            commitments[0].0.to_string(),
+            operand(&commitments[0]),
+            "alias".to_string(),
+            None,
+        )]
+    } else {
+        // the partial digests are named <name>_<i>, only the digest of the last item is name,
+        // otherwise the cell of name would be the first partial digest
+        let digest_name = |i: usize| {
+            if i + 2 == commitments.len() {
+                name.to_string()
+            } else {
+                format!("{}_{}", name, i + 1)
+            }
+        };
+        std::iter::once((
+            "".to_string(),
+            digest_name(0),
+            format!("poseidon_hash({}, {})", commitments[0].0, commitments[1].0),
+            operand(&commitments[0]),
+            "poseidon".to_string(),
+            Some(operand(&commitments[1])),
+        ))
+        .chain(commitments[2..].iter().enumerate().map(|(i, cm)| {
+            (
+                "poseidon".to_string(),
+                digest_name(i + 1),
+                format!("poseidon_hash({}, {})", digest_name(i), cm.0),
+                operand(cm),
+                "".to_string(),
+                None,
+            )
+        }))
+        .collect()
+    };
+
+    let algo_type = if commitments
+        .iter()
+        .any(|(_, celltype)| *celltype == ATTRIBUTE_COMMIT_CELL)
+    {
+        SIGN_OF_CONSTRAINT_COMMIT
+    } else {
+        SIGN_OF_CONSTRAINT
+    };
+    (
+        algo_type.to_string(),
+        format!("{} = bundle_commitment(..)", name),
+        items,
+    )
+}
+
+// the gate algos "value = 0 or enable = 1" of the spends and of the outputs, each of them is a
+// (value name, enable name) pair, e.g. ("old_v", "enable_spends") and ("new_v", "enable_outputs")
+pub fn enable_algo_configs(spends: (&str, &str), outputs: (&str, &str)) -> Vec<AlgoConfig> {
+    [spends, outputs]
+        .iter()
+        .map(|(value, enable)| {
+            (
+                "".to_string(),
+                format!("{} = 0 or {} = 1", value, enable),
+                vec![
+                    (
+                        "".to_string(),
+                        "".to_string(),
+                        value.to_string(),
+                        (value.to_string(), "".to_string()),
+                        "".to_string(),
+                        None,
+                    ),
+                    (
+                        "mul".to_string(),
+                        "".to_string(),
+                        format!("1 - {}", enable),
+                        (enable.to_string(), "".to_string()),
+                        "boolean_neg".to_string(),
+                        None,
+                    ),
+                ],
+            )
+        })
+        .collect()
+}
+
 impl CellType {
     pub fn is_input_cell(celltype: Self) -> bool {
         celltype == Self::Input || celltype == Self::YInput
@@ -220,6 +322,144 @@ impl CellType {
     }
 }
 
+// the sinsemilla config (and its advice columns) a commit is assigned with, First by default
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ColumnGroup {
+    First,  // advices[..5]
+    Second, // advices[5..]
+}
+
+impl Default for ColumnGroup {
+    fn default() -> Self {
+        ColumnGroup::First
+    }
+}
+
+// (is_short_commit, commit_name, (domain_name, num_window), input name list(name, type), random_name)
+pub type CommitConfigTuple = (bool, String, (String, usize), Vec<(String, String)>, String);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommitSpec {
+    pub is_short: bool,
+    pub output_name: String, // the commit name, the operand of the result
+    pub domain: String,
+    pub num_windows: usize,
+    pub inputs: Vec<(String, String)>, // (name, Operand type), "" for the type of the gate cell
+    pub randomness_name: String,       // the Scalar of the random, "" for r = 0
+    pub column_group: ColumnGroup,
+    // absorbed as the first message piece, e.g. b"note" and b"memo" to tell two commits of one
+    // domain apart. At most MAX_PIECE_WIDTH bits, empty for none
+    pub personalization: Vec<u8>,
+}
+
+impl From<&CommitConfigTuple> for CommitSpec {
+    fn from(config: &CommitConfigTuple) -> Self {
+        let (is_short, output_name, (domain, num_windows), inputs, randomness_name) = config;
+        Self {
+            is_short: *is_short,
+            output_name: output_name.clone(),
+            domain: domain.clone(),
+            num_windows: *num_windows,
+            inputs: inputs.clone(),
+            randomness_name: randomness_name.clone(),
+            // the tuples have no column group, see ICConfig::get_commit_column_groups
+            column_group: ColumnGroup::default(),
+            personalization: Vec::new(),
+        }
+    }
+}
+
+// one definition of a commit: the gates of its domain (see get_commit_gate_configs) and the widths
+// of the off-circuit inputs of compute_commit_value are both derived from the input gates, so they
+// can not get out of sync
+#[derive(Clone, Debug, Default)]
+pub struct CommitScheme {
+    pub spec: CommitSpec,
+    pub piece_gates: Vec<GateConfig>, // the gates of the message pieces
+    pub input_gates: Vec<GateConfig>, // the gate of every input of spec.inputs, in the same order
+}
+
+impl CommitScheme {
+    // the gates of the domain, the pieces first
+    pub fn gate_configs(&self) -> Vec<GateConfig> {
+        self.piece_gates
+            .iter()
+            .chain(self.input_gates.iter())
+            .cloned()
+            .collect()
+    }
+
+    // (name, width, is point) of the inputs: a gate led by an Input (YInput for a point) cell
+    // commits the width of the cell, the other gates commit the slices of a value
+    pub fn input_widths(&self) -> Vec<(String, usize, bool)> {
+        assert_eq!(
+            self.spec.inputs.len(),
+            self.input_gates.len(),
+            "[CommitScheme] [{}] has [{}] inputs but [{}] input gates",
+            self.spec.output_name,
+            self.spec.inputs.len(),
+            self.input_gates.len()
+        );
+
+        self.spec
+            .inputs
+            .iter()
+            .zip(self.input_gates.iter())
+            .map(|((name, _), config)| {
+                let gate = GateInfo::from(config);
+                let cell = &gate.cells[0];
+                if CellType::is_input_cell(cell.celltype) {
+                    assert_eq!(
+                        &cell.name, name,
+                        "[CommitScheme] the input gate [{}] is not of [{}]",
+                        gate.name, name
+                    );
+                    (name.clone(), cell.width, cell.celltype == CellType::YInput)
+                } else {
+                    let width = gate
+                        .cells
+                        .iter()
+                        .filter(|cell| CellType::is_piece_or_slice_cell(cell.celltype))
+                        .map(|cell| cell.width)
+                        .sum();
+                    (name.clone(), width, false)
+                }
+            })
+            .collect()
+    }
+
+    // the inputs of compute_commit_value from the (name, x, y_lsb) inputs of SinsemillaCircuit::new
+    // (see CommitInputBuilder::inputs), in the order and widths of the input gates
+    pub fn commit_inputs(
+        &self,
+        inputs: &Vec<(String, Option<pallas::Base>, Option<pallas::Base>)>,
+    ) -> CommitInputs {
+        let widths = self.input_widths();
+        assert_eq!(
+            inputs.len(),
+            widths.len(),
+            "[CommitScheme] [{}] expects [{}] inputs, got [{}]",
+            self.spec.output_name,
+            widths.len(),
+            inputs.len()
+        );
+
+        inputs
+            .iter()
+            .zip(widths)
+            .map(|((name, x, y_lsb), (expected, width, is_point))| {
+                assert!(
+                    name == &expected && (x.is_none() || y_lsb.is_some() == is_point),
+                    "[CommitScheme] the input [{}] does not match the gate of [{}]",
+                    name,
+                    expected
+                );
+                (name.clone(), width, *x, *y_lsb)
+            })
+            .collect()
+    }
+}
+
 pub(crate) type CommitInputs = Vec<(String, usize, Option<pallas::Base>, Option<pallas::Base>)>;
 
 #[derive(Clone, Debug)]